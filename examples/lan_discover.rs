@@ -0,0 +1,118 @@
+// Copyright 2016 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement, version 1.0.  This, along with the
+// Licenses can be found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+//! Beacon on the LAN through every IPv4 interface which can broadcast and print the peers doing the
+//! same, along with the interface each of them was discovered on.
+//!
+//! Run it on two machines on the same network, or just once to discover itself:
+//!
+//! ```text
+//! cargo run --example lan_discover -- [port] [seconds]
+//! ```
+
+// For explanation of lint checks, run `rustc -W help` or see
+// https://github.com/maidsafe/QA/blob/master/Documentation/Rust%20Lint%20Checks.md
+#![forbid(bad_style, exceeding_bitshifts, mutable_transmutes, no_mangle_const_items,
+          unknown_crate_types, warnings)]
+#![deny(deprecated, drop_with_repr_extern, improper_ctypes, missing_docs,
+        non_shorthand_field_patterns, overflowing_literals, plugin_as_library,
+        private_no_mangle_fns, private_no_mangle_statics, stable_features, unconditional_recursion,
+        unknown_lints, unsafe_code, unused, unused_allocation, unused_attributes,
+        unused_comparisons, unused_features, unused_parens, while_true)]
+#![warn(trivial_casts, trivial_numeric_casts, unused_extern_crates, unused_import_braces,
+        unused_qualifications, unused_results)]
+#![allow(box_pointers, fat_ptr_transmutes, missing_copy_implementations,
+         missing_debug_implementations, variant_size_differences)]
+
+#![cfg_attr(feature="clippy", feature(plugin))]
+#![cfg_attr(feature="clippy", plugin(clippy))]
+#![cfg_attr(feature="clippy", deny(clippy, clippy_pedantic))]
+#![cfg_attr(feature="clippy", allow(use_debug))]
+
+extern crate crust;
+#[macro_use]
+extern crate unwrap;
+
+use std::collections::HashSet;
+use std::env;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, UdpSocket};
+use std::time::{Duration, Instant};
+
+use crust::get_if_addrs::{self, Interface};
+
+const MAGIC: &'static [u8] = b"crust-lan-discover";
+const DEFAULT_PORT: u16 = 5484;
+const DEFAULT_SECONDS: u64 = 5;
+
+fn main() {
+    let mut args = env::args().skip(1);
+    let port = args.next().map_or(DEFAULT_PORT, |arg| unwrap!(arg.parse()));
+    let seconds = args.next().map_or(DEFAULT_SECONDS, |arg| unwrap!(arg.parse()));
+
+    let socket = unwrap!(UdpSocket::bind(SocketAddr::new(IpAddr::V4(Ipv4Addr::new(0, 0, 0, 0)),
+                                                         port)));
+    unwrap!(socket.set_broadcast(true));
+    unwrap!(socket.set_read_timeout(Some(Duration::from_millis(200))));
+
+    let ifaces = unwrap!(get_if_addrs::get_if_addrs());
+    let targets: Vec<SocketAddr> = unwrap!(get_if_addrs::beacon_targets())
+        .into_iter()
+        .map(|(_, broadcast)| SocketAddr::new(broadcast, port))
+        .collect();
+    println!("Beaconing to {:?} for {} s", targets, seconds);
+
+    let deadline = Instant::now() + Duration::from_secs(seconds);
+    let mut next_beacon = Instant::now();
+    let mut discovered = HashSet::new();
+    let mut buf = [0; 64];
+    while Instant::now() < deadline {
+        if Instant::now() >= next_beacon {
+            for target in &targets {
+                if let Err(e) = socket.send_to(MAGIC, target) {
+                    println!("Could not beacon to {}: {}", target, e);
+                }
+            }
+            next_beacon += Duration::from_secs(1);
+        }
+
+        // Errors are mostly the read timing out, which is only there to get to beacon again.
+        let (len, peer) = match socket.recv_from(&mut buf) {
+            Ok(received) => received,
+            Err(_) => continue,
+        };
+        if &buf[..len] != MAGIC || !discovered.insert(peer) {
+            continue;
+        }
+        match arrival_interface(&ifaces, &peer.ip()) {
+            Some(interface) => {
+                println!("Discovered {} on {} ({})",
+                         peer,
+                         interface.name,
+                         interface.addr.ip())
+            }
+            None => println!("Discovered {} on an unknown interface", peer),
+        }
+    }
+}
+
+// The interface a beacon from `peer` came in through: the one with that very address if it is
+// our own beacon, otherwise the one on the same network.
+fn arrival_interface<'a>(ifaces: &'a [Interface], peer: &IpAddr) -> Option<&'a Interface> {
+    ifaces.iter()
+        .find(|interface| interface.addr.ip() == *peer)
+        .or_else(|| ifaces.iter().find(|interface| interface.addr.contains(peer)))
+}
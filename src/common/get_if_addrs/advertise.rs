@@ -0,0 +1,160 @@
+// The wire format peers tell each other their interface addresses in. A message is the length of
+// the rest of it as a little-endian `u32`, followed by the addresses one after the other: a family
+// byte (4 or 6), the IP and the netmask, and a byte telling whether a broadcast address follows.
+
+use std::io::{self, Cursor, ErrorKind, Read};
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use common::get_if_addrs::{IfAddr, Ifv4Addr, Ifv6Addr, Routability};
+
+/// Encode the addresses of `addrs` worth advertising to peers, leaving out loopback and link-local
+/// ones, into a message for `decode_advertised()`.
+pub fn encode_advertised(addrs: &[IfAddr]) -> Vec<u8> {
+    let mut body = Vec::new();
+    for addr in addrs.iter().filter(|addr| addr.routability() > Routability::LinkLocal) {
+        match *addr {
+            IfAddr::V4(ref ifv4_addr) => {
+                body.push(4);
+                body.extend_from_slice(&ifv4_addr.ip.octets());
+                body.extend_from_slice(&ifv4_addr.netmask.octets());
+                match ifv4_addr.broadcast {
+                    Some(broadcast) => {
+                        body.push(1);
+                        body.extend_from_slice(&broadcast.octets());
+                    }
+                    None => body.push(0),
+                }
+            }
+            IfAddr::V6(ref ifv6_addr) => {
+                body.push(6);
+                body.extend_from_slice(&ifv6_addr.ip.octets());
+                body.extend_from_slice(&ifv6_addr.netmask.octets());
+                match ifv6_addr.broadcast {
+                    Some(broadcast) => {
+                        body.push(1);
+                        body.extend_from_slice(&broadcast.octets());
+                    }
+                    None => body.push(0),
+                }
+            }
+        }
+    }
+
+    let mut msg = Vec::with_capacity(4 + body.len());
+    unwrap!(msg.write_u32::<LittleEndian>(body.len() as u32));
+    msg.extend_from_slice(&body);
+    msg
+}
+
+/// Decode a message from `encode_advertised()`. Fails with `io::ErrorKind::InvalidData` if `buf`
+/// isn't exactly one well-formed message.
+pub fn decode_advertised(buf: &[u8]) -> io::Result<Vec<IfAddr>> {
+    let mut cursor = Cursor::new(buf);
+    let len = try!(cursor.read_u32::<LittleEndian>().map_err(|_| invalid("Truncated length")));
+    if buf.len() - 4 != len as usize {
+        return Err(invalid("Length doesn't match the message"));
+    }
+
+    let mut addrs = Vec::new();
+    while (cursor.position() as usize) < buf.len() {
+        let addr = match try!(read_u8(&mut cursor)) {
+            4 => {
+                let ip = Ipv4Addr::from(try!(read_array::<[u8; 4]>(&mut cursor)));
+                let netmask = Ipv4Addr::from(try!(read_array::<[u8; 4]>(&mut cursor)));
+                let broadcast = match try!(read_u8(&mut cursor)) {
+                    0 => None,
+                    1 => Some(Ipv4Addr::from(try!(read_array::<[u8; 4]>(&mut cursor)))),
+                    _ => return Err(invalid("Invalid broadcast marker")),
+                };
+                IfAddr::V4(Ifv4Addr {
+                    ip: ip,
+                    netmask: netmask,
+                    broadcast: broadcast,
+                })
+            }
+            6 => {
+                let ip = Ipv6Addr::from(try!(read_array::<[u8; 16]>(&mut cursor)));
+                let netmask = Ipv6Addr::from(try!(read_array::<[u8; 16]>(&mut cursor)));
+                let broadcast = match try!(read_u8(&mut cursor)) {
+                    0 => None,
+                    1 => Some(Ipv6Addr::from(try!(read_array::<[u8; 16]>(&mut cursor)))),
+                    _ => return Err(invalid("Invalid broadcast marker")),
+                };
+                IfAddr::V6(Ifv6Addr {
+                    ip: ip,
+                    netmask: netmask,
+                    broadcast: broadcast,
+                })
+            }
+            _ => return Err(invalid("Unknown address family")),
+        };
+        addrs.push(addr);
+    }
+    Ok(addrs)
+}
+
+fn read_u8(cursor: &mut Cursor<&[u8]>) -> io::Result<u8> {
+    cursor.read_u8().map_err(|_| invalid("Truncated address"))
+}
+
+fn read_array<A: AsMut<[u8]> + Default>(cursor: &mut Cursor<&[u8]>) -> io::Result<A> {
+    let mut array = A::default();
+    try!(cursor.read_exact(array.as_mut()).map_err(|_| invalid("Truncated address")));
+    Ok(array)
+}
+
+fn invalid(what: &str) -> io::Error {
+    io::Error::new(ErrorKind::InvalidData, what)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::net::IpAddr;
+    use std::str::FromStr;
+
+    use common::get_if_addrs::InterfaceBuilder;
+
+    fn addr(ip: &str, netmask: &str, broadcast: Option<&str>) -> IfAddr {
+        let mut builder = InterfaceBuilder::new()
+            .addr(unwrap!(IpAddr::from_str(ip)))
+            .netmask(unwrap!(IpAddr::from_str(netmask)));
+        if let Some(broadcast) = broadcast {
+            builder = builder.broadcast(unwrap!(IpAddr::from_str(broadcast)));
+        }
+        builder.build().addr
+    }
+
+    #[test]
+    fn round_trip() {
+        let advertised = vec![addr("192.168.1.5", "255.255.255.0", Some("192.168.1.255")),
+                              addr("100.64.0.7", "255.192.0.0", None),
+                              addr("2001:db8::5", "ffff:ffff:ffff:ffff::", None)];
+        let mut addrs = advertised.clone();
+        addrs.push(addr("127.0.0.1", "255.0.0.0", None));
+        addrs.push(addr("fe80::5", "ffff:ffff:ffff:ffff::", None));
+
+        let msg = encode_advertised(&addrs);
+        assert_eq!(msg.len(), 4 + 14 + 10 + 34);
+        assert_eq!(unwrap!(decode_advertised(&msg)), advertised);
+        assert!(unwrap!(decode_advertised(&encode_advertised(&[]))).is_empty());
+    }
+
+    #[test]
+    fn malformed_messages() {
+        let msg = encode_advertised(&[addr("192.168.1.5", "255.255.255.0", None)]);
+        let mut trailing = msg.clone();
+        trailing.push(0);
+        let mut unknown_family = msg.clone();
+        unknown_family[4] = 5;
+
+        for buf in &[&msg[..2], &msg[..msg.len() - 1], &trailing[..], &unknown_family[..]] {
+            match decode_advertised(buf) {
+                Err(ref e) if e.kind() == ErrorKind::InvalidData => (),
+                result => panic!("Unexpected {:?}", result),
+            }
+        }
+    }
+}
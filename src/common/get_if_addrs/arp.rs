@@ -0,0 +1,258 @@
+// Detection of IPv4 address conflicts with ARP probes (RFC 5227), sent and received through a
+// packet socket bound to the interface. Opening such a socket takes `CAP_NET_RAW`. Packets are
+// built and parsed as plain byte buffers so the parsing can be tested without it.
+
+use std::io;
+use std::net::Ipv4Addr;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use common::get_if_addrs::Interface;
+use common::get_if_addrs::sysfs;
+use libc::{self, POLLIN, c_int, c_void, pollfd, sockaddr, socklen_t};
+
+const AF_PACKET: c_int = 17;
+const ETH_P_ARP: u16 = 0x0806;
+const ETH_P_IP: u16 = 0x0800;
+const ARPHRD_ETHER: u16 = 1;
+const ARPOP_REQUEST: u16 = 1;
+
+// Sizes of an Ethernet/IPv4 ARP packet and of `struct sockaddr_ll`.
+const ARP_LEN: usize = 28;
+const SOCKADDR_LL_LEN: usize = 20;
+
+const BROADCAST_MAC: [u8; 6] = [0xff; 6];
+
+/// Check whether another host on the link of `interface` claims `addr`, by sending an ARP probe
+/// for it through `interface` and listening for up to `timeout` for a host either using the
+/// address or probing for it too.
+///
+/// This needs `CAP_NET_RAW`: without it, it fails with `io::ErrorKind::PermissionDenied` rather
+/// than reporting no conflict.
+pub fn addr_conflict(addr: Ipv4Addr,
+                     interface: &Interface,
+                     timeout: Duration)
+                     -> io::Result<bool> {
+    // Aliases such as `eth0:0` are addresses of the interface before the colon.
+    let name = interface.name.split(':').next().unwrap_or("");
+    let mac = match sysfs::mac_address(Path::new(sysfs::SYS_CLASS_NET), name) {
+        Some(mac) => mac,
+        None => {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput,
+                                      format!("{} has no Ethernet address to probe from", name)))
+        }
+    };
+    let index = match interface.index {
+        0 => try!(name_to_index(name)),
+        index => index,
+    };
+
+    let socket = try!(PacketSocket::new(index));
+    try!(socket.send_to(&probe(addr, &mac), &BROADCAST_MAC));
+
+    let deadline = Instant::now() + timeout;
+    let mut buf = [0; 64];
+    loop {
+        let now = Instant::now();
+        if now >= deadline || !try!(socket.wait_readable(deadline - now)) {
+            return Ok(false);
+        }
+        let len = try!(socket.recv(&mut buf));
+        if is_conflict(&buf[..len], addr, &mac) {
+            return Ok(true);
+        }
+    }
+}
+
+// An ARP probe for `addr`: a request with an unspecified sender address, so that the neighbours
+// don't update their caches with it.
+fn probe(addr: Ipv4Addr, mac: &[u8; 6]) -> [u8; ARP_LEN] {
+    let mut packet = [0; ARP_LEN];
+    packet[0..2].copy_from_slice(&ARPHRD_ETHER.to_be_bytes());
+    packet[2..4].copy_from_slice(&ETH_P_IP.to_be_bytes());
+    packet[4] = 6;
+    packet[5] = 4;
+    packet[6..8].copy_from_slice(&ARPOP_REQUEST.to_be_bytes());
+    packet[8..14].copy_from_slice(mac);
+    // The sender IP at 14..18 and the target MAC at 18..24 stay zero.
+    packet[24..28].copy_from_slice(&addr.octets());
+    packet
+}
+
+// Whether `packet` shows another host owning `addr`: any ARP packet sent from it, or a probe for
+// it from a host trying to claim it at the same time.
+fn is_conflict(packet: &[u8], addr: Ipv4Addr, our_mac: &[u8; 6]) -> bool {
+    if packet.len() < ARP_LEN || packet[4] != 6 || packet[5] != 4 ||
+       packet[2..4] != ETH_P_IP.to_be_bytes() {
+        return false;
+    }
+    let sender_mac = &packet[8..14];
+    if sender_mac == &our_mac[..] {
+        return false;
+    }
+    let sender_ip = &packet[14..18];
+    let target_ip = &packet[24..28];
+    sender_ip == &addr.octets()[..] ||
+    (sender_ip == &[0; 4][..] && packet[6..8] == ARPOP_REQUEST.to_be_bytes() &&
+     target_ip == &addr.octets()[..])
+}
+
+// A `struct sockaddr_ll` for the interface `index` and the hardware address `mac`.
+fn sockaddr_ll(index: u32, mac: &[u8; 6]) -> [u8; SOCKADDR_LL_LEN] {
+    let mut sa = [0; SOCKADDR_LL_LEN];
+    sa[0..2].copy_from_slice(&(AF_PACKET as u16).to_ne_bytes());
+    sa[2..4].copy_from_slice(&ETH_P_ARP.to_be_bytes());
+    sa[4..8].copy_from_slice(&index.to_ne_bytes());
+    sa[11] = 6;
+    sa[12..18].copy_from_slice(mac);
+    sa
+}
+
+#[allow(unsafe_code)]
+fn name_to_index(name: &str) -> io::Result<u32> {
+    let mut c_name = name.as_bytes().to_vec();
+    c_name.push(0);
+    match unsafe { libc::if_nametoindex(c_name.as_ptr() as *const _) } {
+        0 => Err(io::Error::last_os_error()),
+        index => Ok(index),
+    }
+}
+
+// A datagram packet socket, which leaves the Ethernet header to the kernel.
+struct PacketSocket {
+    fd: c_int,
+    index: u32,
+}
+
+impl PacketSocket {
+    #[allow(unsafe_code)]
+    fn new(index: u32) -> io::Result<PacketSocket> {
+        let fd = unsafe {
+            libc::socket(AF_PACKET, libc::SOCK_DGRAM, c_int::from(ETH_P_ARP.to_be()))
+        };
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let socket = PacketSocket {
+            fd: fd,
+            index: index,
+        };
+        let sa = sockaddr_ll(index, &[0; 6]);
+        let sa_len = SOCKADDR_LL_LEN as socklen_t;
+        if unsafe { libc::bind(fd, sa.as_ptr() as *const sockaddr, sa_len) } < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(socket)
+    }
+
+    #[allow(unsafe_code)]
+    fn send_to(&self, packet: &[u8], mac: &[u8; 6]) -> io::Result<()> {
+        let sa = sockaddr_ll(self.index, mac);
+        let sent = unsafe {
+            libc::sendto(self.fd,
+                         packet.as_ptr() as *const c_void,
+                         packet.len(),
+                         0,
+                         sa.as_ptr() as *const sockaddr,
+                         SOCKADDR_LL_LEN as socklen_t)
+        };
+        if sent < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    #[allow(unsafe_code)]
+    fn wait_readable(&self, timeout: Duration) -> io::Result<bool> {
+        let mut fd = pollfd {
+            fd: self.fd,
+            events: POLLIN,
+            revents: 0,
+        };
+        let timeout_ms = timeout.as_secs() * 1000 + u64::from(timeout.subsec_nanos() / 1_000_000);
+        let timeout_ms = timeout_ms.min(c_int::max_value() as u64) as c_int;
+        match unsafe { libc::poll(&mut fd, 1, timeout_ms) } {
+            n if n < 0 => {
+                let e = io::Error::last_os_error();
+                if e.kind() == io::ErrorKind::Interrupted {
+                    Ok(false)
+                } else {
+                    Err(e)
+                }
+            }
+            0 => Ok(false),
+            _ => Ok(true),
+        }
+    }
+
+    #[allow(unsafe_code)]
+    fn recv(&self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = unsafe { libc::recv(self.fd, buf.as_mut_ptr() as *mut c_void, buf.len(), 0) };
+        if len < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(len as usize)
+    }
+}
+
+impl Drop for PacketSocket {
+    #[allow(unsafe_code)]
+    fn drop(&mut self) {
+        let _ = unsafe { libc::close(self.fd) };
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::net::{IpAddr, Ipv4Addr};
+
+    use common::get_if_addrs::get_if_addrs;
+
+    const OUR_MAC: [u8; 6] = [0x52, 0x54, 0x00, 0x00, 0x00, 0x01];
+    const THEIR_MAC: [u8; 6] = [0x52, 0x54, 0x00, 0x00, 0x00, 0x02];
+
+    fn reply(sender_mac: &[u8; 6], sender_ip: Ipv4Addr) -> [u8; ARP_LEN] {
+        let mut packet = probe(Ipv4Addr::new(192, 168, 1, 2), sender_mac);
+        packet[6..8].copy_from_slice(&2u16.to_be_bytes());
+        packet[14..18].copy_from_slice(&sender_ip.octets());
+        packet
+    }
+
+    #[test]
+    fn conflicts() {
+        let addr = Ipv4Addr::new(192, 168, 1, 5);
+
+        assert!(is_conflict(&reply(&THEIR_MAC, addr), addr, &OUR_MAC));
+        assert!(is_conflict(&probe(addr, &THEIR_MAC), addr, &OUR_MAC));
+
+        assert!(!is_conflict(&probe(addr, &OUR_MAC), addr, &OUR_MAC));
+        assert!(!is_conflict(&reply(&THEIR_MAC, Ipv4Addr::new(192, 168, 1, 6)), addr, &OUR_MAC));
+        assert!(!is_conflict(&probe(Ipv4Addr::new(192, 168, 1, 6), &THEIR_MAC), addr, &OUR_MAC));
+        assert!(!is_conflict(&reply(&THEIR_MAC, addr)[..ARP_LEN - 1], addr, &OUR_MAC));
+    }
+
+    // Probing for real, which is skipped without `CAP_NET_RAW`. Whether there is a conflict
+    // depends on the network, so only the probe working is checked.
+    #[test]
+    fn probe_on_first_ethernet_interface() {
+        let ifaces = unwrap!(get_if_addrs());
+        let interface = match ifaces.iter().find(|interface| {
+            !interface.is_loopback() && interface.addr.ip().is_ipv4() &&
+            sysfs::mac_address(Path::new(sysfs::SYS_CLASS_NET), &interface.name).is_some()
+        }) {
+            Some(interface) => interface,
+            None => return,
+        };
+        let ip = match interface.addr.ip() {
+            IpAddr::V4(ip) => ip,
+            IpAddr::V6(_) => unreachable!(),
+        };
+        match addr_conflict(ip, interface, Duration::from_millis(200)) {
+            Ok(_) => (),
+            Err(ref e) if e.kind() == io::ErrorKind::PermissionDenied => (),
+            Err(e) => panic!("Probing failed: {}", e),
+        }
+    }
+}
@@ -0,0 +1,190 @@
+// Classification of interface addresses by how far they can be reached from.
+
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+/// How far an address can be reached from, from least to most reachable.
+///
+/// Ordering follows reachability, so sorting by `Routability` in descending order puts the
+/// addresses worth advertising first.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+pub enum Routability {
+    /// Only reachable from this machine.
+    Loopback,
+    /// Only reachable from the same link (`169.254.0.0/16`, `fe80::/10`).
+    LinkLocal,
+    /// Reachable from the same carrier-grade NAT, in the shared address space of RFC 6598
+    /// (`100.64.0.0/10`). This is a tier of its own below `Private` since such an address is
+    /// neither under our control nor reachable from the internet.
+    Shared,
+    /// Reachable from the same private network (RFC 1918), or the same site for the unique local
+    /// IPv6 addresses of RFC 4193 (`fc00::/7`).
+    Private,
+    /// Reachable from anywhere, as far as we can tell from the address alone.
+    Global,
+}
+
+impl Routability {
+    /// Classify `ip`.
+    pub fn of(ip: &IpAddr) -> Routability {
+        match *ip {
+            IpAddr::V4(ref ip) => Routability::of_v4(ip),
+            IpAddr::V6(ref ip) => Routability::of_v6(ip),
+        }
+    }
+
+    fn of_v4(ip: &Ipv4Addr) -> Routability {
+        if ip.is_loopback() {
+            Routability::Loopback
+        } else if ip.is_link_local() {
+            Routability::LinkLocal
+        } else if is_shared(ip) {
+            Routability::Shared
+        } else if ip.is_private() {
+            Routability::Private
+        } else {
+            Routability::Global
+        }
+    }
+
+    fn of_v6(ip: &Ipv6Addr) -> Routability {
+        if ip.is_loopback() {
+            Routability::Loopback
+        } else if ip.segments()[0] & 0xffc0 == 0xfe80 {
+            Routability::LinkLocal
+        } else if is_unique_local(ip) {
+            Routability::Private
+        } else {
+            Routability::Global
+        }
+    }
+}
+
+/// Check whether `ip` is in the shared address space of carrier-grade NATs, `100.64.0.0/10`.
+pub fn is_shared(ip: &Ipv4Addr) -> bool {
+    let octets = ip.octets();
+    octets[0] == 100 && octets[1] & 0xc0 == 64
+}
+
+/// Check whether `ip` is a unique local address, from `fc00::/7`.
+pub fn is_unique_local(ip: &Ipv6Addr) -> bool {
+    ip.segments()[0] & 0xfe00 == 0xfc00
+}
+
+/// Get `ip` without the scope the BSDs embed in the second segment of the link-local IPv6
+/// addresses they report, e.g. `fe80::1` for `fe80:4::1`, which is their way of writing
+/// `fe80::1%4`. Other addresses are returned as they are.
+pub fn without_scope(ip: &IpAddr) -> IpAddr {
+    match *ip {
+        IpAddr::V6(ref ip) if ip.segments()[0] & 0xffc0 == 0xfe80 => {
+            let mut segments = ip.segments();
+            segments[1] = 0;
+            IpAddr::V6(Ipv6Addr::from(segments))
+        }
+        ref ip => *ip,
+    }
+}
+
+/// Check whether `a` and `b` are the same address when compared without their scopes, as by
+/// `without_scope()`.
+///
+/// The zone of a link-local address, as in `fe80::1%eth0`, isn't part of an `IpAddr`, but where it
+/// is embedded in the address itself the same address can look different depending on where it
+/// was taken from.
+pub fn addr_eq_ignoring_scope(a: &IpAddr, b: &IpAddr) -> bool {
+    without_scope(a) == without_scope(b)
+}
+
+/// The scope of an IPv6 address, from narrowest to widest, as used when choosing between source
+/// and destination addresses per RFC 6724.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+pub enum V6Scope {
+    /// `::1`, only reachable from this machine.
+    Loopback,
+    /// `fe80::/10`, only reachable from the same link.
+    LinkLocal,
+    /// `fec0::/10`, the deprecated site-local addresses.
+    SiteLocal,
+    /// `fc00::/7`, the unique local addresses replacing site-local ones (RFC 4193). RFC 6724
+    /// gives them global scope but a precedence of their own.
+    UniqueLocal,
+    /// Anything else.
+    Global,
+}
+
+impl V6Scope {
+    /// Classify `ip`.
+    pub fn of(ip: &Ipv6Addr) -> V6Scope {
+        let first = ip.segments()[0];
+        if ip.is_loopback() {
+            V6Scope::Loopback
+        } else if first & 0xffc0 == 0xfe80 {
+            V6Scope::LinkLocal
+        } else if first & 0xffc0 == 0xfec0 {
+            V6Scope::SiteLocal
+        } else if is_unique_local(ip) {
+            V6Scope::UniqueLocal
+        } else {
+            V6Scope::Global
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+    use std::str::FromStr;
+
+    fn of(ip: &str) -> Routability {
+        Routability::of(&unwrap!(IpAddr::from_str(ip)))
+    }
+
+    #[test]
+    fn classification() {
+        assert_eq!(Routability::of(&IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1))),
+                   Routability::Loopback);
+        assert_eq!(Routability::of(&IpAddr::V6(Ipv6Addr::new(0, 0, 0, 0, 0, 0, 0, 1))),
+                   Routability::Loopback);
+        assert_eq!(of("169.254.1.1"), Routability::LinkLocal);
+        assert_eq!(of("fe80::1"), Routability::LinkLocal);
+        assert_eq!(of("192.168.1.5"), Routability::Private);
+        assert_eq!(of("10.1.2.3"), Routability::Private);
+        assert_eq!(of("100.64.1.1"), Routability::Shared);
+        assert_eq!(of("100.127.255.254"), Routability::Shared);
+        assert_eq!(of("100.128.0.1"), Routability::Global);
+        assert_eq!(of("100.63.255.255"), Routability::Global);
+        assert_eq!(of("8.8.8.8"), Routability::Global);
+        assert_eq!(of("2001:db8::1"), Routability::Global);
+        assert_eq!(of("fd00::1"), Routability::Private);
+        assert_eq!(of("fc00::1"), Routability::Private);
+        assert_eq!(of("fe00::1"), Routability::Global);
+        assert!(Routability::Global > Routability::Private);
+        assert!(Routability::Private > Routability::Shared);
+    }
+
+    #[test]
+    fn comparison_ignoring_scope() {
+        let ip = |ip| unwrap!(IpAddr::from_str(ip));
+        assert!(addr_eq_ignoring_scope(&ip("fe80::1"), &ip("fe80::1")));
+        assert!(addr_eq_ignoring_scope(&ip("fe80:4::1"), &ip("fe80::1")));
+        assert!(addr_eq_ignoring_scope(&ip("fe80:4::1"), &ip("fe80:7::1")));
+        assert!(!addr_eq_ignoring_scope(&ip("fe80::1"), &ip("fe80::2")));
+        // Only link-local addresses have a scope to ignore.
+        assert!(!addr_eq_ignoring_scope(&ip("2001:4::1"), &ip("2001::1")));
+        assert!(addr_eq_ignoring_scope(&ip("192.168.1.5"), &ip("192.168.1.5")));
+        assert!(!addr_eq_ignoring_scope(&ip("192.168.1.5"), &ip("::ffff:192.168.1.5")));
+        assert_eq!(without_scope(&ip("fe80:4::1")), ip("fe80::1"));
+    }
+
+    #[test]
+    fn v6_scopes() {
+        let scope = |ip| V6Scope::of(&unwrap!(Ipv6Addr::from_str(ip)));
+        assert_eq!(scope("::1"), V6Scope::Loopback);
+        assert_eq!(scope("fe80::1"), V6Scope::LinkLocal);
+        assert_eq!(scope("fec0::1"), V6Scope::SiteLocal);
+        assert_eq!(scope("fc00::1"), V6Scope::UniqueLocal);
+        assert_eq!(scope("fd12:3456::1"), V6Scope::UniqueLocal);
+        assert_eq!(scope("2001:db8::1"), V6Scope::Global);
+    }
+}
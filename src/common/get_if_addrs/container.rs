@@ -0,0 +1,127 @@
+// Guessing whether this process runs inside a container, where LAN discovery usually only reaches
+// the container's own bridge. The filesystem root is passed in so tests can point the checks at a
+// mock tree.
+
+use std::env;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+use common::get_if_addrs::{IfAddr, Interface, get_if_addrs};
+
+/// The environment variable overriding `in_container()`: `1` or `true` to say we are in a
+/// container, `0` or `false` to say we aren't.
+pub const IN_CONTAINER_ENV: &'static str = "CRUST_IN_CONTAINER";
+
+/// Guess whether this process runs inside a container.
+///
+/// Unless `IN_CONTAINER_ENV` says otherwise, this is the case if any of these holds:
+///
+/// - `/.dockerenv`, which Docker creates, exists.
+/// - `/proc/1/cgroup` puts the first process in a cgroup of docker, LXC, containerd or
+///   Kubernetes.
+/// - The only interfaces besides loopback are of a single `eth0` on `172.17.0.0/16`, the default
+///   network of Docker.
+pub fn in_container() -> bool {
+    if let Ok(value) = env::var(IN_CONTAINER_ENV) {
+        match &value[..] {
+            "1" | "true" => return true,
+            "0" | "false" => return false,
+            _ => warn!("Ignoring {}={:?}", IN_CONTAINER_ENV, value),
+        }
+    }
+    let ifaces = get_if_addrs().unwrap_or_else(|e| {
+        warn!("Could not enumerate interfaces: {:?}", e);
+        Vec::new()
+    });
+    looks_like_container(Path::new("/"), &ifaces)
+}
+
+fn looks_like_container(root: &Path, ifaces: &[Interface]) -> bool {
+    root.join(".dockerenv").exists() || has_container_cgroup(root) ||
+    has_docker_interfaces(ifaces)
+}
+
+fn has_container_cgroup(root: &Path) -> bool {
+    let mut contents = String::new();
+    match File::open(root.join("proc/1/cgroup")) {
+        Ok(mut file) => {
+            if file.read_to_string(&mut contents).is_err() {
+                return false;
+            }
+        }
+        Err(_) => return false,
+    }
+    // Lines look like `12:memory:/docker/<id>`, the path being the last field.
+    contents.lines().filter_map(|line| line.splitn(3, ':').nth(2)).any(|path| {
+        ["docker", "lxc", "containerd", "kubepods"].iter().any(|hint| path.contains(hint))
+    })
+}
+
+fn has_docker_interfaces(ifaces: &[Interface]) -> bool {
+    let mut on_docker_network = false;
+    for interface in ifaces.iter().filter(|interface| !interface.is_loopback()) {
+        if interface.name != "eth0" {
+            return false;
+        }
+        // Docker networks may have IPv6 enabled too, so only IPv4 addresses are looked at.
+        if let IfAddr::V4(ref ifv4_addr) = interface.addr {
+            let octets = ifv4_addr.ip.octets();
+            if octets[0] != 172 || octets[1] != 17 {
+                return false;
+            }
+            on_docker_network = true;
+        }
+    }
+    on_docker_network
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::fs;
+    use std::io::Write;
+    use std::path::PathBuf;
+
+    use common::get_if_addrs::test_util::{interface, temp_path};
+
+    fn mock_root(dockerenv: bool, cgroup: &str) -> PathBuf {
+        let root = temp_path("container");
+        unwrap!(fs::create_dir_all(root.join("proc/1")));
+        if dockerenv {
+            let _ = unwrap!(File::create(root.join(".dockerenv")));
+        }
+        let mut file = unwrap!(File::create(root.join("proc/1/cgroup")));
+        unwrap!(file.write_all(cgroup.as_bytes()));
+        root
+    }
+
+    #[test]
+    fn signals() {
+        let host_cgroup = "12:memory:/user.slice\n0::/init.scope\n";
+        let host_ifaces = vec![interface("lo", "127.0.0.1", "255.0.0.0"),
+                               interface("eth0", "192.168.1.5", "255.255.255.0"),
+                               interface("docker0", "172.17.0.1", "255.255.0.0")];
+        let docker_ifaces = vec![interface("lo", "127.0.0.1", "255.0.0.0"),
+                                 interface("eth0", "172.17.0.2", "255.255.0.0"),
+                                 interface("eth0", "2001:db8::2", "ffff:ffff:ffff:ffff::")];
+
+        let host = mock_root(false, host_cgroup);
+        assert!(!looks_like_container(&host, &host_ifaces));
+        assert!(!looks_like_container(&host, &[interface("lo", "127.0.0.1", "255.0.0.0")]));
+        assert!(looks_like_container(&host, &docker_ifaces));
+
+        let dockerenv = mock_root(true, host_cgroup);
+        assert!(looks_like_container(&dockerenv, &host_ifaces));
+
+        let lxc = mock_root(false, "12:memory:/lxc/web\n");
+        assert!(looks_like_container(&lxc, &host_ifaces));
+        let kubernetes = mock_root(false, "0::/kubepods/besteffort/pod1234\n");
+        assert!(looks_like_container(&kubernetes, &host_ifaces));
+
+        for root in &[host, dockerenv, lxc, kubernetes] {
+            unwrap!(fs::remove_dir_all(root));
+        }
+    }
+}
@@ -0,0 +1,97 @@
+// A structured alternative to the plain `io::Error` the enumeration functions fail with.
+
+use std::io;
+
+use common::get_if_addrs::{Interface, get_if_addrs};
+
+quick_error! {
+    /// An error enumerating the interfaces on this machine.
+    #[derive(Debug)]
+    pub enum IfError {
+        /// The system call doing the enumeration failed.
+        Syscall(e: io::Error) {
+            description("Enumeration system call failed")
+            display("Enumeration system call failed: {}", e)
+            cause(e)
+            from()
+        }
+        /// The enumeration succeeded but no interfaces were found, not even a loopback one.
+        NoInterfaces {
+            description("No network interfaces found")
+        }
+        /// This platform doesn't implement any way of enumerating interfaces.
+        UnsupportedPlatform {
+            description("Interface enumeration is not supported on this platform")
+        }
+        /// The name of an interface is not valid UTF-8.
+        NameDecode {
+            description("Interface name is not valid UTF-8")
+        }
+    }
+}
+
+/// Get the same as `get_if_addrs()`, but fail with an `IfError` telling what went wrong. Finding
+/// no interfaces at all or one whose name can't be decoded is an error too.
+pub fn get_if_addrs_checked() -> Result<Vec<Interface>, IfError> {
+    check(get_if_addrs())
+}
+
+fn check(result: io::Result<Vec<Interface>>) -> Result<Vec<Interface>, IfError> {
+    let ifaces = match result {
+        Ok(ifaces) => ifaces,
+        Err(ref e) if is_unsupported(e) => return Err(IfError::UnsupportedPlatform),
+        Err(e) => return Err(IfError::Syscall(e)),
+    };
+    if ifaces.is_empty() {
+        return Err(IfError::NoInterfaces);
+    }
+    // Names are decoded lossily during enumeration, which leaves replacement characters behind.
+    if ifaces.iter().any(|interface| interface.name.contains('\u{fffd}')) {
+        return Err(IfError::NameDecode);
+    }
+    Ok(ifaces)
+}
+
+#[cfg(unix)]
+fn is_unsupported(e: &io::Error) -> bool {
+    use libc::{ENOSYS, EOPNOTSUPP};
+    e.raw_os_error().map_or(false, |code| code == ENOSYS || code == EOPNOTSUPP)
+}
+
+#[cfg(windows)]
+fn is_unsupported(e: &io::Error) -> bool {
+    // `ERROR_NOT_SUPPORTED` and `ERROR_CALL_NOT_IMPLEMENTED`.
+    e.raw_os_error().map_or(false, |code| code == 50 || code == 120)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use common::get_if_addrs::InterfaceBuilder;
+
+    #[test]
+    fn display_of_each_variant() {
+        let syscall = IfError::from(io::Error::new(io::ErrorKind::Other, "boom"));
+        assert_eq!(syscall.to_string(), "Enumeration system call failed: boom");
+        assert_eq!(IfError::NoInterfaces.to_string(), "No network interfaces found");
+        assert_eq!(IfError::UnsupportedPlatform.to_string(),
+                   "Interface enumeration is not supported on this platform");
+        assert_eq!(IfError::NameDecode.to_string(),
+                   "Interface name is not valid UTF-8");
+    }
+
+    #[test]
+    fn checks() {
+        match check(Ok(Vec::new())) {
+            Err(IfError::NoInterfaces) => (),
+            result => panic!("Unexpected {:?}", result),
+        }
+        let undecodable = InterfaceBuilder::new().name("eth\u{fffd}").build();
+        match check(Ok(vec![undecodable])) {
+            Err(IfError::NameDecode) => (),
+            result => panic!("Unexpected {:?}", result),
+        }
+        assert!(!unwrap!(get_if_addrs_checked()).is_empty());
+    }
+}
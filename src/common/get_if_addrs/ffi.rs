@@ -0,0 +1,143 @@
+// A C interface to the enumeration, for hosts embedding crust from other languages. Interfaces
+// are copied into plain fixed-size structs in an array the caller owns, so nothing allocated on
+// the Rust side ever crosses the boundary.
+
+use std::slice;
+
+use common::get_if_addrs::{IfAddr, Interface, get_if_addrs};
+use libc::c_char;
+
+/// The size of `CIfAddr::name`, the terminating NUL included.
+pub const CIFADDR_NAME_LEN: usize = 64;
+
+/// An interface address as `crust_getifaddrs()` reports it.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct CIfAddr {
+    /// The NUL-terminated name of the interface, truncated to `CIFADDR_NAME_LEN - 1` bytes.
+    pub name: [c_char; CIFADDR_NAME_LEN],
+    /// The address family: 4 for IPv4, 6 for IPv6.
+    pub family: u8,
+    /// The prefix length of the netmask, or 0 if it isn't contiguous.
+    pub prefix_len: u8,
+    /// The address in network byte order, in the first 4 bytes for IPv4.
+    pub addr: [u8; 16],
+    /// The netmask in network byte order, in the first 4 bytes for IPv4.
+    pub netmask: [u8; 16],
+    /// The index of the interface, or 0 if unknown.
+    pub index: u32,
+    /// The bits of the `IfFlags` of the interface.
+    pub flags: u32,
+}
+
+impl CIfAddr {
+    fn from_interface(interface: &Interface) -> CIfAddr {
+        let mut c_addr = CIfAddr {
+            name: [0; CIFADDR_NAME_LEN],
+            family: 0,
+            prefix_len: interface.addr.prefix_len().unwrap_or(0),
+            addr: [0; 16],
+            netmask: [0; 16],
+            index: interface.index,
+            flags: interface.flags.bits(),
+        };
+        // The last byte is left as the terminating NUL.
+        let name = &mut c_addr.name[..CIFADDR_NAME_LEN - 1];
+        for (c, &byte) in name.iter_mut().zip(interface.name.as_bytes()) {
+            *c = byte as c_char;
+        }
+        match interface.addr {
+            IfAddr::V4(ref ifv4_addr) => {
+                c_addr.family = 4;
+                c_addr.addr[..4].copy_from_slice(&ifv4_addr.ip.octets());
+                c_addr.netmask[..4].copy_from_slice(&ifv4_addr.netmask.octets());
+            }
+            IfAddr::V6(ref ifv6_addr) => {
+                c_addr.family = 6;
+                c_addr.addr.copy_from_slice(&ifv6_addr.ip.octets());
+                c_addr.netmask.copy_from_slice(&ifv6_addr.netmask.octets());
+            }
+        }
+        c_addr
+    }
+}
+
+/// Enumerate the interfaces as `get_if_addrs()` does into the array of `cap` entries at `out`.
+///
+/// Returns the number of interfaces, of which only the first `cap` are written: when that is more
+/// than `cap`, call again with an array of at least that many entries. Passing a null `out` and a
+/// `cap` of 0 just asks for the number. Returns -1 if the interfaces couldn't be enumerated.
+///
+/// # Safety
+///
+/// Unless `cap` is 0, `out` must point to an array of at least `cap` writable `CIfAddr`s.
+#[allow(unsafe_code)]
+#[no_mangle]
+pub unsafe extern "C" fn crust_getifaddrs(out: *mut CIfAddr, cap: usize) -> isize {
+    let ifaces = match get_if_addrs() {
+        Ok(ifaces) => ifaces,
+        Err(e) => {
+            warn!("Could not enumerate interfaces: {:?}", e);
+            return -1;
+        }
+    };
+    if cap > 0 && !out.is_null() {
+        let out = slice::from_raw_parts_mut(out, cap);
+        for (c_addr, interface) in out.iter_mut().zip(&ifaces) {
+            *c_addr = CIfAddr::from_interface(interface);
+        }
+    }
+    ifaces.len() as isize
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::ffi::CStr;
+    use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+    use std::ptr;
+
+    #[allow(unsafe_code)]
+    #[test]
+    fn fills_a_fixed_buffer() {
+        let ifaces = unwrap!(get_if_addrs());
+        let mut buffer = [CIfAddr::from_interface(&ifaces[0]); 64];
+        let count = unsafe { crust_getifaddrs(buffer.as_mut_ptr(), buffer.len()) };
+        assert_eq!(count, ifaces.len() as isize);
+        for (c_addr, interface) in buffer.iter().zip(&ifaces) {
+            let name = unsafe { CStr::from_ptr(c_addr.name.as_ptr()) };
+            assert_eq!(unwrap!(name.to_str()), interface.name);
+            let ip = match c_addr.family {
+                4 => {
+                    let mut octets = [0; 4];
+                    octets.copy_from_slice(&c_addr.addr[..4]);
+                    IpAddr::V4(Ipv4Addr::from(octets))
+                }
+                6 => IpAddr::V6(Ipv6Addr::from(c_addr.addr)),
+                family => panic!("Unexpected family {}", family),
+            };
+            assert_eq!(ip, interface.addr.ip());
+            assert_eq!(c_addr.prefix_len, interface.addr.prefix_len().unwrap_or(0));
+            assert_eq!(c_addr.index, interface.index);
+            assert_eq!(c_addr.flags, interface.flags.bits());
+        }
+
+        // Asking for the number, and a buffer too small for all of them.
+        assert_eq!(unsafe { crust_getifaddrs(ptr::null_mut(), 0) }, count);
+        let mut one = [CIfAddr::from_interface(&ifaces[0]); 1];
+        one[0].index = !0;
+        assert_eq!(unsafe { crust_getifaddrs(one.as_mut_ptr(), 1) }, count);
+        assert_eq!(one[0].index, ifaces[0].index);
+    }
+
+    #[test]
+    fn long_names_are_truncated() {
+        use common::get_if_addrs::InterfaceBuilder;
+
+        let name: String = ::std::iter::repeat('x').take(100).collect();
+        let c_addr = CIfAddr::from_interface(&InterfaceBuilder::new().name(&name).build());
+        assert!(c_addr.name[..CIFADDR_NAME_LEN - 1].iter().all(|&c| c == b'x' as c_char));
+        assert_eq!(c_addr.name[CIFADDR_NAME_LEN - 1], 0);
+    }
+}
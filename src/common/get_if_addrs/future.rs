@@ -0,0 +1,186 @@
+// Enumeration for async code, which mustn't block the thread its executor polls it on. This
+// doesn't depend on any particular executor: a worker thread shared by all the futures enumerates
+// for each of them in turn and wakes it up once it's done. `InterfaceSource` lets code be written
+// once for both ways of enumerating, and for mock interfaces too.
+
+use std::future::Future;
+use std::io;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::sync::mpsc::{self, SendError, Sender};
+use std::task::{Context, Poll, Waker};
+use std::thread;
+
+use common::get_if_addrs::{Interface, get_if_addrs};
+
+/// The future returned by `get_if_addrs_async()`.
+pub struct GetIfAddrsFuture {
+    shared: Arc<Mutex<Shared>>,
+}
+
+struct Shared {
+    result: Option<io::Result<Vec<Interface>>>,
+    waker: Option<Waker>,
+}
+
+lazy_static! {
+    // The worker enumerating for the futures, started by the first of them.
+    static ref WORKER: Mutex<Option<Sender<Arc<Mutex<Shared>>>>> = Mutex::new(None);
+}
+
+/// Get the same as `get_if_addrs()`, but through a future which can be awaited without blocking
+/// the executor.
+///
+/// The enumerations are done on a single worker thread, one after the other, so many futures
+/// don't cost a thread each.
+pub fn get_if_addrs_async() -> GetIfAddrsFuture {
+    let shared = Arc::new(Mutex::new(Shared {
+        result: None,
+        waker: None,
+    }));
+    if let Err(e) = enqueue(shared.clone()) {
+        unwrap!(shared.lock()).result = Some(Err(e));
+    }
+    GetIfAddrsFuture { shared: shared }
+}
+
+// Hand `shared` to the worker, starting it first if it isn't running.
+fn enqueue(shared: Arc<Mutex<Shared>>) -> io::Result<()> {
+    let mut worker = unwrap!(WORKER.lock());
+    let shared = match worker.as_ref() {
+        Some(job_tx) => {
+            match job_tx.send(shared) {
+                Ok(()) => return Ok(()),
+                // The worker is gone, e.g. because an enumeration panicked.
+                Err(SendError(shared)) => shared,
+            }
+        }
+        None => shared,
+    };
+
+    let (job_tx, job_rx) = mpsc::channel::<Arc<Mutex<Shared>>>();
+    let _ = try!(thread::Builder::new().name("GetIfAddrs".to_string()).spawn(move || {
+        for job in job_rx {
+            let result = get_if_addrs();
+            let mut shared = unwrap!(job.lock());
+            shared.result = Some(result);
+            if let Some(waker) = shared.waker.take() {
+                waker.wake();
+            }
+        }
+    }));
+    unwrap!(job_tx.send(shared));
+    *worker = Some(job_tx);
+    Ok(())
+}
+
+impl Future for GetIfAddrsFuture {
+    type Output = io::Result<Vec<Interface>>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        let mut shared = unwrap!(self.shared.lock());
+        match shared.result.take() {
+            Some(result) => Poll::Ready(result),
+            None => {
+                shared.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+/// Somewhere to get interfaces from, either blocking or through a future.
+pub trait InterfaceSource {
+    /// The future `interfaces_async()` returns.
+    type Future: Future<Output = io::Result<Vec<Interface>>>;
+
+    /// Get the interfaces, blocking until they are known.
+    fn interfaces(&self) -> io::Result<Vec<Interface>>;
+
+    /// Get the interfaces through a future which can be awaited without blocking the executor.
+    fn interfaces_async(&self) -> Self::Future;
+}
+
+/// The interfaces on this machine, as enumerated by `get_if_addrs()` and `get_if_addrs_async()`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemSource;
+
+impl InterfaceSource for SystemSource {
+    type Future = GetIfAddrsFuture;
+
+    fn interfaces(&self) -> io::Result<Vec<Interface>> {
+        get_if_addrs()
+    }
+
+    fn interfaces_async(&self) -> GetIfAddrsFuture {
+        get_if_addrs_async()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::net::{IpAddr, Ipv4Addr};
+
+    use common::get_if_addrs::InterfaceBuilder;
+    use common::get_if_addrs::test_util::{Ready, block_on, ready};
+
+    #[test]
+    fn same_as_sync_enumeration() {
+        let ifaces = unwrap!(block_on(get_if_addrs_async()));
+        assert_eq!(ifaces, unwrap!(get_if_addrs()));
+    }
+
+    #[test]
+    fn futures_share_a_worker() {
+        let futures: Vec<GetIfAddrsFuture> = (0..8).map(|_| get_if_addrs_async()).collect();
+        let expected = unwrap!(get_if_addrs());
+        for future in futures {
+            assert_eq!(unwrap!(block_on(future)), expected);
+        }
+        assert!(unwrap!(WORKER.lock()).is_some());
+    }
+
+    struct MockSource(Vec<Interface>);
+
+    impl InterfaceSource for MockSource {
+        type Future = Ready<io::Result<Vec<Interface>>>;
+
+        fn interfaces(&self) -> io::Result<Vec<Interface>> {
+            Ok(self.0.clone())
+        }
+
+        fn interfaces_async(&self) -> Self::Future {
+            ready(Ok(self.0.clone()))
+        }
+    }
+
+    // Something written once for any source.
+    fn loopback_names<S: InterfaceSource>(source: &S) -> (Vec<String>, Vec<String>) {
+        let names = |ifaces: Vec<Interface>| {
+            ifaces.into_iter()
+                .filter(Interface::is_loopback)
+                .map(|interface| interface.name)
+                .collect()
+        };
+        (names(unwrap!(source.interfaces())), names(unwrap!(block_on(source.interfaces_async()))))
+    }
+
+    #[test]
+    fn generic_over_sources() {
+        let mock = MockSource(vec![InterfaceBuilder::new()
+                                       .name("lo0")
+                                       .addr(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)))
+                                       .build(),
+                                   InterfaceBuilder::new()
+                                       .name("en0")
+                                       .addr(IpAddr::V4(Ipv4Addr::new(192, 168, 1, 5)))
+                                       .build()]);
+        assert_eq!(loopback_names(&mock), (vec!["lo0".to_string()], vec!["lo0".to_string()]));
+
+        let (sync, async_) = loopback_names(&SystemSource);
+        assert!(!sync.is_empty());
+        assert_eq!(sync, async_);
+    }
+}
@@ -0,0 +1,187 @@
+// Host candidates for ICE (RFC 8445): the local addresses a connectivity-check engine pairs with
+// those of a peer, each with a foundation and a priority.
+
+use std::io;
+use std::net::{IpAddr, SocketAddr};
+
+use common::get_if_addrs::{AddrFlags, Family, Interface, Routability, get_if_addrs_detailed};
+use common::get_if_addrs::select::network_of;
+
+/// Which addresses to leave out when gathering host candidates, on top of those never gathered.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CandidateOptions {
+    /// Leave out the addresses the OS flags as `AddrFlags::ANYCAST`: other hosts share them, so
+    /// a peer checking connectivity to one may well reach another host. On by default.
+    pub exclude_anycast: bool,
+    /// Also leave out the addresses within any of these prefixes, given as an address and a
+    /// prefix length, for anycast deployments the OS doesn't know about. Empty by default.
+    pub anycast_prefixes: Vec<(IpAddr, u8)>,
+}
+
+impl Default for CandidateOptions {
+    fn default() -> CandidateOptions {
+        CandidateOptions {
+            exclude_anycast: true,
+            anycast_prefixes: Vec::new(),
+        }
+    }
+}
+
+/// An address to gather as an ICE host candidate.
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub struct HostCandidate {
+    /// The transport address of the candidate.
+    pub addr: SocketAddr,
+    /// Tells candidates apart which can't share the results of connectivity checks. Host
+    /// candidates on different addresses always have different foundations.
+    pub foundation: String,
+    /// The priority of the candidate as computed per RFC 8445, section 5.1.2.1.
+    pub priority: u32,
+    /// The name of the interface the candidate's address is assigned to.
+    pub interface: String,
+}
+
+// The type preference RFC 8445 recommends for host candidates.
+const HOST_TYPE_PREFERENCE: u32 = 126;
+// Only one component, as there is no separate RTCP flow to gather for.
+const COMPONENT_ID: u32 = 1;
+
+/// Gather the host candidates of this machine for `port`.
+///
+/// Loopback and link-local addresses are left out, as are deprecated ones. The local preference
+/// making up the priority of each candidate favours more routable addresses, then IPv6 over IPv4
+/// as RFC 8421 suggests for dual-stack hosts, then interfaces listed earlier, so that no two
+/// candidates share a priority.
+pub fn gather_host_candidates(port: u16) -> io::Result<Vec<HostCandidate>> {
+    gather_host_candidates_with_options(port, &CandidateOptions::default())
+}
+
+/// Gather the host candidates of this machine for `port` as `gather_host_candidates()` does,
+/// leaving out the anycast addresses `options` tells.
+pub fn gather_host_candidates_with_options(port: u16,
+                                           options: &CandidateOptions)
+                                           -> io::Result<Vec<HostCandidate>> {
+    Ok(host_candidates(try!(get_if_addrs_detailed()), port, options))
+}
+
+fn host_candidates(ifaces: Vec<Interface>,
+                   port: u16,
+                   options: &CandidateOptions)
+                   -> Vec<HostCandidate> {
+    let mut ifaces: Vec<Interface> = ifaces.into_iter()
+        .filter(|interface| interface.addr.routability() > Routability::LinkLocal)
+        .filter(|interface| !interface.addr_flags.contains(AddrFlags::DEPRECATED))
+        .filter(|interface| {
+            !(options.exclude_anycast && interface.addr_flags.contains(AddrFlags::ANYCAST))
+        })
+        .filter(|interface| {
+            let ip = interface.addr.ip();
+            !options.anycast_prefixes
+                .iter()
+                .any(|&(prefix, prefix_len)| network_of(prefix, prefix_len).contains(&ip))
+        })
+        .collect();
+    // The sort is stable, so interfaces keep their order within each class.
+    ifaces.sort_by(|a, b| rank(b).cmp(&rank(a)));
+
+    ifaces.into_iter()
+        .enumerate()
+        .map(|(i, interface)| {
+            let local_preference = 65535u32.saturating_sub(i as u32);
+            HostCandidate {
+                addr: interface.socket_addr(port),
+                foundation: (i + 1).to_string(),
+                priority: (HOST_TYPE_PREFERENCE << 24) + (local_preference << 8) +
+                          (256 - COMPONENT_ID),
+                interface: interface.name,
+            }
+        })
+        .collect()
+}
+
+fn rank(interface: &Interface) -> (Routability, bool) {
+    (interface.addr.routability(), interface.addr.family() == Family::V6)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::collections::HashSet;
+    use std::net::IpAddr;
+    use std::str::FromStr;
+
+    use common::get_if_addrs::InterfaceBuilder;
+    use common::get_if_addrs::test_util::interface;
+
+    #[test]
+    fn candidates_of_a_dual_stack_host() {
+        let v6_netmask = "ffff:ffff:ffff:ffff::";
+        let ifaces = vec![interface("lo", "127.0.0.1", "255.0.0.0"),
+                          interface("eth0", "192.168.1.5", "255.255.255.0"),
+                          interface("eth0", "fe80::5", v6_netmask),
+                          interface("eth0", "2001:db8::5", v6_netmask),
+                          interface("wlan0", "10.0.0.5", "255.255.255.0"),
+                          interface("wlan0", "2001:db8::6", v6_netmask),
+                          InterfaceBuilder::new()
+                              .name("wlan0")
+                              .addr(unwrap!(IpAddr::from_str("2001:db8::7")))
+                              .addr_flags(AddrFlags::DEPRECATED)
+                              .build()];
+
+        let candidates = host_candidates(ifaces, 5483, &CandidateOptions::default());
+        let addrs: Vec<SocketAddr> = candidates.iter().map(|candidate| candidate.addr).collect();
+        assert_eq!(addrs,
+                   vec![unwrap!(SocketAddr::from_str("[2001:db8::5]:5483")),
+                        unwrap!(SocketAddr::from_str("[2001:db8::6]:5483")),
+                        unwrap!(SocketAddr::from_str("192.168.1.5:5483")),
+                        unwrap!(SocketAddr::from_str("10.0.0.5:5483"))]);
+        assert_eq!(candidates[0].interface, "eth0");
+        assert_eq!(candidates[1].interface, "wlan0");
+
+        // Type preference 126, local preference 65535 and component 1.
+        assert_eq!(candidates[0].priority, 2130706431);
+        assert!(candidates.windows(2).all(|pair| pair[0].priority > pair[1].priority));
+
+        let foundations: HashSet<&str> =
+            candidates.iter().map(|candidate| &candidate.foundation[..]).collect();
+        assert_eq!(foundations.len(), candidates.len());
+        let interfaces: HashSet<&str> =
+            candidates.iter().map(|candidate| &candidate.interface[..]).collect();
+        assert_eq!(interfaces.len(), 2);
+    }
+
+    #[test]
+    fn anycast_addresses_are_left_out() {
+        let anycast = InterfaceBuilder::new()
+            .name("eth0")
+            .addr(unwrap!(IpAddr::from_str("192.0.2.53")))
+            .addr_flags(AddrFlags::ANYCAST)
+            .build();
+        let ifaces = vec![interface("eth0", "192.168.1.5", "255.255.255.0"),
+                          anycast,
+                          interface("eth0", "2001:db8:53::1", "ffff:ffff:ffff:ffff::"),
+                          interface("eth0", "2001:db8::5", "ffff:ffff:ffff:ffff::")];
+        let addrs = |options: &CandidateOptions| -> Vec<IpAddr> {
+            host_candidates(ifaces.clone(), 5483, options)
+                .into_iter()
+                .map(|candidate| candidate.addr.ip())
+                .collect()
+        };
+
+        let mut options = CandidateOptions::default();
+        assert!(!addrs(&options).contains(&unwrap!(IpAddr::from_str("192.0.2.53"))));
+        assert_eq!(addrs(&options).len(), 3);
+
+        options.anycast_prefixes.push((unwrap!(IpAddr::from_str("2001:db8:53::")), 48));
+        assert_eq!(addrs(&options),
+                   vec![unwrap!(IpAddr::from_str("2001:db8::5")),
+                        unwrap!(IpAddr::from_str("192.168.1.5"))]);
+
+        let options = CandidateOptions {
+            exclude_anycast: false,
+            anycast_prefixes: Vec::new(),
+        };
+        assert_eq!(addrs(&options).len(), 4);
+    }
+}
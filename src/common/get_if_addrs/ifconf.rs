@@ -0,0 +1,184 @@
+// Enumeration through the `SIOCGIFCONF` ioctl, the way it was done before `getifaddrs` existed.
+// This is only a fallback: the ioctl knows nothing about IPv6, so those addresses are missing from
+// what it reports.
+
+use std::ffi::CStr;
+use std::io;
+use std::mem::size_of;
+
+use common::get_if_addrs::{AddrFlags, IfFlags, Interface, InterfaceBuilder};
+use common::get_if_addrs::posix::{convert_flags, netmask_to_ipaddr, sockaddr_to_ipaddr};
+use libc::{self, c_char, c_int, c_short, c_uint, c_ulong, c_ushort, sockaddr};
+
+// The type of the request argument of `ioctl`, which differs between C libraries.
+#[cfg(all(target_os = "linux", target_env = "gnu"))]
+type IoctlRequest = c_ulong;
+#[cfg(not(all(target_os = "linux", target_env = "gnu")))]
+type IoctlRequest = c_int;
+
+const SIOCGIFCONF: IoctlRequest = 0x8912;
+const SIOCGIFFLAGS: IoctlRequest = 0x8913;
+const SIOCGIFBRDADDR: IoctlRequest = 0x8919;
+const SIOCGIFNETMASK: IoctlRequest = 0x891b;
+
+const IF_NAMESIZE: usize = 16;
+// `SIOCGIFCONF` is first given room for this many addresses, doubling until they all fit.
+const INITIAL_CAPACITY: usize = 32;
+
+// The second member of `struct ifreq` is a union whose largest variant is `struct ifmap`, which
+// takes up three `unsigned long`s on 64-bit targets and four on 32-bit ones once padded.
+#[cfg(target_pointer_width = "64")]
+const IFRU_WORDS: usize = 3;
+#[cfg(target_pointer_width = "32")]
+const IFRU_WORDS: usize = 4;
+
+// `struct ifreq`, with the union left as raw words to be read as whichever variant the request
+// fills in.
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct IfReq {
+    ifr_name: [c_char; IF_NAMESIZE],
+    ifr_ifru: [c_ulong; IFRU_WORDS],
+}
+
+impl IfReq {
+    fn new(ifr_name: [c_char; IF_NAMESIZE]) -> IfReq {
+        IfReq {
+            ifr_name: ifr_name,
+            ifr_ifru: [0; IFRU_WORDS],
+        }
+    }
+
+    fn sockaddr(&self) -> *const sockaddr {
+        self.ifr_ifru.as_ptr() as *const sockaddr
+    }
+
+    #[allow(unsafe_code)]
+    fn flags(&self) -> c_uint {
+        unsafe { *(self.ifr_ifru.as_ptr() as *const c_short) as c_ushort as c_uint }
+    }
+}
+
+// `struct ifconf`.
+#[repr(C)]
+struct IfConf {
+    ifc_len: c_int,
+    ifc_req: *mut IfReq,
+}
+
+/// Get the IPv4 addresses of the interfaces on this machine through `ioctl(SIOCGIFCONF)`, along
+/// with their netmask, broadcast address and flags.
+///
+/// IPv6 addresses are not reported at all.
+#[allow(unsafe_code)]
+pub fn get_if_addrs_ifconf() -> io::Result<Vec<Interface>> {
+    let socket = try!(Socket::new());
+    let mut ret = Vec::new();
+    for req in try!(socket.if_conf()) {
+        let ip = match sockaddr_to_ipaddr(req.sockaddr()) {
+            Some(ip) => ip,
+            None => continue,
+        };
+        let flags = convert_flags(try!(socket.ioctl(SIOCGIFFLAGS, &req)).flags());
+        let name = unsafe { CStr::from_ptr(req.ifr_name.as_ptr()) }.to_string_lossy();
+        let mut builder = InterfaceBuilder::new()
+            .name(&name)
+            .addr(ip)
+            .flags(flags)
+            .index(unsafe { libc::if_nametoindex(req.ifr_name.as_ptr()) });
+        if let Some(netmask) = netmask_to_ipaddr(try!(socket.ioctl(SIOCGIFNETMASK, &req))
+            .sockaddr()) {
+            builder = builder.netmask(netmask);
+        } else {
+            builder = builder.addr_flags(AddrFlags::NO_NETMASK);
+        }
+        if flags.contains(IfFlags::BROADCAST) {
+            if let Some(broadcast) = sockaddr_to_ipaddr(try!(socket.ioctl(SIOCGIFBRDADDR, &req))
+                .sockaddr()) {
+                builder = builder.broadcast(broadcast);
+            }
+        }
+        ret.push(builder.build());
+    }
+    Ok(ret)
+}
+
+struct Socket {
+    fd: c_int,
+}
+
+impl Socket {
+    #[allow(unsafe_code)]
+    fn new() -> io::Result<Socket> {
+        let fd = unsafe { libc::socket(libc::AF_INET, libc::SOCK_DGRAM, 0) };
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(Socket { fd: fd })
+    }
+
+    // Get one request per address, each naming its interface and holding the address.
+    #[allow(unsafe_code)]
+    #[allow(trivial_casts)]
+    fn if_conf(&self) -> io::Result<Vec<IfReq>> {
+        let mut capacity = INITIAL_CAPACITY;
+        loop {
+            let mut reqs = vec![IfReq::new([0; IF_NAMESIZE]); capacity];
+            let mut ifc = IfConf {
+                ifc_len: (capacity * size_of::<IfReq>()) as c_int,
+                ifc_req: reqs.as_mut_ptr(),
+            };
+            if unsafe { libc::ioctl(self.fd, SIOCGIFCONF, &mut ifc as *mut IfConf) } < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            // The kernel silently drops whatever doesn't fit, so a full buffer may be truncated.
+            let len = ifc.ifc_len as usize / size_of::<IfReq>();
+            if len < capacity {
+                reqs.truncate(len);
+                return Ok(reqs);
+            }
+            capacity *= 2;
+        }
+    }
+
+    // Make `request` about the interface named in `req` and return what the kernel filled in.
+    #[allow(unsafe_code)]
+    #[allow(trivial_casts)]
+    fn ioctl(&self, request: IoctlRequest, req: &IfReq) -> io::Result<IfReq> {
+        let mut answer = IfReq::new(req.ifr_name);
+        if unsafe { libc::ioctl(self.fd, request, &mut answer as *mut IfReq) } < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(answer)
+    }
+}
+
+impl Drop for Socket {
+    #[allow(unsafe_code)]
+    fn drop(&mut self) {
+        let _ = unsafe { libc::close(self.fd) };
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use common::get_if_addrs::get_if_addrs_all;
+
+    #[test]
+    fn matches_getifaddrs_for_ipv4() {
+        let expected: Vec<Interface> = unwrap!(get_if_addrs_all())
+            .into_iter()
+            .filter(|interface| interface.addr.ip().is_ipv4())
+            .collect();
+        let ifconf = unwrap!(get_if_addrs_ifconf());
+        assert_eq!(ifconf.len(), expected.len());
+        for interface in &ifconf {
+            assert!(expected.contains(interface),
+                    "{:?} is not among {:?}",
+                    interface,
+                    expected);
+        }
+    }
+}
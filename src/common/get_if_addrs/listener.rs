@@ -0,0 +1,563 @@
+use std::io;
+use std::net::{IpAddr, SocketAddr, TcpListener, TcpStream};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+use std::time::Duration;
+
+use common::get_if_addrs::{Family, IfEvent, IfWatcher, Interface, get_if_addrs};
+use net2::TcpBuilder;
+
+// How long `accept` waits for a connection before looking for interface changes, when watching.
+const WATCH_POLL_MS: u64 = 500;
+// How many ports `bind_all_same_port()` tries before giving up.
+const SAME_PORT_ATTEMPTS: usize = 16;
+
+/// Socket options for the listeners of a `MultiListener`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ListenOptions {
+    /// Set `SO_REUSEADDR`, which allows binding while connections from a previous listener on the
+    /// same address linger in `TIME_WAIT`. By default this is only set on Unix, the same as
+    /// `TcpListener::bind` does.
+    pub reuse_address: bool,
+    /// Set `SO_REUSEPORT`, which allows several processes to listen on the same address with the
+    /// kernel spreading the connections between them. Every one of them needs to set it. This is
+    /// ignored on Windows, which has no such option. Off by default.
+    pub reuse_port: bool,
+    /// Set `IP_FREEBIND`, which allows binding addresses not assigned to any interface on this
+    /// machine (yet), e.g. a floating virtual IP a load balancer moves between nodes, which then
+    /// starts accepting connections as soon as the address is moved here. This needs no privileges,
+    /// unlike `IP_TRANSPARENT` which needs `CAP_NET_ADMIN`, but ports below 1024 still need
+    /// `CAP_NET_BIND_SERVICE`. This is ignored on anything but Linux and Android, which have no
+    /// such option. Off by default.
+    pub freebind: bool,
+    /// The maximum number of pending connections.
+    pub backlog: i32,
+}
+
+impl Default for ListenOptions {
+    fn default() -> ListenOptions {
+        ListenOptions {
+            reuse_address: cfg!(unix),
+            reuse_port: false,
+            freebind: false,
+            backlog: 128,
+        }
+    }
+}
+
+/// A set of TCP listeners, one per interface address, from which connections can be accepted as
+/// if it were a single listener.
+pub struct MultiListener {
+    port: u16,
+    options: ListenOptions,
+    listeners: Vec<TcpListener>,
+    watcher: Option<(IfWatcher, Receiver<IfEvent>)>,
+}
+
+impl MultiListener {
+    /// Create a `MultiListener` without any listeners, which will bind them on `port` (or on an
+    /// ephemeral port each if `port` is 0).
+    pub fn new(port: u16) -> MultiListener {
+        MultiListener::with_options(port, ListenOptions::default())
+    }
+
+    /// Create a `MultiListener` without any listeners, which will bind them on `port` with the
+    /// socket options `options`.
+    pub fn with_options(port: u16, options: ListenOptions) -> MultiListener {
+        MultiListener {
+            port: port,
+            options: options,
+            listeners: Vec::new(),
+            watcher: None,
+        }
+    }
+
+    /// Bind a listener on `ip`, returning its local address.
+    pub fn add(&mut self, ip: IpAddr) -> io::Result<SocketAddr> {
+        let listener = try!(bind(&SocketAddr::new(ip, self.port), &self.options));
+        try!(listener.set_nonblocking(true));
+        let local_addr = try!(listener.local_addr());
+        self.listeners.push(listener);
+        Ok(local_addr)
+    }
+
+    /// Drop the listener bound on `ip`, returning whether there was one.
+    pub fn remove(&mut self, ip: &IpAddr) -> bool {
+        let len = self.listeners.len();
+        self.listeners.retain(|listener| match listener.local_addr() {
+            Ok(local_addr) => local_addr.ip() != *ip,
+            Err(_) => false,
+        });
+        self.listeners.len() != len
+    }
+
+    /// Get the local addresses of all the listeners.
+    pub fn local_addrs(&self) -> Vec<SocketAddr> {
+        self.listeners.iter().filter_map(|listener| listener.local_addr().ok()).collect()
+    }
+
+    /// Keep the listeners in line with the interfaces on this machine, checking for changes every
+    /// `interval`: a listener is bound on every address that appears and dropped for every
+    /// address that disappears. The changes are applied from within `accept`.
+    pub fn watch(&mut self, interval: Duration) -> io::Result<()> {
+        let (event_tx, event_rx) = mpsc::channel();
+        let watcher = try!(IfWatcher::start(interval, event_tx));
+        self.watcher = Some((watcher, event_rx));
+        Ok(())
+    }
+
+    /// Bring the listeners in line with `event`.
+    pub fn handle_event(&mut self, event: &IfEvent) -> io::Result<()> {
+        match *event {
+            IfEvent::Added(ref interface) => {
+                let _ = try!(self.add(interface.addr.ip()));
+            }
+            IfEvent::Removed(ref interface) => {
+                let _ = self.remove(&interface.addr.ip());
+            }
+            IfEvent::Changed { .. } |
+            IfEvent::FlagsChanged { .. } => (),
+        }
+        Ok(())
+    }
+
+    /// Block until a connection arrives on any of the listeners and accept it.
+    pub fn accept(&mut self) -> io::Result<(TcpStream, SocketAddr)> {
+        loop {
+            self.apply_events();
+            let timeout = match (self.watcher.is_some(), self.listeners.is_empty()) {
+                (false, true) => {
+                    return Err(io::Error::new(io::ErrorKind::InvalidInput,
+                                              "MultiListener has no listeners to accept from"))
+                }
+                (false, false) => -1,
+                (true, true) => {
+                    thread::sleep(Duration::from_millis(WATCH_POLL_MS));
+                    continue;
+                }
+                (true, false) => WATCH_POLL_MS as i32,
+            };
+
+            for index in try!(poll::readable(&self.listeners, timeout)) {
+                match self.listeners[index].accept() {
+                    Ok((stream, peer_addr)) => {
+                        try!(stream.set_nonblocking(false));
+                        return Ok((stream, peer_addr));
+                    }
+                    // Someone else got there first or the peer went away in the meantime.
+                    Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => (),
+                    Err(e) => return Err(e),
+                }
+            }
+        }
+    }
+
+    fn apply_events(&mut self) {
+        loop {
+            let event = match self.watcher {
+                Some((_, ref event_rx)) => event_rx.try_recv(),
+                None => return,
+            };
+            match event {
+                Ok(event) => {
+                    if let Err(e) = self.handle_event(&event) {
+                        warn!("Could not update listeners after {:?}: {:?}", event, e);
+                    }
+                }
+                Err(TryRecvError::Empty) => return,
+                Err(TryRecvError::Disconnected) => {
+                    self.watcher = None;
+                    return;
+                }
+            }
+        }
+    }
+}
+
+/// Bind a TCP listener on `port` on every address of every interface on this machine.
+///
+/// Addresses which can't be bound are skipped.
+pub fn bind_all(port: u16) -> io::Result<MultiListener> {
+    bind_all_with_options(port, ListenOptions::default())
+}
+
+/// Bind a TCP listener on `port` on every address of every interface on this machine, with the
+/// socket options `options`.
+///
+/// Addresses which can't be bound are skipped.
+pub fn bind_all_with_options(port: u16, options: ListenOptions) -> io::Result<MultiListener> {
+    let mut multi_listener = MultiListener::with_options(port, options);
+    for interface in try!(get_if_addrs()) {
+        if let Err(e) = multi_listener.add(interface.addr.ip()) {
+            warn!("Could not listen on {}: {:?}", interface.addr.ip(), e);
+        }
+    }
+    Ok(multi_listener)
+}
+
+/// Bind a TCP listener on `port` on every address of the family `family` of every interface on
+/// this machine, failing if any of them can't be bound rather than skipping it.
+///
+/// The outer error is that of enumerating the interfaces. Should binding fail, the inner error
+/// lists every interface address which couldn't be bound along with why, e.g. for reporting all
+/// the misconfigurations at once, and none of the listeners are kept.
+pub fn try_bind_all(port: u16,
+                    family: Family)
+                    -> io::Result<Result<MultiListener, Vec<(Interface, io::Error)>>> {
+    try_bind_all_with_options(port, family, ListenOptions::default())
+}
+
+/// Bind a TCP listener on `port` on every address of the family `family` of every interface on
+/// this machine with the socket options `options`, failing as `try_bind_all()` does if any of
+/// them can't be bound.
+pub fn try_bind_all_with_options(port: u16,
+                                 family: Family,
+                                 options: ListenOptions)
+                                 -> io::Result<Result<MultiListener, Vec<(Interface, io::Error)>>> {
+    let ifaces = try!(get_if_addrs())
+        .into_iter()
+        .filter(|interface| interface.addr.family() == family)
+        .collect();
+    Ok(bind_each(port, ifaces, options))
+}
+
+fn bind_each(port: u16,
+             ifaces: Vec<Interface>,
+             options: ListenOptions)
+             -> Result<MultiListener, Vec<(Interface, io::Error)>> {
+    let mut multi_listener = MultiListener::with_options(port, options);
+    let mut errors = Vec::new();
+    for interface in ifaces {
+        if let Err(e) = multi_listener.add(interface.addr.ip()) {
+            errors.push((interface, e));
+        }
+    }
+    if errors.is_empty() {
+        Ok(multi_listener)
+    } else {
+        Err(errors)
+    }
+}
+
+/// Bind a TCP listener on every address of every interface on this machine, all on the same
+/// ephemeral port, and return that port along with the listeners. This way a single port can be
+/// advertised for all of them.
+///
+/// The port is picked by binding to it on the unspecified address first. Should it turn out to be
+/// taken on any of the addresses, all the listeners are dropped and another port is tried, up to
+/// a limit after which this fails with `io::ErrorKind::AddrInUse`. Addresses which can't be bound
+/// for any other reason are skipped, as by `bind_all()`.
+pub fn bind_all_same_port() -> io::Result<(u16, MultiListener)> {
+    let ips: Vec<IpAddr> =
+        try!(get_if_addrs()).iter().map(|interface| interface.addr.ip()).collect();
+    bind_same_port(&ips)
+}
+
+fn bind_same_port(ips: &[IpAddr]) -> io::Result<(u16, MultiListener)> {
+    'ports: for _ in 0..SAME_PORT_ATTEMPTS {
+        // The probe has to go before binding the same port on the interfaces.
+        let port = try!(try!(TcpListener::bind("0.0.0.0:0")).local_addr()).port();
+        let mut multi_listener = MultiListener::new(port);
+        for ip in ips {
+            match multi_listener.add(*ip) {
+                Ok(_) => (),
+                Err(ref e) if e.kind() == io::ErrorKind::AddrInUse => continue 'ports,
+                Err(e) => warn!("Could not listen on {}: {:?}", ip, e),
+            }
+        }
+        return Ok((port, multi_listener));
+    }
+    Err(io::Error::new(io::ErrorKind::AddrInUse,
+                       format!("No port free on all of {} addresses after {} attempts",
+                               ips.len(),
+                               SAME_PORT_ATTEMPTS)))
+}
+
+fn bind(addr: &SocketAddr, options: &ListenOptions) -> io::Result<TcpListener> {
+    let builder = match *addr {
+        SocketAddr::V4(..) => try!(TcpBuilder::new_v4()),
+        SocketAddr::V6(..) => try!(TcpBuilder::new_v6()),
+    };
+    let _ = try!(builder.reuse_address(options.reuse_address));
+    if options.reuse_port {
+        try!(enable_so_reuseport(&builder));
+    }
+    if options.freebind {
+        try!(enable_ip_freebind(&builder));
+    }
+    let _ = try!(builder.bind(addr));
+    builder.listen(options.backlog)
+}
+
+#[cfg(unix)]
+fn enable_so_reuseport(builder: &TcpBuilder) -> io::Result<()> {
+    use net2::unix::UnixTcpBuilderExt;
+    let _ = try!(builder.reuse_port(true));
+    Ok(())
+}
+
+#[cfg(windows)]
+fn enable_so_reuseport(_builder: &TcpBuilder) -> io::Result<()> {
+    Ok(())
+}
+
+// Set at the `IPPROTO_IP` level, which sockets of either family accept it at.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[allow(unsafe_code, trivial_casts)]
+fn enable_ip_freebind(builder: &TcpBuilder) -> io::Result<()> {
+    use libc::{self, c_int, c_void, socklen_t};
+    use std::mem::size_of;
+    use std::os::unix::io::AsRawFd;
+
+    const IP_FREEBIND: c_int = 15;
+    let enable: c_int = 1;
+    let result = unsafe {
+        libc::setsockopt(builder.as_raw_fd(),
+                         libc::IPPROTO_IP,
+                         IP_FREEBIND,
+                         &enable as *const c_int as *const c_void,
+                         size_of::<c_int>() as socklen_t)
+    };
+    if result < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+fn enable_ip_freebind(_builder: &TcpBuilder) -> io::Result<()> {
+    Ok(())
+}
+
+#[cfg(unix)]
+mod poll {
+    use std::io;
+    use std::net::TcpListener;
+    use std::os::unix::io::AsRawFd;
+
+    use libc::{self, POLLIN, c_int, nfds_t, pollfd};
+
+    // Wait up to `timeout` ms (forever if negative) for any of `listeners` to become readable
+    // and return the indices of those that are.
+    #[allow(unsafe_code)]
+    pub fn readable(listeners: &[TcpListener], timeout: c_int) -> io::Result<Vec<usize>> {
+        let mut fds: Vec<pollfd> = listeners.iter()
+            .map(|listener| {
+                pollfd {
+                    fd: listener.as_raw_fd(),
+                    events: POLLIN,
+                    revents: 0,
+                }
+            })
+            .collect();
+        if unsafe { libc::poll(fds.as_mut_ptr(), fds.len() as nfds_t, timeout) } < 0 {
+            let e = io::Error::last_os_error();
+            return if e.kind() == io::ErrorKind::Interrupted {
+                Ok(Vec::new())
+            } else {
+                Err(e)
+            };
+        }
+        Ok(fds.iter()
+            .enumerate()
+            .filter(|&(_, fd)| fd.revents & POLLIN != 0)
+            .map(|(index, _)| index)
+            .collect())
+    }
+}
+
+#[cfg(windows)]
+mod poll {
+    use std::io;
+    use std::net::TcpListener;
+    use std::os::windows::io::AsRawSocket;
+
+    use libc::{c_int, c_short, c_ulong};
+
+    const POLLRDNORM: c_short = 0x100;
+
+    #[repr(C)]
+    struct WsaPollFd {
+        fd: usize,
+        events: c_short,
+        revents: c_short,
+    }
+
+    #[link(name="ws2_32")]
+    extern "system" {
+        fn WSAPoll(fds: *mut WsaPollFd, nfds: c_ulong, timeout: c_int) -> c_int;
+    }
+
+    // Wait up to `timeout` ms (forever if negative) for any of `listeners` to become readable
+    // and return the indices of those that are.
+    #[allow(unsafe_code)]
+    pub fn readable(listeners: &[TcpListener], timeout: c_int) -> io::Result<Vec<usize>> {
+        let mut fds: Vec<WsaPollFd> = listeners.iter()
+            .map(|listener| {
+                WsaPollFd {
+                    fd: listener.as_raw_socket() as usize,
+                    events: POLLRDNORM,
+                    revents: 0,
+                }
+            })
+            .collect();
+        if unsafe { WSAPoll(fds.as_mut_ptr(), fds.len() as c_ulong, timeout) } < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(fds.iter()
+            .enumerate()
+            .filter(|&(_, fd)| fd.revents & POLLRDNORM != 0)
+            .map(|(index, _)| index)
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::io::{Read, Write};
+    use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, TcpStream};
+
+    use common::get_if_addrs::{IfEvent, InterfaceBuilder};
+
+    fn localhost(last_octet: u8) -> IpAddr {
+        IpAddr::V4(Ipv4Addr::new(127, 0, 0, last_octet))
+    }
+
+    fn connect_and_accept(multi_listener: &mut MultiListener, addr: &SocketAddr) {
+        let mut client = unwrap!(TcpStream::connect(addr));
+        let (mut accepted, peer_addr) = unwrap!(multi_listener.accept());
+        assert_eq!(peer_addr, unwrap!(client.local_addr()));
+        assert_eq!(unwrap!(accepted.local_addr()), *addr);
+
+        unwrap!(client.write_all(b"ping"));
+        let mut buf = [0; 4];
+        unwrap!(accepted.read_exact(&mut buf));
+        assert_eq!(&buf, b"ping");
+    }
+
+    #[test]
+    fn accepts_from_any_listener() {
+        let mut multi_listener = MultiListener::new(0);
+        // Only Linux routes all of 127.0.0.0/8 to loopback out of the box.
+        let second = if cfg!(target_os = "linux") {
+            localhost(2)
+        } else {
+            IpAddr::V6(Ipv6Addr::new(0, 0, 0, 0, 0, 0, 0, 1))
+        };
+        let addr0 = unwrap!(multi_listener.add(localhost(1)));
+        let addr1 = unwrap!(multi_listener.add(second));
+
+        connect_and_accept(&mut multi_listener, &addr1);
+        connect_and_accept(&mut multi_listener, &addr0);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn reuse_port_allows_binding_twice() {
+        let reuse_port = ListenOptions { reuse_port: true, ..ListenOptions::default() };
+        let mut first = MultiListener::with_options(0, reuse_port);
+        let port = unwrap!(first.add(localhost(1))).port();
+
+        let mut second = MultiListener::with_options(port, reuse_port);
+        assert!(second.add(localhost(1)).is_ok());
+
+        let mut third = MultiListener::new(port);
+        assert!(third.add(localhost(1)).is_err());
+    }
+
+    #[test]
+    fn binds_the_same_port_everywhere() {
+        let second = if cfg!(target_os = "linux") {
+            localhost(2)
+        } else {
+            IpAddr::V6(Ipv6Addr::new(0, 0, 0, 0, 0, 0, 0, 1))
+        };
+        let (port, mut multi_listener) = unwrap!(bind_same_port(&[localhost(1), second]));
+        let addrs = multi_listener.local_addrs();
+        assert_eq!(addrs,
+                   vec![SocketAddr::new(localhost(1), port), SocketAddr::new(second, port)]);
+        connect_and_accept(&mut multi_listener, &addrs[1]);
+
+        let (port, multi_listener) = unwrap!(bind_all_same_port());
+        assert!(multi_listener.local_addrs().iter().all(|addr| addr.port() == port));
+    }
+
+    #[test]
+    fn reports_every_address_it_cannot_bind() {
+        let second = if cfg!(target_os = "linux") {
+            localhost(2)
+        } else {
+            IpAddr::V6(Ipv6Addr::new(0, 0, 0, 0, 0, 0, 0, 1))
+        };
+        let taken = unwrap!(TcpListener::bind((localhost(1), 0)));
+        let port = unwrap!(taken.local_addr()).port();
+        let interface = |ip| InterfaceBuilder::new().name("lo").addr(ip).build();
+
+        let ifaces = vec![interface(second), interface(localhost(1))];
+        let errors = match bind_each(port, ifaces, ListenOptions::default()) {
+            Ok(_) => panic!("Bound a port which is taken"),
+            Err(errors) => errors,
+        };
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].0, interface(localhost(1)));
+        assert_eq!(errors[0].1.kind(), io::ErrorKind::AddrInUse);
+
+        let multi_listener = unwrap!(unwrap!(try_bind_all(0, Family::V4)));
+        assert!(multi_listener.local_addrs().iter().all(|addr| addr.is_ipv4()));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn freebind_allows_binding_a_non_local_address() {
+        // From `192.0.2.0/24`, reserved for documentation, so not assigned on this machine.
+        let vip = IpAddr::V4(Ipv4Addr::new(192, 0, 2, 77));
+        let mut multi_listener = MultiListener::new(0);
+        match multi_listener.add(vip) {
+            Err(ref e) if e.kind() == io::ErrorKind::AddrNotAvailable => (),
+            result => panic!("Unexpected {:?}", result),
+        }
+
+        let freebind = ListenOptions { freebind: true, ..ListenOptions::default() };
+        let mut multi_listener = MultiListener::with_options(0, freebind);
+        let addr = unwrap!(multi_listener.add(vip));
+        assert_eq!(addr.ip(), vip);
+        assert_eq!(multi_listener.local_addrs(), vec![addr]);
+        let all = unwrap!(unwrap!(try_bind_all_with_options(0, Family::V4, freebind)));
+        assert!(!all.local_addrs().is_empty());
+    }
+
+    #[test]
+    fn addresses_in_use_are_reported() {
+        let held = unwrap!(TcpListener::bind(SocketAddr::new(localhost(1), 0)));
+        let port = unwrap!(held.local_addr()).port();
+        let interface = InterfaceBuilder::new().name("lo").addr(localhost(1)).build();
+
+        let errors = match bind_each(port, vec![interface.clone()], ListenOptions::default()) {
+            Ok(_) => panic!("Bound {}:{} while it was held", localhost(1), port),
+            Err(errors) => errors,
+        };
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].0, interface);
+        assert_eq!(errors[0].1.kind(), io::ErrorKind::AddrInUse);
+
+        drop(held);
+        assert!(bind_each(port, vec![interface], ListenOptions::default()).is_ok());
+    }
+
+    #[test]
+    fn follows_interface_events() {
+        let mut multi_listener = MultiListener::new(0);
+        let interface = InterfaceBuilder::new().name("lo").addr(localhost(1)).build();
+
+        unwrap!(multi_listener.handle_event(&IfEvent::Added(interface.clone())));
+        let addrs = multi_listener.local_addrs();
+        assert_eq!(addrs.len(), 1);
+        connect_and_accept(&mut multi_listener, &addrs[0]);
+
+        unwrap!(multi_listener.handle_event(&IfEvent::Removed(interface)));
+        assert!(multi_listener.local_addrs().is_empty());
+        assert!(multi_listener.accept().is_err());
+    }
+}
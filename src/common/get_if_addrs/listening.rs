@@ -0,0 +1,303 @@
+// The addresses of this machine on which TCP sockets are listening, found by cross-referencing
+// the interface addresses with the listening sockets of the OS. Linux exposes the sockets as text
+// in `/proc/net/tcp` and `/proc/net/tcp6`, Windows through `GetTcpTable2` and `GetTcp6Table2`.
+// The text is parsed from a plain reader so tests can feed it samples.
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+use std::fs::File;
+use std::io;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+use std::io::{BufRead, BufReader};
+#[cfg(any(target_os = "linux", target_os = "android"))]
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::net::SocketAddr;
+
+use common::get_if_addrs::{Interface, get_if_addrs};
+
+/// Get the addresses of the interfaces on this machine on which a TCP socket is listening, each
+/// with the port, e.g. for checking `bind_all()` took effect. A socket listening on the
+/// unspecified address counts as listening on every interface address of its family.
+///
+/// A socket on `::` may or may not accept IPv4 connections too, which can't be told from the
+/// outside, so it only counts for IPv6 addresses. Sockets of any process are included.
+///
+/// Listing the listening sockets is implemented on Linux, Android and Windows: elsewhere this is
+/// always empty.
+pub fn listening_addrs() -> io::Result<Vec<SocketAddr>> {
+    let listeners = try!(read_listeners());
+    if listeners.is_empty() {
+        return Ok(Vec::new());
+    }
+    Ok(cross_reference(&try!(get_if_addrs()), &listeners))
+}
+
+// The addresses the listening TCP sockets of the OS are bound to, with their ports.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn read_listeners() -> io::Result<Vec<SocketAddr>> {
+    let mut listeners = parse_proc_net_tcp(BufReader::new(try!(File::open("/proc/net/tcp"))));
+    // Without IPv6 support there is no `/proc/net/tcp6`.
+    match File::open("/proc/net/tcp6") {
+        Ok(file) => listeners.extend(parse_proc_net_tcp(BufReader::new(file))),
+        Err(ref e) if e.kind() == io::ErrorKind::NotFound => (),
+        Err(e) => return Err(e),
+    }
+    Ok(listeners)
+}
+
+#[cfg(windows)]
+fn read_listeners() -> io::Result<Vec<SocketAddr>> {
+    windows::listeners()
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android", windows)))]
+fn read_listeners() -> io::Result<Vec<SocketAddr>> {
+    Ok(Vec::new())
+}
+
+// The addresses of `ifaces` which one of `listeners` listens on, with its port.
+fn cross_reference(ifaces: &[Interface], listeners: &[SocketAddr]) -> Vec<SocketAddr> {
+    let mut ret = Vec::new();
+    for listener in listeners {
+        for interface in ifaces {
+            let ip = interface.addr.ip();
+            let matches = if listener.ip().is_unspecified() {
+                ip.is_ipv4() == listener.is_ipv4()
+            } else {
+                ip == listener.ip()
+            };
+            let addr = interface.socket_addr(listener.port());
+            if matches && !ret.contains(&addr) {
+                ret.push(addr);
+            }
+        }
+    }
+    ret
+}
+
+// The state of a listening socket.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+const TCP_LISTEN: &'static str = "0A";
+
+// Find the listening sockets in the contents of `/proc/net/tcp` or `/proc/net/tcp6`, which after
+// a header line have a line like `0: 0100007F:0277 00000000:0000 0A 00000000:00000000 ...` per
+// socket, giving the local and the remote address and the state. An address is the IP as native
+// `u32`s printed in hex, one for IPv4 and four for IPv6, and the port in hex.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn parse_proc_net_tcp<R: BufRead>(reader: R) -> Vec<SocketAddr> {
+    fn parse_ip(field: &str) -> Option<IpAddr> {
+        let mut octets = Vec::with_capacity(16);
+        for start in (0..field.len()).step_by(8) {
+            let word = match field.get(start..start + 8) {
+                Some(word) => word,
+                None => return None,
+            };
+            match u32::from_str_radix(word, 16) {
+                Ok(word) => octets.extend_from_slice(&word.to_ne_bytes()),
+                Err(_) => return None,
+            }
+        }
+        match octets.len() {
+            4 => Some(IpAddr::V4(Ipv4Addr::new(octets[0], octets[1], octets[2], octets[3]))),
+            16 => {
+                let mut array = [0; 16];
+                array.copy_from_slice(&octets);
+                Some(IpAddr::V6(Ipv6Addr::from(array)))
+            }
+            _ => None,
+        }
+    }
+
+    let mut listeners = Vec::new();
+    for line in reader.lines().skip(1) {
+        let line = match line {
+            Ok(line) => line,
+            Err(_) => break,
+        };
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.len() < 4 || fields[3] != TCP_LISTEN {
+            continue;
+        }
+        let mut local = fields[1].split(':');
+        let ip = local.next().and_then(parse_ip);
+        let port = local.next().and_then(|port| u16::from_str_radix(port, 16).ok());
+        if let (Some(ip), Some(port)) = (ip, port) {
+            listeners.push(SocketAddr::new(ip, port));
+        }
+    }
+    listeners
+}
+
+#[cfg(windows)]
+mod windows {
+    use std::io;
+    use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+    use std::slice;
+
+    use libc::c_ulong;
+    use winapi::{DWORD, ERROR_SUCCESS};
+
+    // `MIB_TCPROW2`.
+    #[repr(C)]
+    #[derive(Clone, Copy)]
+    struct MibTcpRow2 {
+        state: DWORD,
+        local_addr: DWORD,
+        local_port: DWORD,
+        remote_addr: DWORD,
+        remote_port: DWORD,
+        owning_pid: DWORD,
+        offload_state: DWORD,
+    }
+
+    // `MIB_TCP6ROW2`.
+    #[repr(C)]
+    #[derive(Clone, Copy)]
+    struct MibTcp6Row2 {
+        local_addr: [u8; 16],
+        local_scope_id: DWORD,
+        local_port: DWORD,
+        remote_addr: [u8; 16],
+        remote_scope_id: DWORD,
+        remote_port: DWORD,
+        state: DWORD,
+        owning_pid: DWORD,
+        offload_state: DWORD,
+    }
+
+    // `MIB_TCPTABLE2` and `MIB_TCP6TABLE2`, whose `table` really holds `num_entries` rows.
+    #[repr(C)]
+    struct MibTable<R> {
+        num_entries: DWORD,
+        table: [R; 1],
+    }
+
+    const ERROR_INSUFFICIENT_BUFFER: DWORD = 122;
+    const MIB_TCP_STATE_LISTEN: DWORD = 2;
+
+    #[link(name="Iphlpapi")]
+    extern "system" {
+        fn GetTcpTable2(table: *mut MibTable<MibTcpRow2>, size: *mut c_ulong, order: i32) -> DWORD;
+        fn GetTcp6Table2(table: *mut MibTable<MibTcp6Row2>,
+                         size: *mut c_ulong,
+                         order: i32)
+                         -> DWORD;
+    }
+
+    // The local addresses of the listening sockets. Ports are in network order in the low bytes.
+    #[allow(unsafe_code)]
+    pub fn listeners() -> io::Result<Vec<SocketAddr>> {
+        let mut listeners = Vec::new();
+        for row in try!(rows(|table, size| unsafe { GetTcpTable2(table, size, 0) })) {
+            let row: MibTcpRow2 = row;
+            if row.state == MIB_TCP_STATE_LISTEN {
+                let ip = Ipv4Addr::from(row.local_addr.to_ne_bytes());
+                listeners.push(SocketAddr::new(IpAddr::V4(ip), port(row.local_port)));
+            }
+        }
+        for row in try!(rows(|table, size| unsafe { GetTcp6Table2(table, size, 0) })) {
+            let row: MibTcp6Row2 = row;
+            if row.state == MIB_TCP_STATE_LISTEN {
+                let ip = Ipv6Addr::from(row.local_addr);
+                listeners.push(SocketAddr::new(IpAddr::V6(ip), port(row.local_port)));
+            }
+        }
+        Ok(listeners)
+    }
+
+    fn port(local_port: DWORD) -> u16 {
+        u16::from_be(local_port as u16)
+    }
+
+    // The rows of the table `get` fills in. Sockets can appear in between asking for the size of
+    // the table and for the table, so keep growing the buffer while it's too small.
+    #[allow(unsafe_code)]
+    fn rows<R: Copy, F>(mut get: F) -> io::Result<Vec<R>>
+        where F: FnMut(*mut MibTable<R>, *mut c_ulong) -> DWORD
+    {
+        // A buffer of `u32`s so that it is aligned for the table.
+        let mut buffer: Vec<u32> = vec![0; 1];
+        let mut size: c_ulong = 4;
+        loop {
+            match get(buffer.as_mut_ptr() as *mut MibTable<R>, &mut size) {
+                ERROR_SUCCESS => break,
+                ERROR_INSUFFICIENT_BUFFER => buffer = vec![0; (size as usize + 3) / 4],
+                code => return Err(io::Error::from_raw_os_error(code as i32)),
+            }
+        }
+        let table = buffer.as_ptr() as *const MibTable<R>;
+        Ok(unsafe {
+            slice::from_raw_parts((*table).table.as_ptr(), (*table).num_entries as usize).to_vec()
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::net::{IpAddr, TcpListener};
+    use std::str::FromStr;
+
+    use common::get_if_addrs::InterfaceBuilder;
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    #[test]
+    fn parses_proc_net_tcp() {
+        let sample = "\
+  sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode
+   0: 0100007F:0277 00000000:0000 0A 00000000:00000000 00:00000000 00000000     0        0 \
+17330 1 0000000000000000 100 0 0 10 0
+   1: 00000000:1F90 00000000:0000 0A 00000000:00000000 00:00000000 00000000  1000        0 \
+21151 1 0000000000000000 100 0 0 10 0
+   2: 0501A8C0:D2F0 0101A8C0:01BB 01 00000000:00000000 00:00000000 00000000  1000        0 \
+30512 1 0000000000000000 20 4 30 10 -1
+   3: zz00007F:0277 00000000:0000 0A 00000000:00000000 00:00000000 00000000     0        0 \
+17331 1 0000000000000000 100 0 0 10 0
+";
+        assert_eq!(parse_proc_net_tcp(sample.as_bytes()),
+                   vec![unwrap!(SocketAddr::from_str("127.0.0.1:631")),
+                        unwrap!(SocketAddr::from_str("0.0.0.0:8080"))]);
+
+        let sample6 = "  sl  local_address                         remote_address                \
+                       st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode
+   0: 00000000000000000000000001000000:0277 00000000000000000000000000000000:0000 0A \
+00000000:00000000 00:00000000 00000000     0        0 17329 1 0000000000000000 100 0 0 10 0
+   1: B80D0120000000000000000005000000:1F90 00000000000000000000000000000000:0000 0A \
+00000000:00000000 00:00000000 00000000     0        0 17332 1 0000000000000000 100 0 0 10 0
+";
+        assert_eq!(parse_proc_net_tcp(sample6.as_bytes()),
+                   vec![unwrap!(SocketAddr::from_str("[::1]:631")),
+                        unwrap!(SocketAddr::from_str("[2001:db8::5]:8080"))]);
+    }
+
+    #[test]
+    fn cross_references_interfaces() {
+        let interface = |ip| {
+            InterfaceBuilder::new().addr(unwrap!(IpAddr::from_str(ip))).index(2).build()
+        };
+        let ifaces = [interface("127.0.0.1"),
+                      interface("192.168.1.5"),
+                      interface("::1"),
+                      interface("fe80::5")];
+        let listeners = [unwrap!(SocketAddr::from_str("127.0.0.1:631")),
+                         unwrap!(SocketAddr::from_str("10.0.0.1:22")),
+                         unwrap!(SocketAddr::from_str("0.0.0.0:8080")),
+                         unwrap!(SocketAddr::from_str("[::]:8080"))];
+        assert_eq!(cross_reference(&ifaces, &listeners),
+                   vec![unwrap!(SocketAddr::from_str("127.0.0.1:631")),
+                        unwrap!(SocketAddr::from_str("127.0.0.1:8080")),
+                        unwrap!(SocketAddr::from_str("192.168.1.5:8080")),
+                        unwrap!(SocketAddr::from_str("[::1]:8080")),
+                        ifaces[3].socket_addr(8080)]);
+    }
+
+    #[test]
+    fn finds_a_listener_of_our_own() {
+        let listener = unwrap!(TcpListener::bind("127.0.0.1:0"));
+        let addr = unwrap!(listener.local_addr());
+        let listening = unwrap!(listening_addrs());
+        if cfg!(any(target_os = "linux", target_os = "android", windows)) {
+            assert!(listening.contains(&addr), "{} not in {:?}", addr, listening);
+        }
+    }
+}
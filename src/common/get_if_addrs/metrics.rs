@@ -0,0 +1,112 @@
+// Metrics about the interfaces on this machine in the Prometheus text exposition format, for
+// serving from an existing metrics endpoint.
+
+use std::io;
+
+use common::get_if_addrs::{Family, IfFlags, Interface, get_if_addrs_all, group_by_name};
+
+/// Render the interfaces on this machine, including those which are down, as metrics in the
+/// Prometheus text exposition format:
+///
+/// - `crust_interface_up{name="eth0"}` is 1 if the interface is up and 0 otherwise.
+/// - `crust_interface_running{name="eth0"}` is 1 if the interface is operational and 0 otherwise.
+/// - `crust_interface_addresses{name="eth0",family="ipv4"}` is the number of addresses of a
+///   family assigned to the interface, for each family it has any of.
+pub fn interface_metrics_text() -> io::Result<String> {
+    Ok(render(try!(get_if_addrs_all())))
+}
+
+// The metrics telling whether an interface has a flag set, with their help texts.
+const FLAG_METRICS: [(&'static str, &'static str, IfFlags); 2] =
+    [("crust_interface_up", "Whether the interface is up.", IfFlags::UP),
+     ("crust_interface_running", "Whether the interface is operational.", IfFlags::RUNNING)];
+
+fn render(ifaces: Vec<Interface>) -> String {
+    let groups = group_by_name(ifaces);
+    let mut text = String::new();
+
+    for &(metric, help, flag) in &FLAG_METRICS {
+        header(&mut text, metric, help);
+        for &(ref name, ref group) in &groups {
+            // Flags are a property of the interface, so any of its addresses tells.
+            let set = group.iter().any(|interface| interface.flags.contains(flag));
+            text.push_str(&format!("{}{{name=\"{}\"}} {}\n",
+                                   metric,
+                                   escape(name),
+                                   if set { 1 } else { 0 }));
+        }
+    }
+
+    header(&mut text,
+           "crust_interface_addresses",
+           "The number of addresses of a family assigned to the interface.");
+    for &(ref name, ref group) in &groups {
+        for &(family, label) in &[(Family::V4, "ipv4"), (Family::V6, "ipv6")] {
+            let count = group.iter().filter(|interface| interface.addr.family() == family).count();
+            if count > 0 {
+                text.push_str(&format!("crust_interface_addresses{{name=\"{}\",family=\"{}\"}} \
+                                        {}\n",
+                                       escape(name),
+                                       label,
+                                       count));
+            }
+        }
+    }
+    text
+}
+
+fn header(text: &mut String, metric: &str, help: &str) {
+    text.push_str(&format!("# HELP {} {}\n# TYPE {} gauge\n", metric, help, metric));
+}
+
+// Escape `value` for use as a label value, as the exposition format requires of backslashes,
+// double quotes and line feeds.
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use common::get_if_addrs::test_util::interface;
+
+    #[test]
+    fn metrics_of_a_mock_interface_set() {
+        let v6_netmask = "ffff:ffff:ffff:ffff::";
+        let mut ifaces = vec![interface("eth0", "192.168.1.5", "255.255.255.0"),
+                              interface("eth0", "2001:db8::5", v6_netmask),
+                              interface("eth0", "fe80::5", v6_netmask),
+                              interface("wlan0", "10.0.0.5", "255.255.255.0"),
+                              interface("eth\"1", "10.1.0.5", "255.255.255.0")];
+        for interface in &mut ifaces[..3] {
+            interface.flags = IfFlags::UP | IfFlags::RUNNING;
+        }
+        ifaces[3].flags = IfFlags::UP;
+        let text = render(ifaces);
+        let lines: Vec<&str> = text.lines().collect();
+
+        for line in &["# TYPE crust_interface_up gauge",
+                      "crust_interface_up{name=\"eth0\"} 1",
+                      "crust_interface_up{name=\"wlan0\"} 1",
+                      "crust_interface_up{name=\"eth\\\"1\"} 0",
+                      "crust_interface_running{name=\"eth0\"} 1",
+                      "crust_interface_running{name=\"wlan0\"} 0",
+                      "crust_interface_addresses{name=\"eth0\",family=\"ipv4\"} 1",
+                      "crust_interface_addresses{name=\"eth0\",family=\"ipv6\"} 2",
+                      "crust_interface_addresses{name=\"wlan0\",family=\"ipv4\"} 1"] {
+            assert!(lines.contains(line), "Missing {:?} in:\n{}", line, text);
+        }
+        assert!(!text.contains("name=\"wlan0\",family=\"ipv6\""));
+        assert!(lines.iter().all(|line| line.starts_with("# ") || line.starts_with("crust_")));
+    }
+
+    #[test]
+    fn metrics_of_this_machine() {
+        let text = unwrap!(interface_metrics_text());
+        let names = unwrap!(get_if_addrs_all()).into_iter().map(|interface| interface.name);
+        for name in names {
+            assert!(text.contains(&format!("crust_interface_up{{name=\"{}\"}}", escape(&name))));
+        }
+    }
+}
@@ -0,0 +1,1767 @@
+//! Enumeration of the network interfaces on this host along with their addresses.
+
+use std::borrow::Cow;
+use std::hash::Hasher;
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6, TcpListener};
+use std::ops::BitOr;
+
+#[cfg(feature = "socket2")]
+use socket2;
+
+use self::watcher::Fnv1a;
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub use self::arp::addr_conflict;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub use self::netlink::get_if_addrs_netlink;
+pub use self::advertise::{decode_advertised, encode_advertised};
+pub use self::classify::{Routability, V6Scope, addr_eq_ignoring_scope, without_scope};
+pub use self::container::{IN_CONTAINER_ENV, in_container};
+pub use self::error::{IfError, get_if_addrs_checked};
+#[cfg(feature = "ffi")]
+pub use self::ffi::{CIFADDR_NAME_LEN, CIfAddr, crust_getifaddrs};
+#[cfg(feature = "async")]
+pub use self::future::{GetIfAddrsFuture, InterfaceSource, SystemSource, get_if_addrs_async};
+pub use self::ice::{CandidateOptions, HostCandidate, gather_host_candidates,
+                    gather_host_candidates_with_options};
+pub use self::listener::{ListenOptions, MultiListener, bind_all, bind_all_same_port,
+                         bind_all_with_options, try_bind_all, try_bind_all_with_options};
+pub use self::listening::listening_addrs;
+pub use self::metrics::interface_metrics_text;
+pub use self::neighbors::neighbor_count;
+pub use self::pack::{pack, unpack};
+pub use self::policy::{SelectionPolicy, select};
+pub use self::punch::hole_punch_socket;
+pub use self::report::diagnostic_report;
+pub use self::route::default_gateways;
+pub use self::select::{EnumOrder, ReconcileResult, beacon_targets, broadcast_capable_interfaces,
+                       default_interface, get_if_addrs_ordered, get_if_addrs_scored, group_by_name,
+                       group_by_network, interface_for_gateway, interfaces_compatible,
+                       is_multihomed, listen_addrs_excluding, listen_addrs_excluding_addrs,
+                       loopback_interfaces, on_link_interfaces, overlapping_subnets,
+                       recommended_bind_addrs, reconcile_advertised, representative_addresses,
+                       same_nat_candidates, stun_source, subnet_representatives};
+pub use self::snapshot::{load_snapshot, save_snapshot};
+pub use self::source::ordered_source_addrs;
+pub use self::stats::{IfStats, interface_stats};
+#[cfg(feature = "async")]
+pub use self::stream::{InterfaceStream, STREAM_POLL_MS, interface_stream};
+pub use self::watcher::{IfEvent, IfWatcher, InterfacesDiff, NetworkToken, SHARED_WATCHER_POLL_MS,
+                        Snapshot, WAIT_POLL_MS, WatcherHandle, assert_interfaces_match,
+                        changed_since, diff_events, network_fingerprint, new_addresses,
+                        same_network, shared_watcher, wait_for_addr};
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+mod arp;
+mod advertise;
+mod classify;
+mod container;
+mod error;
+#[cfg(feature = "ffi")]
+mod ffi;
+#[cfg(feature = "async")]
+mod future;
+mod ice;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+mod ifconf;
+mod listener;
+mod listening;
+mod metrics;
+mod neighbors;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+mod netlink;
+#[cfg(not(windows))]
+mod posix;
+mod pack;
+mod policy;
+mod punch;
+mod report;
+mod route;
+mod select;
+mod snapshot;
+mod sockaddr;
+mod source;
+mod stats;
+#[cfg(feature = "async")]
+mod stream;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+mod sysfs;
+#[cfg(test)]
+mod test_util;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+mod vlan;
+mod watcher;
+#[cfg(windows)]
+mod windows;
+
+/// Details about an interface on this host
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub struct Interface {
+    /// The name of the interface.
+    pub name: String,
+    /// The address details of the interface.
+    pub addr: IfAddr,
+    /// The index the OS uses to identify the interface, or 0 if it is not known.
+    pub index: u32,
+    /// The state and capabilities of the interface.
+    pub flags: IfFlags,
+    /// How many more seconds the address remains preferred for new connections, with `u32::MAX`
+    /// meaning forever. Only known when enumerating through `get_if_addrs_netlink()`.
+    pub preferred_lft: Option<u32>,
+    /// How many more seconds the address remains assigned, with `u32::MAX` meaning forever. Only
+    /// known when enumerating through `get_if_addrs_netlink()`.
+    pub valid_lft: Option<u32>,
+    /// The state of the address itself, as opposed to that of the interface. Only known when
+    /// enumerating through `get_if_addrs_netlink()`, except for `AddrFlags::ANYCAST` which is
+    /// only known on Windows and `AddrFlags::NO_NETMASK` which is set wherever it applies.
+    pub addr_flags: AddrFlags,
+    /// The DNS suffix of the adapter the address is assigned to. Only known on Windows.
+    pub dns_suffix: Option<String>,
+    /// The description of the adapter the address is assigned to, typically naming the hardware,
+    /// e.g. `Intel(R) Ethernet Connection I219-V`. Only known on Windows.
+    pub description: Option<String>,
+    /// The 802.1Q VLAN id of the interface, e.g. 100 for `eth0.100`, or `None` if it isn't a VLAN
+    /// interface. Only known on Linux.
+    pub vlan_id: Option<u16>,
+    /// The address at the other end of a point-to-point link such as a WireGuard or `utun` tunnel,
+    /// which has no broadcast address. Not known on Windows.
+    pub peer: Option<IpAddr>,
+    /// How the address came to be assigned. Only known on Windows and, as a best guess, when
+    /// enumerating through `get_if_addrs_netlink()`: elsewhere this is `AddrOrigin::Other`.
+    pub origin: AddrOrigin,
+    /// The metric the OS ranks the interface by for traffic of the family of the address, lower
+    /// being preferred: the interface metric of the adapter on Windows, and when enumerating
+    /// through `get_if_addrs_netlink()` the lowest metric of the default routes through the
+    /// interface, `None` if no default route goes through it. Not known elsewhere.
+    pub metric: Option<u32>,
+}
+
+/// A view of an interface on this host whose name borrows from the list the OS returns, as handed
+/// out by `for_each_interface_ref()`. Only the details `get_if_addrs()` reports on every platform
+/// are included.
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub struct InterfaceRef<'a> {
+    /// The name of the interface. This borrows from the list the OS returns where the name is
+    /// valid UTF-8 there and is a copy otherwise.
+    pub name: Cow<'a, str>,
+    /// The address details of the interface.
+    pub addr: IfAddr,
+    /// The index the OS uses to identify the interface, or 0 if it is not known.
+    pub index: u32,
+    /// The state and capabilities of the interface.
+    pub flags: IfFlags,
+}
+
+/// Flags describing the state and capabilities of an interface.
+///
+/// The bit values are our own and don't necessarily match the platform's `IFF_*` constants.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Default)]
+pub struct IfFlags(u32);
+
+/// Flags describing the state of an address assigned to an interface.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Default)]
+pub struct AddrFlags(u32);
+
+/// How an address came to be assigned to an interface.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum AddrOrigin {
+    /// Leased from a DHCP server.
+    Dhcp,
+    /// Configured statically.
+    Manual,
+    /// Self-assigned from a link-local range, `169.254.0.0/16` or `fe80::/10`.
+    LinkLocal,
+    /// Assigned by the OS itself, like the loopback addresses.
+    WellKnown,
+    /// Assigned otherwise, e.g. by IPv6 stateless autoconfiguration, or not known.
+    Other,
+}
+
+/// An IP address family.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum Family {
+    /// IPv4.
+    V4,
+    /// IPv6.
+    V6,
+}
+
+/// Details about the address of an interface on this host
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub enum IfAddr {
+    /// This is an Ipv4 interface.
+    V4(Ifv4Addr),
+    /// This is an Ipv6 interface.
+    V6(Ifv6Addr),
+}
+
+/// Details about the ipv4 address of an interface on this host
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub struct Ifv4Addr {
+    /// The IP address of the interface.
+    pub ip: Ipv4Addr,
+    /// The netmask of the interface.
+    pub netmask: Ipv4Addr,
+    /// The broadcast address of the interface.
+    pub broadcast: Option<Ipv4Addr>,
+}
+
+/// Details about the ipv6 address of an interface on this host
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub struct Ifv6Addr {
+    /// The IP address of the interface.
+    pub ip: Ipv6Addr,
+    /// The netmask of the interface.
+    pub netmask: Ipv6Addr,
+    /// The broadcast address of the interface.
+    pub broadcast: Option<Ipv6Addr>,
+}
+
+/// Builds an `Interface` piece by piece, mainly to construct synthetic interfaces for tests.
+///
+/// The netmask and broadcast address are ignored unless they are of the same family as the
+/// address. Anything left unset takes the same value enumeration uses when the OS doesn't report
+/// it.
+///
+/// ```
+/// # extern crate crust;
+/// # fn main() {
+/// use crust::get_if_addrs::{IfFlags, InterfaceBuilder};
+/// use std::net::{IpAddr, Ipv4Addr};
+///
+/// let eth0 = InterfaceBuilder::new()
+///     .name("eth0")
+///     .addr(IpAddr::V4(Ipv4Addr::new(192, 168, 1, 5)))
+///     .netmask(IpAddr::V4(Ipv4Addr::new(255, 255, 255, 0)))
+///     .broadcast(IpAddr::V4(Ipv4Addr::new(192, 168, 1, 255)))
+///     .flags(IfFlags::UP | IfFlags::BROADCAST | IfFlags::MULTICAST)
+///     .index(2)
+///     .build();
+///
+/// assert_eq!(eth0.name, "eth0");
+/// assert_eq!(eth0.addr.ip(), IpAddr::V4(Ipv4Addr::new(192, 168, 1, 5)));
+/// assert!(eth0.flags.contains(IfFlags::UP));
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct InterfaceBuilder {
+    name: String,
+    addr: IpAddr,
+    netmask: Option<IpAddr>,
+    broadcast: Option<IpAddr>,
+    flags: IfFlags,
+    index: u32,
+    preferred_lft: Option<u32>,
+    valid_lft: Option<u32>,
+    addr_flags: AddrFlags,
+    dns_suffix: Option<String>,
+    description: Option<String>,
+    vlan_id: Option<u16>,
+    peer: Option<IpAddr>,
+    origin: AddrOrigin,
+    metric: Option<u32>,
+}
+
+// The prefixes of the names the tunnel drivers give their interfaces: `utun` on macOS and iOS,
+// `wg` by convention for WireGuard, `tun` for TUN devices and `ppp` for PPP links.
+const TUNNEL_PREFIXES: [&'static str; 4] = ["utun", "wg", "tun", "ppp"];
+
+impl Interface {
+    /// Check whether this is a loopback interface.
+    pub fn is_loopback(&self) -> bool {
+        self.addr.is_loopback()
+    }
+
+    /// Check whether this is a tunnel interface, such as a WireGuard, `utun`, TUN or PPP one. These
+    /// are point-to-point links, which is told by their flags, or else by the names their drivers
+    /// give them where the flags aren't known.
+    pub fn is_tunnel(&self) -> bool {
+        self.flags.contains(IfFlags::POINT_TO_POINT) || self.peer.is_some() ||
+        TUNNEL_PREFIXES.iter().any(|prefix| self.name.starts_with(prefix))
+    }
+
+    /// Get the netmask of this interface address, or `None` if the OS didn't report one, in which
+    /// case `addr` has a placeholder netmask of all zeros.
+    pub fn netmask(&self) -> Option<IpAddr> {
+        if self.addr_flags.contains(AddrFlags::NO_NETMASK) {
+            return None;
+        }
+        Some(match self.addr {
+            IfAddr::V4(ref ifv4_addr) => IpAddr::V4(ifv4_addr.netmask),
+            IfAddr::V6(ref ifv6_addr) => IpAddr::V6(ifv6_addr.netmask),
+        })
+    }
+
+    /// Get the socket address for `port` on this interface address. Link-local IPv6 addresses get
+    /// the index of the interface as their scope id, without which they can't be used.
+    pub fn socket_addr(&self, port: u16) -> SocketAddr {
+        match self.addr {
+            IfAddr::V4(ref ifv4_addr) => SocketAddr::V4(SocketAddrV4::new(ifv4_addr.ip, port)),
+            IfAddr::V6(ref ifv6_addr) => {
+                let scope_id = match V6Scope::of(&ifv6_addr.ip) {
+                    V6Scope::LinkLocal => self.index,
+                    _ => 0,
+                };
+                SocketAddr::V6(SocketAddrV6::new(ifv6_addr.ip, port, 0, scope_id))
+            }
+        }
+    }
+
+    /// Get the socket address of this interface address with port 0, as by `socket_addr(0)`, for
+    /// binding on an ephemeral port or setting the port later. Link-local IPv6 addresses keep the
+    /// index of the interface as their scope id.
+    pub fn as_unspecified_socket(&self) -> SocketAddr {
+        self.socket_addr(0)
+    }
+
+    /// Bind a TCP listener on `port` on this interface address, as by `socket_addr()` so that
+    /// link-local IPv6 addresses get their scope id, to check that the address can actually be
+    /// used right now before advertising it: it may have been removed since it was enumerated, or
+    /// still be tentative. Port 0 binds an ephemeral port.
+    pub fn try_bind(&self, port: u16) -> io::Result<TcpListener> {
+        TcpListener::bind(self.socket_addr(port))
+    }
+
+    /// Get an identifier of the interface which stays the same across reboots, e.g. for keying
+    /// configuration, unlike its name or index which can change.
+    ///
+    /// Where the hardware address of the interface is known, which is only on Linux, the
+    /// identifier is derived from it, e.g. `mac-525400123456`. Otherwise it is a hash of the name
+    /// and the network of the address, e.g. `net-8c3f0b1d2e4a5967`, which stays the same as long
+    /// as neither changes.
+    pub fn stable_id(&self) -> String {
+        self.stable_id_with(mac_address(&self.name))
+    }
+
+    fn stable_id_with(&self, mac: Option<[u8; 6]>) -> String {
+        if let Some(mac) = mac {
+            return format!("mac-{:02x}{:02x}{:02x}{:02x}{:02x}{:02x}",
+                           mac[0],
+                           mac[1],
+                           mac[2],
+                           mac[3],
+                           mac[4],
+                           mac[5]);
+        }
+        let mut hasher = Fnv1a::default();
+        hasher.write(self.name.as_bytes());
+        hasher.write_u8(0);
+        match self.addr.network() {
+            IpAddr::V4(network) => hasher.write(&network.octets()),
+            IpAddr::V6(network) => hasher.write(&network.octets()),
+        }
+        hasher.write_u8(self.addr.prefix_len().unwrap_or(0xff));
+        format!("net-{:016x}", hasher.finish())
+    }
+
+    /// Get the same as `socket_addr()` as a `socket2::SockAddr`, for creating sockets with
+    /// `socket2`.
+    #[cfg(feature = "socket2")]
+    pub fn sock_addr(&self, port: u16) -> socket2::SockAddr {
+        socket2::SockAddr::from(self.socket_addr(port))
+    }
+}
+
+impl<'a> InterfaceRef<'a> {
+    // A view of `interface` owning its name, for the enumerations not borrowing from the OS.
+    fn owning(interface: Interface) -> InterfaceRef<'a> {
+        InterfaceRef {
+            name: Cow::Owned(interface.name),
+            addr: interface.addr,
+            index: interface.index,
+            flags: interface.flags,
+        }
+    }
+}
+
+impl IfFlags {
+    /// The interface is administratively up.
+    pub const UP: IfFlags = IfFlags(0x1);
+    /// The interface supports broadcast.
+    pub const BROADCAST: IfFlags = IfFlags(0x2);
+    /// The interface is a loopback interface.
+    pub const LOOPBACK: IfFlags = IfFlags(0x4);
+    /// The interface is a point-to-point link.
+    pub const POINT_TO_POINT: IfFlags = IfFlags(0x8);
+    /// The interface is operational, i.e. it has resources allocated and a carrier.
+    pub const RUNNING: IfFlags = IfFlags(0x10);
+    /// The interface supports multicast.
+    pub const MULTICAST: IfFlags = IfFlags(0x20);
+
+    /// No flags set.
+    pub fn empty() -> IfFlags {
+        IfFlags(0)
+    }
+
+    /// The raw bits of the flags.
+    pub fn bits(&self) -> u32 {
+        self.0
+    }
+
+    /// Check whether no flags are set.
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    /// Check whether all the flags set in `other` are also set in `self`.
+    pub fn contains(&self, other: IfFlags) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Set all the flags in `other`.
+    pub fn insert(&mut self, other: IfFlags) {
+        self.0 |= other.0;
+    }
+}
+
+impl BitOr for IfFlags {
+    type Output = IfFlags;
+
+    fn bitor(self, rhs: IfFlags) -> IfFlags {
+        IfFlags(self.0 | rhs.0)
+    }
+}
+
+impl AddrFlags {
+    /// The address is a temporary (privacy) IPv6 address which will be rotated.
+    pub const TEMPORARY: AddrFlags = AddrFlags(0x1);
+    /// The address is deprecated: still valid, but it shouldn't be used for new connections.
+    pub const DEPRECATED: AddrFlags = AddrFlags(0x2);
+    /// The IPv6 address is tentative: Duplicate Address Detection hasn't finished yet, and until it
+    /// has binding to the address fails.
+    pub const TENTATIVE: AddrFlags = AddrFlags(0x4);
+    /// Duplicate Address Detection found the IPv6 address to be in use by another node, so it
+    /// can't be used.
+    pub const DAD_FAILED: AddrFlags = AddrFlags(0x8);
+    /// The address is also assigned to other hosts, which share it as an anycast address, so it
+    /// doesn't identify this one.
+    pub const ANYCAST: AddrFlags = AddrFlags(0x10);
+    /// The OS reported no netmask for the address, which then has one of all zeros as a
+    /// placeholder: its prefix length and network don't tell anything, nor does whether it
+    /// `contains()` an address. `Interface::netmask()` is `None` for such an address.
+    pub const NO_NETMASK: AddrFlags = AddrFlags(0x20);
+
+    /// No flags set.
+    pub fn empty() -> AddrFlags {
+        AddrFlags(0)
+    }
+
+    /// The raw bits of the flags.
+    pub fn bits(&self) -> u32 {
+        self.0
+    }
+
+    /// Check whether no flags are set.
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    /// Check whether all the flags set in `other` are also set in `self`.
+    pub fn contains(&self, other: AddrFlags) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Set all the flags in `other`.
+    pub fn insert(&mut self, other: AddrFlags) {
+        self.0 |= other.0;
+    }
+}
+
+impl BitOr for AddrFlags {
+    type Output = AddrFlags;
+
+    fn bitor(self, rhs: AddrFlags) -> AddrFlags {
+        AddrFlags(self.0 | rhs.0)
+    }
+}
+
+impl InterfaceBuilder {
+    /// Start building an unnamed interface with the unspecified address `0.0.0.0`.
+    pub fn new() -> InterfaceBuilder {
+        InterfaceBuilder {
+            name: String::new(),
+            addr: IpAddr::V4(Ipv4Addr::new(0, 0, 0, 0)),
+            netmask: None,
+            broadcast: None,
+            flags: IfFlags::empty(),
+            index: 0,
+            preferred_lft: None,
+            valid_lft: None,
+            addr_flags: AddrFlags::empty(),
+            dns_suffix: None,
+            description: None,
+            vlan_id: None,
+            peer: None,
+            origin: AddrOrigin::Other,
+            metric: None,
+        }
+    }
+
+    /// Set the name of the interface.
+    pub fn name(mut self, name: &str) -> InterfaceBuilder {
+        self.name = name.to_string();
+        self
+    }
+
+    /// Set the IP address of the interface.
+    pub fn addr(mut self, addr: IpAddr) -> InterfaceBuilder {
+        self.addr = addr;
+        self
+    }
+
+    /// Set the netmask of the interface.
+    pub fn netmask(mut self, netmask: IpAddr) -> InterfaceBuilder {
+        self.netmask = Some(netmask);
+        self
+    }
+
+    /// Set the broadcast address of the interface.
+    pub fn broadcast(mut self, broadcast: IpAddr) -> InterfaceBuilder {
+        self.broadcast = Some(broadcast);
+        self
+    }
+
+    /// Set the flags of the interface.
+    pub fn flags(mut self, flags: IfFlags) -> InterfaceBuilder {
+        self.flags = flags;
+        self
+    }
+
+    /// Set the OS index of the interface.
+    pub fn index(mut self, index: u32) -> InterfaceBuilder {
+        self.index = index;
+        self
+    }
+
+    /// Set the preferred and valid lifetimes of the address, in seconds.
+    pub fn lifetimes(mut self, preferred_lft: u32, valid_lft: u32) -> InterfaceBuilder {
+        self.preferred_lft = Some(preferred_lft);
+        self.valid_lft = Some(valid_lft);
+        self
+    }
+
+    /// Set the flags of the address.
+    pub fn addr_flags(mut self, addr_flags: AddrFlags) -> InterfaceBuilder {
+        self.addr_flags = addr_flags;
+        self
+    }
+
+    /// Set the DNS suffix of the adapter.
+    pub fn dns_suffix(mut self, dns_suffix: &str) -> InterfaceBuilder {
+        self.dns_suffix = Some(dns_suffix.to_string());
+        self
+    }
+
+    /// Set the description of the adapter.
+    pub fn description(mut self, description: &str) -> InterfaceBuilder {
+        self.description = Some(description.to_string());
+        self
+    }
+
+    /// Set the VLAN id of the interface.
+    pub fn vlan_id(mut self, vlan_id: u16) -> InterfaceBuilder {
+        self.vlan_id = Some(vlan_id);
+        self
+    }
+
+    /// Set the address at the other end of the point-to-point link.
+    pub fn peer(mut self, peer: IpAddr) -> InterfaceBuilder {
+        self.peer = Some(peer);
+        self
+    }
+
+    /// Set how the address came to be assigned.
+    pub fn origin(mut self, origin: AddrOrigin) -> InterfaceBuilder {
+        self.origin = origin;
+        self
+    }
+
+    /// Set the metric of the interface.
+    pub fn metric(mut self, metric: u32) -> InterfaceBuilder {
+        self.metric = Some(metric);
+        self
+    }
+
+    /// Build the `Interface`.
+    pub fn build(self) -> Interface {
+        let addr = match self.addr {
+            IpAddr::V4(ip) => {
+                IfAddr::V4(Ifv4Addr {
+                    ip: ip,
+                    netmask: match self.netmask {
+                        Some(IpAddr::V4(netmask)) => netmask,
+                        _ => Ipv4Addr::new(0, 0, 0, 0),
+                    },
+                    broadcast: match self.broadcast {
+                        Some(IpAddr::V4(broadcast)) => Some(broadcast),
+                        _ => None,
+                    },
+                })
+            }
+            IpAddr::V6(ip) => {
+                IfAddr::V6(Ifv6Addr {
+                    ip: ip,
+                    netmask: match self.netmask {
+                        Some(IpAddr::V6(netmask)) => netmask,
+                        _ => Ipv6Addr::new(0, 0, 0, 0, 0, 0, 0, 0),
+                    },
+                    broadcast: match self.broadcast {
+                        Some(IpAddr::V6(broadcast)) => Some(broadcast),
+                        _ => None,
+                    },
+                })
+            }
+        };
+
+        Interface {
+            name: self.name,
+            addr: addr,
+            index: self.index,
+            flags: self.flags,
+            preferred_lft: self.preferred_lft,
+            valid_lft: self.valid_lft,
+            addr_flags: self.addr_flags,
+            dns_suffix: self.dns_suffix,
+            description: self.description,
+            vlan_id: self.vlan_id,
+            peer: self.peer,
+            origin: self.origin,
+            metric: self.metric,
+        }
+    }
+}
+
+impl Default for InterfaceBuilder {
+    fn default() -> InterfaceBuilder {
+        InterfaceBuilder::new()
+    }
+}
+
+impl IfAddr {
+    /// Check whether this is a loopback address.
+    pub fn is_loopback(&self) -> bool {
+        match *self {
+            IfAddr::V4(ref ifv4_addr) => ifv4_addr.is_loopback(),
+            IfAddr::V6(ref ifv6_addr) => ifv6_addr.is_loopback(),
+        }
+    }
+
+    /// Get the family of this address.
+    pub fn family(&self) -> Family {
+        match *self {
+            IfAddr::V4(_) => Family::V4,
+            IfAddr::V6(_) => Family::V6,
+        }
+    }
+
+    /// Get the IP address of this interface address.
+    pub fn ip(&self) -> IpAddr {
+        match *self {
+            IfAddr::V4(ref ifv4_addr) => IpAddr::V4(ifv4_addr.ip),
+            IfAddr::V6(ref ifv6_addr) => IpAddr::V6(ifv6_addr.ip),
+        }
+    }
+
+    /// Get the length of the network prefix, or `None` if the netmask isn't contiguous.
+    pub fn prefix_len(&self) -> Option<u8> {
+        match *self {
+            IfAddr::V4(ref ifv4_addr) => ifv4_addr.prefix_len(),
+            IfAddr::V6(ref ifv6_addr) => ifv6_addr.prefix_len(),
+        }
+    }
+
+    /// Render this interface address in CIDR notation, e.g. `192.168.1.5/24`, or get `None` if
+    /// the netmask isn't contiguous.
+    pub fn cidr_string(&self) -> Option<String> {
+        self.prefix_len().map(|prefix_len| format!("{}/{}", self.ip(), prefix_len))
+    }
+
+    /// Get the name a reverse DNS lookup of this address would query, e.g.
+    /// `5.1.168.192.in-addr.arpa` for `192.168.1.5`. No lookup is made.
+    pub fn ptr_name(&self) -> String {
+        match *self {
+            IfAddr::V4(ref ifv4_addr) => {
+                let octets = ifv4_addr.ip.octets();
+                format!("{}.{}.{}.{}.in-addr.arpa",
+                        octets[3],
+                        octets[2],
+                        octets[1],
+                        octets[0])
+            }
+            IfAddr::V6(ref ifv6_addr) => {
+                let mut name = String::with_capacity(72);
+                for octet in ifv6_addr.ip.octets().iter().rev() {
+                    name.push_str(&format!("{:x}.{:x}.", octet & 0xf, octet >> 4));
+                }
+                name.push_str("ip6.arpa");
+                name
+            }
+        }
+    }
+
+    /// Get the address of the network this interface address is on.
+    pub fn network(&self) -> IpAddr {
+        match *self {
+            IfAddr::V4(ref ifv4_addr) => IpAddr::V4(ifv4_addr.network()),
+            IfAddr::V6(ref ifv6_addr) => IpAddr::V6(ifv6_addr.network()),
+        }
+    }
+
+    /// Get the number of usable host addresses on the IPv4 network of this interface address,
+    /// e.g. 254 for a `/24`, for sizing a sweep of the network: the network and broadcast addresses
+    /// don't count, except on `/31` point-to-point links (RFC 3021) whose two addresses are both
+    /// hosts. Get `None` for IPv6, whose networks are too large to sweep, and for netmasks which
+    /// aren't contiguous.
+    pub fn broadcast_domain_hosts(&self) -> Option<u64> {
+        match (self, self.prefix_len()) {
+            (&IfAddr::V4(_), Some(32)) => Some(1),
+            (&IfAddr::V4(_), Some(31)) => Some(2),
+            (&IfAddr::V4(_), Some(prefix_len)) => Some((1u64 << (32 - prefix_len)) - 2),
+            _ => None,
+        }
+    }
+
+    /// Iterate over the usable host addresses on the IPv4 network of this interface address, those
+    /// `broadcast_domain_hosts()` counts, e.g. for a ping or connect sweep of the LAN. The own
+    /// address of the interface is included.
+    ///
+    /// Networks with a prefix shorter than `min_prefix_len` are deemed too large to sweep and
+    /// yield nothing, e.g. with 16 nothing wider than 65534 hosts is swept. IPv6 networks and
+    /// netmasks which aren't contiguous yield nothing either.
+    pub fn iter_subnet_hosts(&self, min_prefix_len: u8) -> impl Iterator<Item = IpAddr> {
+        let (first, last) = match (self, self.prefix_len()) {
+            (&IfAddr::V4(ref ifv4_addr), Some(prefix_len)) if prefix_len >= min_prefix_len => {
+                let network = u32::from(ifv4_addr.network());
+                let size = (!0u32).checked_shr(u32::from(prefix_len)).unwrap_or(0);
+                if prefix_len >= 31 {
+                    (network, network + size)
+                } else {
+                    (network + 1, network + size - 1)
+                }
+            }
+            _ => (1, 0),
+        };
+        (first..=last).map(|host| IpAddr::V4(Ipv4Addr::from(host)))
+    }
+
+    /// Get the first and the last usable host address on the network of this interface address,
+    /// e.g. `192.168.1.1` and `192.168.1.254` for a `/24`, without iterating over them as
+    /// `iter_subnet_hosts()` does. The network and IPv4 broadcast addresses aren't usable, except
+    /// on `/31` and IPv6 `/127` point-to-point links (RFC 3021, RFC 6164) whose two addresses are
+    /// both hosts. On IPv6, which has no broadcast, the last address of the network is usable.
+    /// Get `None` for netmasks which aren't contiguous.
+    pub fn subnet_range(&self) -> Option<(IpAddr, IpAddr)> {
+        match (self, self.prefix_len()) {
+            (&IfAddr::V4(ref ifv4_addr), Some(prefix_len)) => {
+                let network = u32::from(ifv4_addr.network());
+                let size = (!0u32).checked_shr(u32::from(prefix_len)).unwrap_or(0);
+                let (first, last) = if prefix_len >= 31 {
+                    (network, network + size)
+                } else {
+                    (network + 1, network + size - 1)
+                };
+                Some((IpAddr::V4(Ipv4Addr::from(first)), IpAddr::V4(Ipv4Addr::from(last))))
+            }
+            (&IfAddr::V6(ref ifv6_addr), Some(prefix_len)) => {
+                let network = u128::from(ifv6_addr.network());
+                let size = (!0u128).checked_shr(u32::from(prefix_len)).unwrap_or(0);
+                let first = if prefix_len >= 127 { network } else { network + 1 };
+                let last = network + size;
+                Some((IpAddr::V6(Ipv6Addr::from(first)), IpAddr::V6(Ipv6Addr::from(last))))
+            }
+            _ => None,
+        }
+    }
+
+    /// Get how far this address can be reached from.
+    pub fn routability(&self) -> Routability {
+        Routability::of(&self.ip())
+    }
+
+    /// Check whether this is an IPv4 link-local address, from `169.254.0.0/16`, such as those
+    /// assigned by APIPA when no DHCP server answers.
+    pub fn is_ipv4_link_local(&self) -> bool {
+        match *self {
+            IfAddr::V4(ref ifv4_addr) => ifv4_addr.ip.is_link_local(),
+            IfAddr::V6(_) => false,
+        }
+    }
+
+    /// Check whether this is an address in the shared address space of carrier-grade NATs,
+    /// `100.64.0.0/10`. Such addresses are classified as `Routability::Shared` rather than
+    /// `Routability::Global`.
+    pub fn is_cgnat(&self) -> bool {
+        match *self {
+            IfAddr::V4(ref ifv4_addr) => classify::is_shared(&ifv4_addr.ip),
+            IfAddr::V6(_) => false,
+        }
+    }
+
+    /// Check whether this is an IPv6 unique local address, from `fc00::/7` (RFC 4193). These are
+    /// the IPv6 counterpart of RFC 1918 addresses and classified as `Routability::Private`.
+    pub fn is_unique_local(&self) -> bool {
+        match *self {
+            IfAddr::V4(_) => false,
+            IfAddr::V6(ref ifv6_addr) => classify::is_unique_local(&ifv6_addr.ip),
+        }
+    }
+
+    /// Check whether this address can likely reach the internet: it is either a global address, or
+    /// a private or shared one on the same network as a default gateway.
+    ///
+    /// The default gateways are those of `default_gateways()`, so on platforms it can't read the
+    /// routing table of only global addresses count.
+    pub fn likely_internet(&self) -> bool {
+        self.likely_internet_via(&default_gateways())
+    }
+
+    fn likely_internet_via(&self, gateways: &[(IpAddr, String)]) -> bool {
+        match self.routability() {
+            Routability::Global => true,
+            Routability::Private | Routability::Shared => {
+                gateways.iter().any(|&(ref gateway, _)| self.contains(gateway))
+            }
+            Routability::Loopback | Routability::LinkLocal => false,
+        }
+    }
+
+    /// Get the scope of this address if it is an IPv6 one.
+    pub fn v6_scope(&self) -> Option<V6Scope> {
+        match *self {
+            IfAddr::V4(_) => None,
+            IfAddr::V6(ref ifv6_addr) => Some(V6Scope::of(&ifv6_addr.ip)),
+        }
+    }
+
+    /// Check whether this and `other` are on the same network, i.e. of the same family and with
+    /// the same prefix and network address.
+    pub fn same_link(&self, other: &IfAddr) -> bool {
+        match (self.prefix_len(), other.prefix_len()) {
+            (Some(prefix_len), Some(other_prefix_len)) => {
+                prefix_len == other_prefix_len && self.network() == other.network()
+            }
+            _ => false,
+        }
+    }
+
+    /// Check whether `ip` is on the same network as this interface address.
+    pub fn contains(&self, ip: &IpAddr) -> bool {
+        match (self, ip) {
+            (&IfAddr::V4(ref ifv4_addr), &IpAddr::V4(ref ip)) => ifv4_addr.contains(ip),
+            (&IfAddr::V6(ref ifv6_addr), &IpAddr::V6(ref ip)) => ifv6_addr.contains(ip),
+            _ => false,
+        }
+    }
+}
+
+impl Ifv4Addr {
+    /// Check whether this is a loopback address.
+    pub fn is_loopback(&self) -> bool {
+        self.ip.octets()[0] == 127
+    }
+
+    /// Get the length of the network prefix, or `None` if the netmask isn't contiguous.
+    pub fn prefix_len(&self) -> Option<u8> {
+        let mask = u32::from(self.netmask);
+        let len = mask.count_ones();
+        if mask == (!0u32).checked_shl(32 - len).unwrap_or(0) {
+            Some(len as u8)
+        } else {
+            None
+        }
+    }
+
+    /// Get the address of the network this interface address is on.
+    pub fn network(&self) -> Ipv4Addr {
+        Ipv4Addr::from(u32::from(self.ip) & u32::from(self.netmask))
+    }
+
+    /// Check whether `ip` is on the same network as this interface address.
+    pub fn contains(&self, ip: &Ipv4Addr) -> bool {
+        u32::from(*ip) & u32::from(self.netmask) == u32::from(self.network())
+    }
+}
+
+impl Ifv6Addr {
+    /// Check whether this is a loopback address.
+    pub fn is_loopback(&self) -> bool {
+        self.ip.segments() == [0, 0, 0, 0, 0, 0, 0, 1]
+    }
+
+    /// Get the length of the network prefix, or `None` if the netmask isn't contiguous.
+    pub fn prefix_len(&self) -> Option<u8> {
+        let mask = u128::from(self.netmask);
+        let len = mask.count_ones();
+        if mask == (!0u128).checked_shl(128 - len).unwrap_or(0) {
+            Some(len as u8)
+        } else {
+            None
+        }
+    }
+
+    /// Get the address of the network this interface address is on.
+    pub fn network(&self) -> Ipv6Addr {
+        Ipv6Addr::from(u128::from(self.ip) & u128::from(self.netmask))
+    }
+
+    /// Check whether `ip` is on the same network as this interface address.
+    pub fn contains(&self, ip: &Ipv6Addr) -> bool {
+        u128::from(*ip) & u128::from(self.netmask) == u128::from(self.network())
+    }
+}
+
+/// Get a list of all the network interfaces on this machine along with their IP info.
+///
+/// Only interfaces which are up are listed. Link-local addresses, both `169.254.0.0/16` and
+/// `fe80::/10`, are left out on every platform. Use `get_if_addrs_with_link_local()` to get them
+/// too, or `get_if_addrs_all()` to also get the interfaces which are down.
+///
+/// On Linux and Android, if `getifaddrs` turns out not to be implemented, the interfaces are
+/// enumerated through `ioctl(SIOCGIFCONF)` instead, which only reports IPv4 addresses.
+///
+/// An error means the interfaces couldn't be enumerated. `Ok` with an empty list means they were,
+/// and there are none to list, as in a container whose networking isn't up yet: no platform
+/// reports that as an error.
+pub fn get_if_addrs() -> io::Result<Vec<Interface>> {
+    get_if_addrs_with_link_local().map(drop_link_local)
+}
+
+/// Get at most `max` of the interfaces `get_if_addrs()` returns, keeping the first ones the OS
+/// lists. The enumeration stops as soon as `max` are found, which bounds the work on hosts with
+/// huge numbers of interfaces.
+pub fn get_if_addrs_limited(max: usize) -> io::Result<Vec<Interface>> {
+    enumerate_limited(Limit::new(max, qualifies))
+}
+
+#[cfg(not(windows))]
+fn enumerate_limited(limit: Limit) -> io::Result<Vec<Interface>> {
+    posix::get_if_addrs_limited(limit)
+}
+
+#[cfg(windows)]
+fn enumerate_limited(limit: Limit) -> io::Result<Vec<Interface>> {
+    windows::get_if_addrs_limited(limit)
+}
+
+/// Hand the interfaces `get_if_addrs()` returns to `f` one at a time, in the order the OS lists
+/// them, until `f` returns false.
+///
+/// The interfaces are taken straight from the list the OS returns without collecting them first,
+/// so finding one of them doesn't cost a `Vec` of all of them.
+pub fn for_each_interface<F>(mut f: F) -> io::Result<()>
+    where F: FnMut(&Interface) -> bool
+{
+    for_each_interface_all(&mut |interface| !qualifies(&interface) || f(&interface))
+}
+
+#[cfg(not(windows))]
+fn for_each_interface_all(visit: &mut dyn FnMut(Interface) -> bool) -> io::Result<()> {
+    posix::for_each_interface(visit)
+}
+
+#[cfg(windows)]
+fn for_each_interface_all(visit: &mut dyn FnMut(Interface) -> bool) -> io::Result<()> {
+    windows::for_each_interface(visit)
+}
+
+/// The same as `for_each_interface()`, but the names of the interfaces handed to `f` borrow from
+/// the list the OS returns rather than each being copied into a `String`, for when they are only
+/// compared or looked at.
+///
+/// The list is freed as soon as the enumeration is done, so a name can't outlive the call of `f`
+/// it is handed to: keep a copy, e.g. with `Cow::into_owned()`, of those needed afterwards. Names
+/// are copied all the same on Windows and wherever the OS doesn't return them as UTF-8.
+pub fn for_each_interface_ref<F>(mut f: F) -> io::Result<()>
+    where F: FnMut(&InterfaceRef) -> bool
+{
+    for_each_interface_ref_all(&mut |interface| {
+        !listed(interface.flags, &interface.addr) || f(&interface)
+    })
+}
+
+#[cfg(not(windows))]
+fn for_each_interface_ref_all(visit: &mut dyn FnMut(InterfaceRef) -> bool) -> io::Result<()> {
+    posix::for_each_interface_ref(visit)
+}
+
+#[cfg(windows)]
+fn for_each_interface_ref_all(visit: &mut dyn FnMut(InterfaceRef) -> bool) -> io::Result<()> {
+    windows::for_each_interface(&mut |interface| visit(InterfaceRef::owning(interface)))
+}
+
+// Whether `get_if_addrs()` lists `interface`.
+fn qualifies(interface: &Interface) -> bool {
+    listed(interface.flags, &interface.addr)
+}
+
+// Whether `get_if_addrs()` lists an address `addr` on an interface with `flags`.
+fn listed(flags: IfFlags, addr: &IfAddr) -> bool {
+    flags.contains(IfFlags::UP) && addr.routability() != Routability::LinkLocal
+}
+
+/// How many of the interfaces an enumeration walks through it collects, counting only those
+/// passing a filter.
+#[derive(Clone, Copy)]
+pub struct Limit {
+    max: usize,
+    filter: fn(&Interface) -> bool,
+}
+
+impl Limit {
+    /// Collect at most `max` of the interfaces passing `filter`.
+    pub fn new(max: usize, filter: fn(&Interface) -> bool) -> Limit {
+        Limit {
+            max: max,
+            filter: filter,
+        }
+    }
+
+    /// Collect every interface.
+    pub fn none() -> Limit {
+        Limit::new(usize::max_value(), |_| true)
+    }
+
+    /// Add `interface` to `ifaces` if it passes the filter and there is room for it, and tell
+    /// whether there is room left for more.
+    pub fn push(&self, ifaces: &mut Vec<Interface>, interface: Interface) -> bool {
+        if ifaces.len() < self.max && (self.filter)(&interface) {
+            ifaces.push(interface);
+        }
+        ifaces.len() < self.max
+    }
+
+    /// Apply the limit to interfaces which have been collected already.
+    pub fn apply(&self, ifaces: Vec<Interface>) -> Vec<Interface> {
+        let mut ret = Vec::new();
+        for interface in ifaces {
+            if !self.push(&mut ret, interface) {
+                break;
+            }
+        }
+        ret
+    }
+}
+
+/// Get the same as `get_if_addrs()`, link-local addresses included.
+pub fn get_if_addrs_with_link_local() -> io::Result<Vec<Interface>> {
+    get_if_addrs_all().map(drop_down)
+}
+
+/// Get every address the OS reports, for diagnostics: unlike `get_if_addrs()` this includes the
+/// interfaces which are down and link-local addresses.
+pub fn get_if_addrs_all() -> io::Result<Vec<Interface>> {
+    enumerate_all()
+}
+
+#[cfg(not(windows))]
+fn enumerate_all() -> io::Result<Vec<Interface>> {
+    posix::get_if_addrs()
+}
+
+#[cfg(windows)]
+fn enumerate_all() -> io::Result<Vec<Interface>> {
+    windows::get_if_addrs()
+}
+
+// Leave out the interfaces which aren't up.
+fn drop_down(ifaces: Vec<Interface>) -> Vec<Interface> {
+    ifaces.into_iter().filter(|interface| interface.flags.contains(IfFlags::UP)).collect()
+}
+
+// Leave out the link-local addresses of either family.
+fn drop_link_local(ifaces: Vec<Interface>) -> Vec<Interface> {
+    ifaces.into_iter()
+        .filter(|interface| interface.addr.routability() != Routability::LinkLocal)
+        .collect()
+}
+
+/// Get only the names and IPs of the network interfaces on this machine.
+///
+/// This is the fast path for when nothing else is needed: the netmask is left unspecified, and
+/// the broadcast address, flags and index are not looked up at all. Without the flags, interfaces
+/// which are down can't be told apart and are listed too.
+pub fn get_if_addrs_minimal() -> io::Result<Vec<Interface>> {
+    enumerate_minimal().map(drop_link_local)
+}
+
+#[cfg(not(windows))]
+fn enumerate_minimal() -> io::Result<Vec<Interface>> {
+    posix::get_if_addrs_minimal()
+}
+
+#[cfg(windows)]
+fn enumerate_minimal() -> io::Result<Vec<Interface>> {
+    windows::get_if_addrs_minimal()
+}
+
+// The enumeration reporting the most about each address on this platform.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn get_if_addrs_detailed() -> io::Result<Vec<Interface>> {
+    get_if_addrs_netlink()
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+fn get_if_addrs_detailed() -> io::Result<Vec<Interface>> {
+    get_if_addrs()
+}
+
+// The same as `get_if_addrs_detailed()`, link-local addresses included.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn get_if_addrs_detailed_with_link_local() -> io::Result<Vec<Interface>> {
+    netlink::get_if_addrs_netlink_with_link_local()
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+fn get_if_addrs_detailed_with_link_local() -> io::Result<Vec<Interface>> {
+    get_if_addrs_with_link_local()
+}
+
+/// Get the interfaces on this machine with the members of bonded or teamed interfaces left out
+/// whenever their master is listed too, so that each logical link is only reported once.
+///
+/// Only Linux exposes enough information for this, elsewhere this is the same as
+/// `get_if_addrs()`.
+pub fn effective_interfaces() -> io::Result<Vec<Interface>> {
+    let ifaces = try!(get_if_addrs());
+    Ok(drop_enslaved(ifaces))
+}
+
+/// Get the same as `get_if_addrs()`, with the name of every interface replaced by what `map`
+/// returns for it, e.g. for presenting friendlier or normalized names in the output and
+/// configuration of a virtualized environment.
+///
+/// The names are only rewritten for presentation: looking an interface up by name in the OS
+/// needs the original one.
+pub fn get_if_addrs_with_name_map<F>(map: F) -> io::Result<Vec<Interface>>
+    where F: Fn(&str) -> String
+{
+    Ok(rename(try!(get_if_addrs()), map))
+}
+
+fn rename<F>(mut ifaces: Vec<Interface>, map: F) -> Vec<Interface>
+    where F: Fn(&str) -> String
+{
+    for interface in &mut ifaces {
+        interface.name = map(&interface.name);
+    }
+    ifaces
+}
+
+/// Get every address the OS reports, as `get_if_addrs_all()` does, on the interfaces whose flags
+/// include all of `required` and none of `forbidden`, e.g. `IfFlags::UP | IfFlags::MULTICAST` and
+/// `IfFlags::LOOPBACK` for the interfaces to send multicast discovery on.
+///
+/// Nothing is left out but by the flags, so link-local addresses and interfaces which are down are
+/// listed unless `required` includes `IfFlags::UP`.
+pub fn get_if_addrs_matching_flags(required: IfFlags,
+                                   forbidden: IfFlags)
+                                   -> io::Result<Vec<Interface>> {
+    Ok(matching_flags(try!(get_if_addrs_all()), required, forbidden))
+}
+
+fn matching_flags(ifaces: Vec<Interface>, required: IfFlags, forbidden: IfFlags) -> Vec<Interface> {
+    ifaces.into_iter()
+        .filter(|interface| {
+            interface.flags.contains(required) && interface.flags.0 & forbidden.0 == 0
+        })
+        .collect()
+}
+
+/// Get the first of the interfaces `get_if_addrs()` returns on the interface with the hardware
+/// address `mac`, for when network cards are identified by their MAC address rather than by
+/// their names, which can change.
+///
+/// Hardware addresses are only known on Linux: elsewhere this is always `None`.
+pub fn interface_by_mac(mac: [u8; 6]) -> io::Result<Option<Interface>> {
+    lookup_mac(mac)
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn lookup_mac(mac: [u8; 6]) -> io::Result<Option<Interface>> {
+    let ifaces = try!(get_if_addrs());
+    Ok(sysfs::find_by_mac(ifaces, ::std::path::Path::new(sysfs::SYS_CLASS_NET), mac))
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+fn lookup_mac(_mac: [u8; 6]) -> io::Result<Option<Interface>> {
+    Ok(None)
+}
+
+// The hardware address of the interface `name`, which is only known on Linux. Aliases such as
+// `eth0:0` have that of the interface before the colon.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn mac_address(name: &str) -> Option<[u8; 6]> {
+    let name = name.split(':').next().unwrap_or("");
+    sysfs::mac_address(::std::path::Path::new(sysfs::SYS_CLASS_NET), name)
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+fn mac_address(_name: &str) -> Option<[u8; 6]> {
+    None
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn drop_enslaved(ifaces: Vec<Interface>) -> Vec<Interface> {
+    sysfs::drop_enslaved(ifaces, ::std::path::Path::new(sysfs::SYS_CLASS_NET))
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+fn drop_enslaved(ifaces: Vec<Interface>) -> Vec<Interface> {
+    ifaces
+}
+
+#[cfg(test)]
+mod test {
+    use common::get_if_addrs::{IfAddr, IfFlags, Interface, InterfaceBuilder, Limit, Routability,
+                               V6Scope, drop_down, drop_link_local, for_each_interface,
+                               get_if_addrs, get_if_addrs_all, get_if_addrs_limited,
+                               get_if_addrs_matching_flags, get_if_addrs_minimal,
+                               get_if_addrs_with_link_local, get_if_addrs_with_name_map,
+                               matching_flags, qualifies, rename};
+    use std::error::Error;
+    use std::io::{self, Read};
+    use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV6};
+    #[cfg(feature = "socket2")]
+    use std::net::SocketAddrV4;
+    use std::process::{Command, Stdio};
+    use std::str::FromStr;
+    use std::thread;
+    use std::time::Duration;
+
+    fn list_system_interfaces(cmd: &str, arg: &str) -> String {
+        let start_cmd = if arg == "" {
+            Command::new(cmd).stdout(Stdio::piped()).spawn()
+        } else {
+            Command::new(cmd).arg(arg).stdout(Stdio::piped()).spawn()
+        };
+        let mut process = match start_cmd {
+            Err(why) => {
+                println!("couldn't start cmd {} : {}", cmd, why.description());
+                return "".to_string();
+            }
+            Ok(process) => process,
+        };
+        thread::sleep(Duration::from_millis(1000));
+        let _ = process.kill();
+        let result: Vec<u8> = unwrap!(process.stdout).bytes().map(|x| unwrap!(x)).collect();
+        unwrap!(String::from_utf8(result))
+    }
+
+    #[cfg(windows)]
+    fn list_system_addrs() -> Vec<IpAddr> {
+        list_system_interfaces("ipconfig", "")
+            .lines()
+            .filter_map(|line| {
+                println!("{}", line);
+                if line.contains("Address") && !line.contains("Link-local") {
+                    let addr_s: Vec<&str> = line.split(" : ").collect();
+                    if line.contains("IPv6") {
+                        return Some(IpAddr::V6(unwrap!(Ipv6Addr::from_str(addr_s[1]))));
+                    } else if line.contains("IPv4") {
+                        return Some(IpAddr::V4(unwrap!(Ipv4Addr::from_str(addr_s[1]))));
+                    }
+                }
+                None
+            })
+            .collect()
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android", target_os = "nacl"))]
+    fn list_system_addrs() -> Vec<IpAddr> {
+        list_system_interfaces("ip", "addr")
+            .lines()
+            .filter_map(|line| {
+                println!("{}", line);
+                if line.contains("inet ") {
+                    let addr_s: Vec<&str> = line.split_whitespace().collect();
+                    let addr: Vec<&str> = addr_s[1].split('/').collect();
+                    return Some(IpAddr::V4(unwrap!(Ipv4Addr::from_str(addr[0]))));
+                }
+                None
+            })
+            .collect()
+    }
+
+    #[cfg(any(target_os = "freebsd", target_os = "macos", target_os = "ios"))]
+    fn list_system_addrs() -> Vec<IpAddr> {
+        list_system_interfaces("ifconfig", "")
+            .lines()
+            .filter_map(|line| {
+                println!("{}", line);
+                if line.contains("inet ") {
+                    let addr_s: Vec<&str> = line.split_whitespace().collect();
+                    return Some(IpAddr::V4(unwrap!(Ipv4Addr::from_str(addr_s[1]))));
+                }
+                None
+            })
+            .collect()
+    }
+
+    // The interface address `ip` with the netmask `netmask`.
+    fn test_addr(ip: &str, netmask: &str) -> IfAddr {
+        InterfaceBuilder::new()
+            .addr(unwrap!(IpAddr::from_str(ip)))
+            .netmask(unwrap!(IpAddr::from_str(netmask)))
+            .build()
+            .addr
+    }
+
+    #[test]
+    fn test_get_if_addrs() {
+        let ifaces = unwrap!(get_if_addrs());
+        println!("Local interfaces:");
+        println!("{:#?}", ifaces);
+        // at least one loop back address
+        assert!(1 <= ifaces.iter().filter(|interface| interface.is_loopback()).count());
+        // one address of IpV4(127.0.0.1)
+        assert!(1 ==
+                ifaces.iter()
+            .filter(|interface| interface.addr.ip() == IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)))
+            .count());
+
+        // each system address shall be listed, though not necessarily by `get_if_addrs()`, which
+        // leaves out interfaces which are down and link-local addresses the system lists
+        let all = unwrap!(get_if_addrs_all());
+        let system_addrs = list_system_addrs();
+        assert!(system_addrs.len() >= 1);
+        for addr in system_addrs {
+            let mut listed = false;
+            println!("\n checking whether {:?} has been properly listed \n", addr);
+            for interface in &all {
+                if interface.addr.ip() == addr {
+                    listed = true;
+                }
+            }
+            assert!(listed);
+        }
+    }
+
+    #[test]
+    fn test_loopback_flags() {
+        let ifaces = unwrap!(get_if_addrs());
+        let loopback = unwrap!(ifaces.iter().find(|interface| interface.is_loopback()));
+        assert!(loopback.flags.contains(IfFlags::UP | IfFlags::LOOPBACK));
+        assert!(loopback.index != 0);
+    }
+
+    #[test]
+    fn test_get_if_addrs_minimal() {
+        // Interfaces which are down are listed too, as the flags telling them apart aren't read.
+        let full = drop_link_local(unwrap!(get_if_addrs_all()));
+        let minimal = unwrap!(get_if_addrs_minimal());
+        let names_and_ips = |ifaces: &[Interface]| {
+            let mut names_and_ips: Vec<(String, IpAddr)> = ifaces.iter()
+                .map(|interface| (interface.name.clone(), interface.addr.ip()))
+                .collect();
+            names_and_ips.sort();
+            names_and_ips
+        };
+        assert_eq!(names_and_ips(&minimal), names_and_ips(&full));
+        for minimal in &minimal {
+            assert_eq!(minimal.addr.prefix_len(), Some(0));
+            assert_eq!(minimal.index, 0);
+            assert!(minimal.flags.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_prefix_len_network_and_contains() {
+        let v4 = |a, b, c, d| IpAddr::V4(Ipv4Addr::new(a, b, c, d));
+        let iface = InterfaceBuilder::new()
+            .addr(v4(192, 168, 1, 5))
+            .netmask(v4(255, 255, 254, 0))
+            .build();
+        assert_eq!(iface.addr.prefix_len(), Some(23));
+        assert_eq!(iface.addr.network(), v4(192, 168, 0, 0));
+        assert!(iface.addr.contains(&v4(192, 168, 0, 1)));
+        assert!(!iface.addr.contains(&v4(192, 168, 2, 1)));
+
+        let non_contiguous = InterfaceBuilder::new()
+            .addr(v4(192, 168, 1, 5))
+            .netmask(v4(255, 0, 255, 0))
+            .build();
+        assert_eq!(non_contiguous.addr.prefix_len(), None);
+
+        let v6 = InterfaceBuilder::new()
+            .addr(IpAddr::V6(unwrap!(Ipv6Addr::from_str("2001:db8::5"))))
+            .netmask(IpAddr::V6(unwrap!(Ipv6Addr::from_str("ffff:ffff:ffff:ffff::"))))
+            .build();
+        assert_eq!(v6.addr.prefix_len(), Some(64));
+        assert!(v6.addr.contains(&IpAddr::V6(unwrap!(Ipv6Addr::from_str("2001:db8::1")))));
+        assert!(!v6.addr.contains(&v4(192, 168, 1, 5)));
+    }
+
+    #[test]
+    fn test_broadcast_domain_hosts() {
+        assert_eq!(test_addr("192.168.1.5", "255.255.255.0").broadcast_domain_hosts(), Some(254));
+        assert_eq!(test_addr("192.168.1.5", "255.255.255.252").broadcast_domain_hosts(), Some(2));
+        assert_eq!(test_addr("10.1.2.3", "255.255.0.0").broadcast_domain_hosts(), Some(65534));
+        assert_eq!(test_addr("10.0.0.1", "255.255.255.254").broadcast_domain_hosts(), Some(2));
+        assert_eq!(test_addr("10.0.0.1", "255.255.255.255").broadcast_domain_hosts(), Some(1));
+        assert_eq!(test_addr("10.0.0.1", "0.0.0.0").broadcast_domain_hosts(), Some(4294967294));
+        assert_eq!(test_addr("10.0.0.1", "255.0.255.0").broadcast_domain_hosts(), None);
+        assert_eq!(test_addr("2001:db8::5", "ffff:ffff:ffff:ffff::").broadcast_domain_hosts(),
+                   None);
+    }
+
+    #[test]
+    fn test_iter_subnet_hosts() {
+        let v4 = |a, b, c, d| IpAddr::V4(Ipv4Addr::new(a, b, c, d));
+        let hosts: Vec<IpAddr> =
+            test_addr("192.168.1.10", "255.255.255.248").iter_subnet_hosts(24).collect();
+        assert_eq!(hosts.len(), 6);
+        assert_eq!(hosts.first(), Some(&v4(192, 168, 1, 9)));
+        assert_eq!(hosts.last(), Some(&v4(192, 168, 1, 14)));
+        assert_eq!(test_addr("10.1.2.3", "255.255.0.0").iter_subnet_hosts(16).count(), 65534);
+        assert_eq!(test_addr("10.0.0.1", "255.255.255.254")
+                       .iter_subnet_hosts(24)
+                       .collect::<Vec<_>>(),
+                   vec![v4(10, 0, 0, 0), v4(10, 0, 0, 1)]);
+        assert_eq!(test_addr("255.255.255.255", "255.255.255.255").iter_subnet_hosts(32).count(),
+                   1);
+        // Too large to sweep, IPv6 and netmasks which aren't contiguous.
+        assert_eq!(test_addr("10.1.2.3", "255.255.0.0").iter_subnet_hosts(17).count(), 0);
+        assert_eq!(test_addr("2001:db8::5", "ffff:ffff:ffff:fff8::").iter_subnet_hosts(0).count(),
+                   0);
+        assert_eq!(test_addr("10.0.0.1", "255.0.255.0").iter_subnet_hosts(0).count(), 0);
+    }
+
+    #[test]
+    fn test_subnet_range() {
+        let range = |first, last| {
+            Some((unwrap!(IpAddr::from_str(first)), unwrap!(IpAddr::from_str(last))))
+        };
+        assert_eq!(test_addr("192.168.1.10", "255.255.255.0").subnet_range(),
+                   range("192.168.1.1", "192.168.1.254"));
+        assert_eq!(test_addr("10.0.0.6", "255.255.255.252").subnet_range(),
+                   range("10.0.0.5", "10.0.0.6"));
+        assert_eq!(test_addr("10.0.0.1", "255.255.255.254").subnet_range(),
+                   range("10.0.0.0", "10.0.0.1"));
+        assert_eq!(test_addr("10.0.0.1", "255.255.255.255").subnet_range(),
+                   range("10.0.0.1", "10.0.0.1"));
+        assert_eq!(test_addr("2001:db8::5", "ffff:ffff:ffff:ffff::").subnet_range(),
+                   range("2001:db8::1", "2001:db8::ffff:ffff:ffff:ffff"));
+        assert_eq!(test_addr("10.0.0.1", "255.0.255.0").subnet_range(), None);
+    }
+
+    #[test]
+    fn test_try_bind() {
+        let loopback = InterfaceBuilder::new()
+            .name("lo")
+            .addr(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)))
+            .build();
+        let listener = unwrap!(loopback.try_bind(0));
+        assert_eq!(unwrap!(listener.local_addr()).ip(), loopback.addr.ip());
+
+        // An address of TEST-NET-1, which no interface has.
+        let bogus = InterfaceBuilder::new()
+            .name("eth0")
+            .addr(IpAddr::V4(Ipv4Addr::new(192, 0, 2, 77)))
+            .build();
+        match bogus.try_bind(0) {
+            Err(ref e) if e.kind() == io::ErrorKind::AddrNotAvailable => (),
+            result => panic!("Unexpected {:?}", result),
+        }
+    }
+
+    #[test]
+    fn test_stable_id() {
+        let interface = |name, ip, netmask| {
+            InterfaceBuilder::new()
+                .name(name)
+                .addr(unwrap!(IpAddr::from_str(ip)))
+                .netmask(unwrap!(IpAddr::from_str(netmask)))
+                .build()
+        };
+        let mac = [0x52, 0x54, 0x00, 0x12, 0x34, 0x5a];
+        let eth0 = interface("eth0", "192.168.1.5", "255.255.255.0");
+        // The same interface in a later enumeration, after DHCP handed out another address.
+        let eth0_later = interface("eth0", "192.168.1.7", "255.255.255.0");
+        let eth1 = interface("eth1", "192.168.1.5", "255.255.255.0");
+        let eth0_elsewhere = interface("eth0", "10.0.0.5", "255.0.0.0");
+
+        assert_eq!(eth0.stable_id_with(Some(mac)), "mac-52540012345a");
+        assert_eq!(eth0_elsewhere.stable_id_with(Some(mac)), "mac-52540012345a");
+
+        let id = eth0.stable_id_with(None);
+        assert!(id.starts_with("net-"));
+        assert_eq!(id.len(), 20);
+        assert_eq!(eth0_later.stable_id_with(None), id);
+        assert!(eth1.stable_id_with(None) != id);
+        assert!(eth0_elsewhere.stable_id_with(None) != id);
+        assert!(eth0.stable_id_with(Some(mac)) != id);
+
+        let ids = || -> Vec<String> {
+            unwrap!(get_if_addrs()).iter().map(|interface| interface.stable_id()).collect()
+        };
+        assert_eq!(ids(), ids());
+    }
+
+    #[test]
+    fn test_same_link() {
+        let first = test_addr("192.168.1.5", "255.255.255.0");
+        assert!(first.same_link(&test_addr("192.168.1.200", "255.255.255.0")));
+        assert!(!first.same_link(&test_addr("192.168.1.6", "255.255.255.128")));
+        assert!(!first.same_link(&test_addr("192.168.2.5", "255.255.255.0")));
+        assert!(!first.same_link(&test_addr("::ffff:c0a8:105", "ffff:ffff:ffff:ffff::")));
+    }
+
+    #[test]
+    fn test_cidr_string() {
+        let cidr = |ip, netmask| test_addr(ip, netmask).cidr_string();
+        assert_eq!(cidr("192.168.1.5", "255.255.255.0"),
+                   Some("192.168.1.5/24".to_string()));
+        assert_eq!(cidr("2001:db8::5", "ffff:ffff:ffff:ffff::"),
+                   Some("2001:db8::5/64".to_string()));
+        assert_eq!(cidr("192.168.1.5", "255.0.255.0"), None);
+    }
+
+    #[test]
+    fn test_socket_addr() {
+        let interface = |ip| {
+            InterfaceBuilder::new().addr(unwrap!(IpAddr::from_str(ip))).index(3).build()
+        };
+        assert_eq!(interface("192.168.1.5").socket_addr(5483),
+                   unwrap!(SocketAddr::from_str("192.168.1.5:5483")));
+        assert_eq!(interface("2001:db8::5").socket_addr(5483),
+                   unwrap!(SocketAddr::from_str("[2001:db8::5]:5483")));
+        assert_eq!(interface("fe80::5").socket_addr(5483),
+                   SocketAddr::V6(SocketAddrV6::new(unwrap!(Ipv6Addr::from_str("fe80::5")),
+                                                    5483,
+                                                    0,
+                                                    3)));
+    }
+
+    #[test]
+    fn test_as_unspecified_socket() {
+        let interface = |ip| {
+            InterfaceBuilder::new().addr(unwrap!(IpAddr::from_str(ip))).index(3).build()
+        };
+        assert_eq!(interface("192.168.1.5").as_unspecified_socket(),
+                   unwrap!(SocketAddr::from_str("192.168.1.5:0")));
+        assert_eq!(interface("2001:db8::5").as_unspecified_socket(),
+                   unwrap!(SocketAddr::from_str("[2001:db8::5]:0")));
+
+        let mut addr = interface("fe80::5").as_unspecified_socket();
+        assert_eq!(addr,
+                   SocketAddr::V6(SocketAddrV6::new(unwrap!(Ipv6Addr::from_str("fe80::5")),
+                                                    0,
+                                                    0,
+                                                    3)));
+        addr.set_port(5483);
+        assert_eq!(addr, interface("fe80::5").socket_addr(5483));
+    }
+
+    #[test]
+    fn test_is_tunnel() {
+        let ip = IpAddr::V4(Ipv4Addr::new(10, 8, 0, 2));
+        let peer = IpAddr::V4(Ipv4Addr::new(10, 8, 0, 1));
+        assert!(InterfaceBuilder::new().name("utun3").addr(ip).build().is_tunnel());
+        assert!(InterfaceBuilder::new().name("wg0").addr(ip).build().is_tunnel());
+        assert!(InterfaceBuilder::new()
+            .name("vpn0")
+            .addr(ip)
+            .flags(IfFlags::UP | IfFlags::POINT_TO_POINT)
+            .build()
+            .is_tunnel());
+        assert!(InterfaceBuilder::new().name("vpn0").addr(ip).peer(peer).build().is_tunnel());
+        assert!(!InterfaceBuilder::new()
+            .name("eth0")
+            .addr(ip)
+            .flags(IfFlags::UP | IfFlags::BROADCAST)
+            .build()
+            .is_tunnel());
+    }
+
+    #[cfg(feature = "socket2")]
+    #[test]
+    fn test_sock_addr_round_trip() {
+        let interface = InterfaceBuilder::new()
+            .addr(unwrap!(IpAddr::from_str("fe80::5")))
+            .index(3)
+            .build();
+        let sock_addr = interface.sock_addr(5483);
+        assert_eq!(unwrap!(sock_addr.as_inet6()),
+                   SocketAddrV6::new(unwrap!(Ipv6Addr::from_str("fe80::5")), 5483, 0, 3));
+
+        let interface = InterfaceBuilder::new()
+            .addr(unwrap!(IpAddr::from_str("192.168.1.5")))
+            .build();
+        assert_eq!(unwrap!(interface.sock_addr(5483).as_inet()),
+                   unwrap!(SocketAddrV4::from_str("192.168.1.5:5483")));
+    }
+
+    #[test]
+    fn test_v6_scope() {
+        let scope = |ip| {
+            InterfaceBuilder::new().addr(unwrap!(IpAddr::from_str(ip))).build().addr.v6_scope()
+        };
+        assert_eq!(scope("fe80::1"), Some(V6Scope::LinkLocal));
+        assert_eq!(scope("fc00::1"), Some(V6Scope::UniqueLocal));
+        assert_eq!(scope("2001:db8::1"), Some(V6Scope::Global));
+        assert_eq!(scope("::1"), Some(V6Scope::Loopback));
+        assert_eq!(scope("127.0.0.1"), None);
+    }
+
+    #[test]
+    fn test_link_local_filtering() {
+        let interface = |ip| InterfaceBuilder::new().addr(unwrap!(IpAddr::from_str(ip))).build();
+        assert!(interface("169.254.1.1").addr.is_ipv4_link_local());
+        assert!(!interface("192.168.1.1").addr.is_ipv4_link_local());
+        assert!(!interface("fe80::1").addr.is_ipv4_link_local());
+
+        let ifaces = vec![interface("169.254.1.1"),
+                          interface("192.168.1.1"),
+                          interface("fe80::1"),
+                          interface("2001:db8::1")];
+        assert_eq!(drop_link_local(ifaces.clone()),
+                   vec![ifaces[1].clone(), ifaces[3].clone()]);
+
+        let all = unwrap!(get_if_addrs_with_link_local());
+        let filtered = unwrap!(get_if_addrs());
+        assert!(filtered.iter().all(|interface| all.contains(interface)));
+        assert!(filtered.iter().all(|interface| {
+            interface.addr.routability() != Routability::LinkLocal
+        }));
+    }
+
+    #[test]
+    fn test_limit() {
+        let flags = |i| if i % 2 == 0 { IfFlags::UP } else { IfFlags::empty() };
+        let ifaces: Vec<Interface> = (0..10000)
+            .map(|i| InterfaceBuilder::new().name(&format!("veth{}", i)).flags(flags(i)).build())
+            .collect();
+
+        let limit = Limit::new(100, qualifies);
+        let mut kept = Vec::new();
+        let offered = ifaces.iter()
+            .take_while(|interface| limit.push(&mut kept, (*interface).clone()))
+            .count();
+        assert_eq!(kept.len(), 100);
+        assert_eq!(offered, 198);
+        assert_eq!(kept[1].name, "veth2");
+        assert_eq!(limit.apply(ifaces.clone()), kept);
+        assert!(Limit::new(0, qualifies).apply(ifaces.clone()).is_empty());
+        assert_eq!(Limit::none().apply(ifaces.clone()), ifaces);
+
+        let ifaces = unwrap!(get_if_addrs());
+        assert_eq!(unwrap!(get_if_addrs_limited(1)), ifaces[..1].to_vec());
+        assert_eq!(unwrap!(get_if_addrs_limited(usize::max_value())), ifaces);
+    }
+
+    #[test]
+    fn test_ptr_name() {
+        let addr = |ip| InterfaceBuilder::new().addr(unwrap!(IpAddr::from_str(ip))).build().addr;
+        assert_eq!(addr("192.168.1.5").ptr_name(), "5.1.168.192.in-addr.arpa");
+        assert_eq!(addr("2001:db8::567:89ab").ptr_name(),
+                   "b.a.9.8.7.6.5.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.8.b.d.0.1.0.0.2.ip6.arpa");
+    }
+
+    #[test]
+    fn test_cgnat() {
+        let addr = |ip| InterfaceBuilder::new().addr(unwrap!(IpAddr::from_str(ip))).build().addr;
+        assert!(addr("100.64.1.1").is_cgnat());
+        assert_eq!(addr("100.64.1.1").routability(), Routability::Shared);
+        assert!(!addr("100.128.1.1").is_cgnat());
+        assert!(!addr("192.168.1.1").is_cgnat());
+        assert!(!addr("::ffff:100.64.1.1").is_cgnat());
+    }
+
+    #[test]
+    fn test_unique_local() {
+        let addr = |ip| InterfaceBuilder::new().addr(unwrap!(IpAddr::from_str(ip))).build().addr;
+        assert!(addr("fd00::1").is_unique_local());
+        assert_eq!(addr("fd00::1").routability(), Routability::Private);
+        assert!(!addr("2001:db8::1").is_unique_local());
+        assert_eq!(addr("2001:db8::1").routability(), Routability::Global);
+        assert!(!addr("10.0.0.1").is_unique_local());
+    }
+
+    #[test]
+    fn test_for_each_interface() {
+        let ifaces = unwrap!(get_if_addrs());
+        let mut visited = Vec::new();
+        unwrap!(for_each_interface(|interface| {
+            visited.push(interface.clone());
+            false
+        }));
+        assert_eq!(visited, ifaces[..1].to_vec());
+
+        let mut visited = Vec::new();
+        unwrap!(for_each_interface(|interface| {
+            visited.push(interface.clone());
+            true
+        }));
+        assert_eq!(visited, ifaces);
+    }
+
+    #[test]
+    fn test_likely_internet() {
+        let gateways = vec![(unwrap!(IpAddr::from_str("192.168.1.1")), "eth0".to_owned())];
+        assert!(test_addr("8.8.8.8", "255.255.255.0").likely_internet_via(&[]));
+        assert!(test_addr("192.168.1.5", "255.255.255.0").likely_internet_via(&gateways));
+        assert!(!test_addr("192.168.1.5", "255.255.255.0").likely_internet_via(&[]));
+        assert!(!test_addr("192.168.2.5", "255.255.255.0").likely_internet_via(&gateways));
+        assert!(!test_addr("127.0.0.1", "255.0.0.0").likely_internet_via(&gateways));
+    }
+
+    #[test]
+    fn test_down_interface_filtering() {
+        let interface = |name, flags| InterfaceBuilder::new().name(name).flags(flags).build();
+        let ifaces = vec![interface("eth0", IfFlags::UP | IfFlags::RUNNING),
+                          interface("eth1", IfFlags::UP),
+                          interface("eth2", IfFlags::BROADCAST),
+                          interface("eth3", IfFlags::empty())];
+        assert_eq!(drop_down(ifaces.clone()), ifaces[..2].to_vec());
+
+        let all = unwrap!(get_if_addrs_all());
+        let up = unwrap!(get_if_addrs_with_link_local());
+        assert!(up.iter().all(|interface| all.contains(interface)));
+        assert!(up.iter().all(|interface| interface.flags.contains(IfFlags::UP)));
+        assert_eq!(up.len(),
+                   all.iter().filter(|interface| interface.flags.contains(IfFlags::UP)).count());
+    }
+
+    #[test]
+    fn test_matching_flags() {
+        let interface = |name, flags| InterfaceBuilder::new().name(name).flags(flags).build();
+        let required = IfFlags::UP | IfFlags::MULTICAST;
+        let ifaces = vec![interface("eth0", required | IfFlags::BROADCAST),
+                          interface("eth1", IfFlags::UP | IfFlags::BROADCAST),
+                          interface("eth2", IfFlags::MULTICAST),
+                          interface("lo", required | IfFlags::LOOPBACK),
+                          interface("wg0", required | IfFlags::POINT_TO_POINT)];
+        assert_eq!(matching_flags(ifaces.clone(), required, IfFlags::LOOPBACK),
+                   vec![ifaces[0].clone(), ifaces[4].clone()]);
+        assert_eq!(matching_flags(ifaces.clone(), IfFlags::empty(), IfFlags::empty()), ifaces);
+        assert_eq!(matching_flags(ifaces.clone(),
+                                  IfFlags::empty(),
+                                  IfFlags::LOOPBACK | IfFlags::POINT_TO_POINT),
+                   ifaces[..3].to_vec());
+
+        for interface in unwrap!(get_if_addrs_matching_flags(required, IfFlags::LOOPBACK)) {
+            assert!(interface.flags.contains(required));
+            assert!(!interface.flags.contains(IfFlags::LOOPBACK));
+        }
+    }
+
+    #[test]
+    fn test_name_map() {
+        let interface = |name| InterfaceBuilder::new().name(name).build();
+        let friendly = |name: &str| if name == "docker0" {
+            "vdocker".to_string()
+        } else {
+            name.to_string()
+        };
+        assert_eq!(rename(vec![interface("eth0"), interface("docker0")], &friendly),
+                   vec![interface("eth0"), interface("vdocker")]);
+
+        let ifaces = unwrap!(get_if_addrs());
+        let renamed = unwrap!(get_if_addrs_with_name_map(|name| format!("v{}", name)));
+        assert_eq!(renamed.len(), ifaces.len());
+        for (interface, renamed) in ifaces.iter().zip(&renamed) {
+            assert_eq!(renamed.name, format!("v{}", interface.name));
+        }
+    }
+}
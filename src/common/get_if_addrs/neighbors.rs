@@ -0,0 +1,188 @@
+// The number of neighbours an interface has in the IPv4 neighbour (ARP) cache, as a hint of it
+// being connected to other hosts. Linux exposes the cache as text in `/proc/net/arp` and Windows
+// through `GetIpNetTable`. The text is parsed from a plain reader so tests can feed it samples.
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+use std::fs::File;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+use std::io::{BufRead, BufReader};
+
+use common::get_if_addrs::Interface;
+
+/// Get the number of hosts in the IPv4 neighbour cache of the interface `interface` is an address
+/// of, which is the number of hosts on its link this machine has talked to recently. An interface
+/// which just came up and has none is probably of no use for discovering peers on the LAN yet.
+///
+/// The cache is of the interface rather than of the address, so all the addresses of an interface
+/// get the same count, IPv6 ones included.
+///
+/// Reading the neighbour cache is implemented on Linux, Android and Windows: elsewhere this is
+/// always 0.
+pub fn neighbor_count(interface: &Interface) -> usize {
+    count_neighbors(interface)
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn count_neighbors(interface: &Interface) -> usize {
+    // Aliases such as `eth0:0` are addresses of the interface before the colon.
+    let name = interface.name.split(':').next().unwrap_or("");
+    match File::open(PROC_NET_ARP) {
+        Ok(file) => count_in_proc_net_arp(BufReader::new(file), name),
+        Err(e) => {
+            warn!("Could not read {}: {:?}", PROC_NET_ARP, e);
+            0
+        }
+    }
+}
+
+#[cfg(windows)]
+fn count_neighbors(interface: &Interface) -> usize {
+    match interface.index {
+        0 => 0,
+        index => windows::neighbor_count(index),
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android", windows)))]
+fn count_neighbors(_interface: &Interface) -> usize {
+    0
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+const PROC_NET_ARP: &'static str = "/proc/net/arp";
+
+// The flag of a complete entry, one whose hardware address is known.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+const ATF_COM: u32 = 0x2;
+
+// Count the complete entries of the device `name` in the contents of `/proc/net/arp`, which after
+// a header line has a line like `192.168.1.1 0x1 0x2 52:54:00:12:34:56 * eth0` per entry, giving
+// the address, the hardware type, the flags, the hardware address, the mask and the device.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn count_in_proc_net_arp<R: BufRead>(reader: R, name: &str) -> usize {
+    let mut count = 0;
+    for line in reader.lines().skip(1) {
+        let line = match line {
+            Ok(line) => line,
+            Err(_) => break,
+        };
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.len() < 6 || fields[5] != name {
+            continue;
+        }
+        let flags = match u32::from_str_radix(fields[2].trim_start_matches("0x"), 16) {
+            Ok(flags) => flags,
+            Err(_) => continue,
+        };
+        if flags & ATF_COM != 0 {
+            count += 1;
+        }
+    }
+    count
+}
+
+#[cfg(windows)]
+mod windows {
+    use std::{ptr, slice};
+
+    use libc::c_ulong;
+    use winapi::{DWORD, ERROR_SUCCESS};
+
+    // `MIB_IPNETROW`.
+    #[repr(C)]
+    #[derive(Clone, Copy)]
+    struct MibIpNetRow {
+        index: DWORD,
+        phys_addr_len: DWORD,
+        phys_addr: [u8; 8],
+        addr: DWORD,
+        entry_type: DWORD,
+    }
+
+    // `MIB_IPNETTABLE`, whose `table` really holds `num_entries` rows.
+    #[repr(C)]
+    struct MibIpNetTable {
+        num_entries: DWORD,
+        table: [MibIpNetRow; 1],
+    }
+
+    const ERROR_INSUFFICIENT_BUFFER: DWORD = 122;
+    const ERROR_NO_DATA: DWORD = 232;
+    // The type of an entry which is no longer valid.
+    const MIB_IPNET_TYPE_INVALID: DWORD = 2;
+
+    #[link(name="Iphlpapi")]
+    extern "system" {
+        fn GetIpNetTable(table: *mut MibIpNetTable, size: *mut c_ulong, order: i32) -> DWORD;
+    }
+
+    // The number of valid entries of the interface `index`. Entries can appear in between asking
+    // for the size of the table and for the table, so keep growing the buffer while it's too
+    // small.
+    #[allow(unsafe_code)]
+    pub fn neighbor_count(index: u32) -> usize {
+        let mut size: c_ulong = 0;
+        // A buffer of `u32`s so that it is aligned for the table.
+        let mut buffer: Vec<u32> = Vec::new();
+        loop {
+            let table = if buffer.is_empty() {
+                ptr::null_mut()
+            } else {
+                buffer.as_mut_ptr() as *mut MibIpNetTable
+            };
+            match unsafe { GetIpNetTable(table, &mut size, 0) } {
+                ERROR_SUCCESS if !table.is_null() => break,
+                ERROR_SUCCESS | ERROR_NO_DATA => return 0,
+                ERROR_INSUFFICIENT_BUFFER => {
+                    buffer = vec![0; (size as usize + 3) / 4];
+                }
+                code => {
+                    warn!("GetIpNetTable failed: {}", code);
+                    return 0;
+                }
+            }
+        }
+        let table = buffer.as_ptr() as *const MibIpNetTable;
+        let rows = unsafe {
+            slice::from_raw_parts((*table).table.as_ptr(), (*table).num_entries as usize)
+        };
+        rows.iter()
+            .filter(|row| row.index == index && row.entry_type != MIB_IPNET_TYPE_INVALID)
+            .count()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use common::get_if_addrs::get_if_addrs;
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    #[test]
+    fn counts_complete_entries_of_the_device() {
+        let sample = "\
+IP address       HW type     Flags       HW address            Mask     Device
+192.168.1.1      0x1         0x2         52:54:00:12:34:56     *        eth0
+192.168.1.7      0x1         0x0         00:00:00:00:00:00     *        eth0
+192.168.1.9      0x1         0x6         52:54:00:12:34:57     *        eth0
+10.0.0.1         0x1         0x2         52:54:00:12:34:58     *        wlan0
+192.168.1.10     0x1         zz          52:54:00:12:34:59     *        eth0
+192.168.1.11     0x1         0x2
+";
+        assert_eq!(count_in_proc_net_arp(sample.as_bytes(), "eth0"), 2);
+        assert_eq!(count_in_proc_net_arp(sample.as_bytes(), "wlan0"), 1);
+        assert_eq!(count_in_proc_net_arp(sample.as_bytes(), "eth1"), 0);
+        assert_eq!(count_in_proc_net_arp(&b"IP address HW type\n"[..], "eth0"), 0);
+    }
+
+    #[test]
+    fn counts_on_this_machine() {
+        for interface in unwrap!(get_if_addrs()) {
+            // Loopback never needs address resolution.
+            if interface.is_loopback() {
+                assert_eq!(neighbor_count(&interface), 0);
+            }
+        }
+    }
+}
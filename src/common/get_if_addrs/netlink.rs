@@ -0,0 +1,627 @@
+// Enumeration through a `NETLINK_ROUTE` socket. This sees more than `getifaddrs` does, e.g. the
+// lifetimes of IPv6 addresses. Messages are parsed from plain byte buffers so the parsing can be
+// tested with synthetic messages.
+
+use std::collections::HashMap;
+use std::ffi::CStr;
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use common::get_if_addrs::{AddrFlags, AddrOrigin, IfAddr, IfFlags, Ifv4Addr, Ifv6Addr,
+                           Interface, Routability, drop_down, drop_link_local};
+use common::get_if_addrs::posix::convert_flags;
+use common::get_if_addrs::{route, vlan};
+use libc::{self, c_char, c_int, c_void};
+
+const AF_NETLINK: c_int = 16;
+const NETLINK_ROUTE: c_int = 0;
+
+const NLMSG_ERROR: u16 = 2;
+const NLMSG_DONE: u16 = 3;
+const RTM_NEWLINK: u16 = 16;
+const RTM_GETLINK: u16 = 18;
+const RTM_NEWADDR: u16 = 20;
+const RTM_GETADDR: u16 = 22;
+
+const NLM_F_REQUEST: u16 = 0x1;
+const NLM_F_DUMP: u16 = 0x300;
+
+// The multicast groups notifying about changes to links and addresses.
+#[cfg(feature = "async")]
+const RTMGRP_LINK: u32 = 0x1;
+#[cfg(feature = "async")]
+const RTMGRP_IPV4_IFADDR: u32 = 0x10;
+#[cfg(feature = "async")]
+const RTMGRP_IPV6_IFADDR: u32 = 0x100;
+
+const IFA_ADDRESS: u16 = 1;
+const IFA_LOCAL: u16 = 2;
+const IFA_LABEL: u16 = 3;
+const IFA_BROADCAST: u16 = 4;
+const IFA_CACHEINFO: u16 = 6;
+const IFA_FLAGS: u16 = 8;
+
+const IFA_F_TEMPORARY: u32 = 0x1;
+const IFA_F_DADFAILED: u32 = 0x8;
+const IFA_F_DEPRECATED: u32 = 0x20;
+const IFA_F_TENTATIVE: u32 = 0x40;
+const IFA_F_PERMANENT: u32 = 0x80;
+
+// Sizes of `struct nlmsghdr`, `struct ifinfomsg`, `struct ifaddrmsg` and `struct rtattr`.
+const NLMSG_HDR_LEN: usize = 16;
+const IFINFOMSG_LEN: usize = 16;
+const IFADDRMSG_LEN: usize = 8;
+const RTATTR_LEN: usize = 4;
+// Size of `struct sockaddr_nl`.
+#[cfg(feature = "async")]
+const SOCKADDR_NL_LEN: usize = 12;
+
+const IF_NAMESIZE: usize = 16;
+const RECV_BUF_LEN: usize = 32 * 1024;
+
+/// Get a list of all the network interfaces on this machine along with their IP info, asking the
+/// kernel through netlink rather than `getifaddrs`.
+///
+/// Unlike `get_if_addrs()`, this also reports the preferred and valid lifetimes of each address.
+/// Interfaces which are down and link-local addresses are left out just the same, and so are
+/// IPv6 addresses which can't be bound to yet or at all because Duplicate Address Detection is
+/// still in progress or failed.
+pub fn get_if_addrs_netlink() -> io::Result<Vec<Interface>> {
+    get_if_addrs_netlink_with_link_local().map(drop_link_local)
+}
+
+/// Get the same as `get_if_addrs_netlink()`, link-local addresses included.
+pub fn get_if_addrs_netlink_with_link_local() -> io::Result<Vec<Interface>> {
+    let socket = try!(NetlinkSocket::new());
+
+    let mut link_flags = HashMap::new();
+    for (msg_type, payload) in try!(socket.dump(RTM_GETLINK, IFINFOMSG_LEN)) {
+        if msg_type == RTM_NEWLINK {
+            if let Some((index, flags)) = parse_link(&payload) {
+                let _ = link_flags.insert(index, flags);
+            }
+        }
+    }
+
+    let routes = route::default_routes();
+    let vlan_ids = vlan::vlan_ids(Path::new(vlan::PROC_NET_VLAN));
+    let mut ret = Vec::new();
+    for (msg_type, payload) in try!(socket.dump(RTM_GETADDR, IFADDRMSG_LEN)) {
+        if msg_type == RTM_NEWADDR {
+            if let Some(mut interface) = parse_addr(&payload, &link_flags) {
+                interface.vlan_id = vlan_ids.get(&interface.name).cloned();
+                interface.metric =
+                    route::default_route_metric(&routes, &interface.name, &interface.addr.ip());
+                ret.push(interface);
+            }
+        }
+    }
+    Ok(drop_down(drop_unbindable(ret)))
+}
+
+// Leave out the addresses which are tentative or failed Duplicate Address Detection.
+fn drop_unbindable(ifaces: Vec<Interface>) -> Vec<Interface> {
+    ifaces.into_iter()
+        .filter(|interface| {
+            !interface.addr_flags.contains(AddrFlags::TENTATIVE) &&
+            !interface.addr_flags.contains(AddrFlags::DAD_FAILED)
+        })
+        .collect()
+}
+
+struct NetlinkSocket {
+    fd: c_int,
+}
+
+impl NetlinkSocket {
+    #[allow(unsafe_code)]
+    fn new() -> io::Result<NetlinkSocket> {
+        let fd = unsafe { libc::socket(AF_NETLINK, libc::SOCK_RAW, NETLINK_ROUTE) };
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(NetlinkSocket { fd: fd })
+    }
+
+    // Send a dump request of type `msg_type` and collect the `(type, payload)` of every message
+    // in the reply. `body_len` is the size of the (zeroed, so `AF_UNSPEC`) request body.
+    #[allow(unsafe_code)]
+    fn dump(&self, msg_type: u16, body_len: usize) -> io::Result<Vec<(u16, Vec<u8>)>> {
+        static SEQ: AtomicUsize = AtomicUsize::new(0);
+
+        let mut request = vec![0u8; NLMSG_HDR_LEN + body_len];
+        write_u32(&mut request[0..], (NLMSG_HDR_LEN + body_len) as u32);
+        write_u16(&mut request[4..], msg_type);
+        write_u16(&mut request[6..], NLM_F_REQUEST | NLM_F_DUMP);
+        write_u32(&mut request[8..], SEQ.fetch_add(1, Ordering::Relaxed) as u32);
+
+        let sent = unsafe {
+            libc::send(self.fd, request.as_ptr() as *const c_void, request.len(), 0)
+        };
+        if sent < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        let mut ret = Vec::new();
+        let mut buf = vec![0u8; RECV_BUF_LEN];
+        loop {
+            let received = unsafe {
+                libc::recv(self.fd, buf.as_mut_ptr() as *mut c_void, buf.len(), 0)
+            };
+            if received < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            for (msg_type, payload) in split_messages(&buf[..received as usize]) {
+                match msg_type {
+                    NLMSG_DONE => return Ok(ret),
+                    NLMSG_ERROR => {
+                        let errno = if payload.len() >= 4 { read_u32(payload) as i32 } else { 0 };
+                        return Err(io::Error::from_raw_os_error(-errno));
+                    }
+                    _ => ret.push((msg_type, payload.to_vec())),
+                }
+            }
+        }
+    }
+}
+
+/// A netlink socket subscribed to the notifications the kernel sends whenever a link or an address
+/// changes, for re-enumerating the interfaces only when there may be something new.
+#[cfg(feature = "async")]
+pub struct ChangeMonitor {
+    socket: NetlinkSocket,
+}
+
+#[cfg(feature = "async")]
+impl ChangeMonitor {
+    /// Subscribe to the notifications about links and addresses of either family.
+    #[allow(unsafe_code)]
+    pub fn new() -> io::Result<ChangeMonitor> {
+        let socket = try!(NetlinkSocket::new());
+        let mut sa = [0u8; SOCKADDR_NL_LEN];
+        write_u16(&mut sa[0..], AF_NETLINK as u16);
+        write_u32(&mut sa[8..], RTMGRP_LINK | RTMGRP_IPV4_IFADDR | RTMGRP_IPV6_IFADDR);
+        let bound = unsafe {
+            libc::bind(socket.fd,
+                       sa.as_ptr() as *const libc::sockaddr,
+                       SOCKADDR_NL_LEN as libc::socklen_t)
+        };
+        if bound < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(ChangeMonitor { socket: socket })
+    }
+
+    /// Wait up to `timeout` for notifications, returning whether any arrived. All those pending
+    /// are consumed, however many there are.
+    #[allow(unsafe_code)]
+    pub fn wait(&self, timeout: ::std::time::Duration) -> io::Result<bool> {
+        let mut fd = libc::pollfd {
+            fd: self.socket.fd,
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        let timeout_ms = timeout.as_secs() * 1000 + u64::from(timeout.subsec_nanos() / 1_000_000);
+        let timeout_ms = timeout_ms.min(c_int::max_value() as u64) as c_int;
+        match unsafe { libc::poll(&mut fd, 1, timeout_ms) } {
+            n if n < 0 => {
+                let e = io::Error::last_os_error();
+                return if e.kind() == io::ErrorKind::Interrupted {
+                    Ok(false)
+                } else {
+                    Err(e)
+                };
+            }
+            0 => return Ok(false),
+            _ => (),
+        }
+
+        let mut buf = vec![0u8; RECV_BUF_LEN];
+        loop {
+            let received = unsafe {
+                libc::recv(self.socket.fd,
+                           buf.as_mut_ptr() as *mut c_void,
+                           buf.len(),
+                           libc::MSG_DONTWAIT)
+            };
+            if received >= 0 {
+                continue;
+            }
+            let e = io::Error::last_os_error();
+            match e.raw_os_error() {
+                // Notifications were dropped for want of buffer space, which is still a change.
+                Some(libc::ENOBUFS) => continue,
+                Some(libc::EINTR) => continue,
+                _ if e.kind() == io::ErrorKind::WouldBlock => return Ok(true),
+                _ => return Err(e),
+            }
+        }
+    }
+}
+
+impl Drop for NetlinkSocket {
+    #[allow(unsafe_code)]
+    fn drop(&mut self) {
+        let _ = unsafe { libc::close(self.fd) };
+    }
+}
+
+// Split a buffer of netlink messages into their `(type, payload)`s, stopping at the first
+// malformed header.
+fn split_messages(mut buf: &[u8]) -> Vec<(u16, &[u8])> {
+    let mut ret = Vec::new();
+    while buf.len() >= NLMSG_HDR_LEN {
+        let len = read_u32(buf) as usize;
+        if len < NLMSG_HDR_LEN || len > buf.len() {
+            break;
+        }
+        ret.push((read_u16(&buf[4..]), &buf[NLMSG_HDR_LEN..len]));
+        buf = &buf[align(len).min(buf.len())..];
+    }
+    ret
+}
+
+// Split the route attributes following a message body into their `(type, payload)`s.
+fn split_attrs(mut buf: &[u8]) -> Vec<(u16, &[u8])> {
+    let mut ret = Vec::new();
+    while buf.len() >= RTATTR_LEN {
+        let len = read_u16(buf) as usize;
+        if len < RTATTR_LEN || len > buf.len() {
+            break;
+        }
+        ret.push((read_u16(&buf[2..]), &buf[RTATTR_LEN..len]));
+        buf = &buf[align(len).min(buf.len())..];
+    }
+    ret
+}
+
+// The index and flags of the link described by the payload of a `RTM_NEWLINK`.
+fn parse_link(payload: &[u8]) -> Option<(u32, IfFlags)> {
+    if payload.len() < IFINFOMSG_LEN {
+        return None;
+    }
+    Some((read_u32(&payload[4..]), convert_flags(read_u32(&payload[8..]))))
+}
+
+// The address described by the payload of a `RTM_NEWADDR`.
+fn parse_addr(payload: &[u8], link_flags: &HashMap<u32, IfFlags>) -> Option<Interface> {
+    if payload.len() < IFADDRMSG_LEN {
+        return None;
+    }
+    let family = payload[0] as c_int;
+    let prefix_len = payload[1] as u32;
+    // Only the lower 8 bits of the flags fit here, `IFA_FLAGS` has all of them if present.
+    let mut ifa_flags = payload[2] as u32;
+    let index = read_u32(&payload[4..]);
+
+    let (mut address, mut local, mut broadcast, mut label) = (None, None, None, None);
+    let (mut preferred_lft, mut valid_lft) = (None, None);
+    for (attr_type, attr) in split_attrs(&payload[IFADDRMSG_LEN..]) {
+        match attr_type {
+            IFA_ADDRESS => address = parse_ip(family, attr),
+            IFA_LOCAL => local = parse_ip(family, attr),
+            IFA_BROADCAST => broadcast = parse_ip(family, attr),
+            IFA_LABEL => {
+                let nul = attr.iter().position(|&b| b == 0).unwrap_or(attr.len());
+                label = Some(String::from_utf8_lossy(&attr[..nul]).into_owned());
+            }
+            IFA_CACHEINFO if attr.len() >= 8 => {
+                preferred_lft = Some(read_u32(attr));
+                valid_lft = Some(read_u32(&attr[4..]));
+            }
+            IFA_FLAGS if attr.len() >= 4 => ifa_flags = read_u32(attr),
+            _ => (),
+        }
+    }
+
+    // On point-to-point links `IFA_ADDRESS` is the peer and `IFA_LOCAL` our own address. A prefix
+    // longer than the address is malformed.
+    let addr = match local.or(address) {
+        Some(IpAddr::V4(ip)) if prefix_len <= 32 => {
+            IfAddr::V4(Ifv4Addr {
+                ip: ip,
+                netmask: Ipv4Addr::from((!0u32).checked_shl(32 - prefix_len).unwrap_or(0)),
+                broadcast: match broadcast {
+                    Some(IpAddr::V4(broadcast)) => Some(broadcast),
+                    _ => None,
+                },
+            })
+        }
+        Some(IpAddr::V6(ip)) if prefix_len <= 128 => {
+            IfAddr::V6(Ifv6Addr {
+                ip: ip,
+                netmask: Ipv6Addr::from((!0u128).checked_shl(128 - prefix_len).unwrap_or(0)),
+                broadcast: None,
+            })
+        }
+        _ => return None,
+    };
+    let peer = match (local, address) {
+        (Some(local), Some(address)) if local != address => Some(address),
+        _ => None,
+    };
+
+    let name = match label {
+        Some(label) => label,
+        None => index_to_name(index),
+    };
+    let origin = guess_origin(&addr, valid_lft, ifa_flags);
+    Some(Interface {
+        name: name,
+        addr: addr,
+        index: index,
+        flags: link_flags.get(&index).cloned().unwrap_or_else(IfFlags::empty),
+        preferred_lft: preferred_lft,
+        valid_lft: valid_lft,
+        addr_flags: convert_addr_flags(ifa_flags),
+        dns_suffix: None,
+        description: None,
+        peer: peer,
+        origin: origin,
+        vlan_id: None,
+        metric: None,
+    })
+}
+
+// A best guess at the origin of an address, netlink not telling. DHCP clients typically add IPv4
+// addresses with the lifetime of their lease, whereas addresses added without a lifetime, as static
+// ones are, are flagged permanent. Clients which add their leases without a lifetime can't be told
+// apart from static configuration, and finite IPv6 lifetimes are mostly those of autoconfiguration.
+fn guess_origin(addr: &IfAddr, valid_lft: Option<u32>, ifa_flags: u32) -> AddrOrigin {
+    match addr.routability() {
+        Routability::Loopback => return AddrOrigin::WellKnown,
+        Routability::LinkLocal => return AddrOrigin::LinkLocal,
+        _ => (),
+    }
+    match (addr, valid_lft) {
+        _ if ifa_flags & IFA_F_PERMANENT != 0 => AddrOrigin::Manual,
+        (&IfAddr::V4(_), Some(valid_lft)) if valid_lft != u32::max_value() => AddrOrigin::Dhcp,
+        _ => AddrOrigin::Other,
+    }
+}
+
+fn convert_addr_flags(ifa_flags: u32) -> AddrFlags {
+    let mut flags = AddrFlags::empty();
+    if ifa_flags & IFA_F_TEMPORARY != 0 {
+        flags.insert(AddrFlags::TEMPORARY);
+    }
+    if ifa_flags & IFA_F_DEPRECATED != 0 {
+        flags.insert(AddrFlags::DEPRECATED);
+    }
+    if ifa_flags & IFA_F_TENTATIVE != 0 {
+        flags.insert(AddrFlags::TENTATIVE);
+    }
+    if ifa_flags & IFA_F_DADFAILED != 0 {
+        flags.insert(AddrFlags::DAD_FAILED);
+    }
+    flags
+}
+
+fn parse_ip(family: c_int, attr: &[u8]) -> Option<IpAddr> {
+    if family == libc::AF_INET && attr.len() == 4 {
+        Some(IpAddr::V4(Ipv4Addr::new(attr[0], attr[1], attr[2], attr[3])))
+    } else if family == libc::AF_INET6 && attr.len() == 16 {
+        let mut octets = [0u8; 16];
+        octets.copy_from_slice(attr);
+        Some(IpAddr::V6(Ipv6Addr::from(octets)))
+    } else {
+        None
+    }
+}
+
+// IPv6 addresses don't carry an `IFA_LABEL`, so their name has to be looked up from the index.
+#[allow(unsafe_code)]
+fn index_to_name(index: u32) -> String {
+    let mut buf: [c_char; IF_NAMESIZE] = [0; IF_NAMESIZE];
+    if unsafe { libc::if_indextoname(index, buf.as_mut_ptr()) }.is_null() {
+        return String::new();
+    }
+    unsafe { CStr::from_ptr(buf.as_ptr()) }.to_string_lossy().into_owned()
+}
+
+fn align(len: usize) -> usize {
+    (len + 3) & !3
+}
+
+fn read_u16(buf: &[u8]) -> u16 {
+    u16::from_ne_bytes([buf[0], buf[1]])
+}
+
+fn read_u32(buf: &[u8]) -> u32 {
+    u32::from_ne_bytes([buf[0], buf[1], buf[2], buf[3]])
+}
+
+fn write_u16(buf: &mut [u8], value: u16) {
+    buf[..2].copy_from_slice(&value.to_ne_bytes());
+}
+
+fn write_u32(buf: &mut [u8], value: u32) {
+    buf[..4].copy_from_slice(&value.to_ne_bytes());
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::collections::HashMap;
+    use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+    use std::str::FromStr;
+
+    use common::get_if_addrs::{AddrFlags, IfAddr, IfFlags};
+    use libc;
+
+    fn attr(attr_type: u16, payload: &[u8]) -> Vec<u8> {
+        let mut ret = vec![0u8; RTATTR_LEN];
+        write_u16(&mut ret[0..], (RTATTR_LEN + payload.len()) as u16);
+        write_u16(&mut ret[2..], attr_type);
+        ret.extend_from_slice(payload);
+        while ret.len() % 4 != 0 {
+            ret.push(0);
+        }
+        ret
+    }
+
+    fn cache_info(preferred_lft: u32, valid_lft: u32) -> Vec<u8> {
+        let mut ret = vec![0u8; 16];
+        write_u32(&mut ret[0..], preferred_lft);
+        write_u32(&mut ret[4..], valid_lft);
+        ret
+    }
+
+    // The payload of a `RTM_NEWADDR` for `ip/prefix_len` on interface `index`.
+    fn addr_payload(ip: &IpAddr, prefix_len: u8, index: u32, attrs: &[Vec<u8>]) -> Vec<u8> {
+        let mut ret = vec![0u8; IFADDRMSG_LEN];
+        ret[1] = prefix_len;
+        write_u32(&mut ret[4..], index);
+        match *ip {
+            IpAddr::V4(ip) => {
+                ret[0] = libc::AF_INET as u8;
+                ret.extend(attr(IFA_LOCAL, &ip.octets()));
+            }
+            IpAddr::V6(ip) => {
+                ret[0] = libc::AF_INET6 as u8;
+                ret.extend(attr(IFA_ADDRESS, &ip.octets()));
+            }
+        }
+        for attr in attrs {
+            ret.extend_from_slice(attr);
+        }
+        ret
+    }
+
+    #[test]
+    fn parses_lifetimes_of_v6_address() {
+        let ip = IpAddr::V6(unwrap!(Ipv6Addr::from_str("2001:db8::5")));
+        let payload = addr_payload(&ip, 64, 2, &[attr(IFA_CACHEINFO, &cache_info(600, 3600))]);
+        let mut link_flags = HashMap::new();
+        let _ = link_flags.insert(2, IfFlags::UP);
+
+        let interface = unwrap!(parse_addr(&payload, &link_flags));
+        assert_eq!(interface.addr.ip(), ip);
+        assert_eq!(interface.addr.prefix_len(), Some(64));
+        assert_eq!(interface.flags, IfFlags::UP);
+        assert_eq!(interface.preferred_lft, Some(600));
+        assert_eq!(interface.valid_lft, Some(3600));
+    }
+
+    #[test]
+    fn guesses_origins() {
+        let v4 = |a, b, c, d| IpAddr::V4(Ipv4Addr::new(a, b, c, d));
+        let origin = |ip: &IpAddr, attrs: &[Vec<u8>], ifa_flags: u32| {
+            let mut payload = addr_payload(ip, 24, 2, attrs);
+            payload[2] = ifa_flags as u8;
+            unwrap!(parse_addr(&payload, &HashMap::new())).origin
+        };
+        let lease = [attr(IFA_CACHEINFO, &cache_info(1800, 3600))];
+        let forever = [attr(IFA_CACHEINFO, &cache_info(!0, !0))];
+        assert_eq!(origin(&v4(192, 168, 1, 5), &lease, 0), AddrOrigin::Dhcp);
+        assert_eq!(origin(&v4(192, 168, 1, 5), &forever, IFA_F_PERMANENT), AddrOrigin::Manual);
+        assert_eq!(origin(&v4(192, 168, 1, 5), &[], 0), AddrOrigin::Other);
+        assert_eq!(origin(&v4(127, 0, 0, 1), &forever, IFA_F_PERMANENT), AddrOrigin::WellKnown);
+        assert_eq!(origin(&v4(169, 254, 3, 4), &forever, IFA_F_PERMANENT), AddrOrigin::LinkLocal);
+        let v6 = IpAddr::V6(unwrap!(Ipv6Addr::from_str("2001:db8::5")));
+        assert_eq!(origin(&v6, &lease, 0), AddrOrigin::Other);
+    }
+
+    #[test]
+    fn parses_temporary_flag() {
+        let ip = IpAddr::V6(unwrap!(Ipv6Addr::from_str("2001:db8::1234")));
+        let mut payload = addr_payload(&ip, 64, 2, &[]);
+        payload[2] = IFA_F_TEMPORARY as u8;
+
+        let interface = unwrap!(parse_addr(&payload, &HashMap::new()));
+        assert_eq!(interface.addr_flags, AddrFlags::TEMPORARY);
+    }
+
+    #[test]
+    fn tentative_and_dad_failed_addresses_are_dropped() {
+        let ip = |ip| IpAddr::V6(unwrap!(Ipv6Addr::from_str(ip)));
+        let mut tentative = addr_payload(&ip("2001:db8::1"), 64, 2, &[]);
+        tentative[2] = IFA_F_TENTATIVE as u8;
+        let mut flags = [0; 4];
+        write_u32(&mut flags, IFA_F_DADFAILED | IFA_F_TENTATIVE);
+        let dad_failed = addr_payload(&ip("2001:db8::2"), 64, 2, &[attr(IFA_FLAGS, &flags)]);
+        let settled = addr_payload(&ip("2001:db8::3"), 64, 2, &[]);
+
+        let ifaces: Vec<Interface> = [tentative, dad_failed, settled]
+            .iter()
+            .map(|payload| unwrap!(parse_addr(payload, &HashMap::new())))
+            .collect();
+        assert_eq!(ifaces[0].addr_flags, AddrFlags::TENTATIVE);
+        assert_eq!(ifaces[1].addr_flags, AddrFlags::TENTATIVE | AddrFlags::DAD_FAILED);
+        assert_eq!(ifaces[2].addr_flags, AddrFlags::empty());
+        assert_eq!(drop_unbindable(ifaces.clone()), ifaces[2..].to_vec());
+    }
+
+    #[test]
+    fn v4_address_is_named_by_its_label() {
+        let ip = IpAddr::V4(Ipv4Addr::new(192, 168, 1, 5));
+        let payload = addr_payload(&ip, 24, 2, &[attr(IFA_LABEL, b"eth0:1\0")]);
+
+        let interface = unwrap!(parse_addr(&payload, &HashMap::new()));
+        assert_eq!(interface.name, "eth0:1");
+        assert_eq!(interface.addr.ip(), ip);
+        assert_eq!(interface.addr.prefix_len(), Some(24));
+        assert_eq!(interface.valid_lft, None);
+    }
+
+    #[test]
+    fn prefixes_longer_than_the_address_are_rejected() {
+        let prefix_len = |ip: &IpAddr, prefix_len| {
+            parse_addr(&addr_payload(ip, prefix_len, 2, &[]), &HashMap::new())
+                .map(|interface| interface.addr.prefix_len())
+        };
+        let v4 = IpAddr::V4(Ipv4Addr::new(192, 168, 1, 5));
+        assert_eq!(prefix_len(&v4, 32), Some(Some(32)));
+        assert_eq!(prefix_len(&v4, 33), None);
+        assert_eq!(prefix_len(&v4, 255), None);
+        let v6 = IpAddr::V6(unwrap!(Ipv6Addr::from_str("2001:db8::5")));
+        assert_eq!(prefix_len(&v6, 128), Some(Some(128)));
+        assert_eq!(prefix_len(&v6, 129), None);
+    }
+
+    #[test]
+    fn point_to_point_address_has_its_peer() {
+        let ip = IpAddr::V4(Ipv4Addr::new(10, 8, 0, 2));
+        let peer = IpAddr::V4(Ipv4Addr::new(10, 8, 0, 1));
+        let payload = addr_payload(&ip,
+                                   32,
+                                   2,
+                                   &[attr(IFA_ADDRESS, &[10, 8, 0, 1]), attr(IFA_LABEL, b"wg0\0")]);
+        let mut link_flags = HashMap::new();
+        let _ = link_flags.insert(2, IfFlags::UP | IfFlags::POINT_TO_POINT);
+
+        let interface = unwrap!(parse_addr(&payload, &link_flags));
+        assert_eq!(interface.addr.ip(), ip);
+        match interface.addr {
+            IfAddr::V4(ref ifv4_addr) => assert_eq!(ifv4_addr.broadcast, None),
+            IfAddr::V6(_) => panic!("Expected an IPv4 address"),
+        }
+        assert_eq!(interface.peer, Some(peer));
+        assert!(interface.is_tunnel());
+
+        let payload = addr_payload(&ip, 24, 2, &[attr(IFA_ADDRESS, &[10, 8, 0, 2])]);
+        let local = unwrap!(parse_addr(&payload, &HashMap::new()));
+        assert_eq!(local.peer, None);
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn change_monitor_times_out_without_changes() {
+        let monitor = unwrap!(ChangeMonitor::new());
+        // Something may really change in the meantime, so only the waiting is checked.
+        let _ = unwrap!(monitor.wait(::std::time::Duration::from_millis(10)));
+    }
+
+    #[test]
+    fn enumerated_v6_addresses_have_lifetimes() {
+        let ifaces = unwrap!(get_if_addrs_netlink());
+        assert!(ifaces.iter().any(|interface| interface.is_loopback()));
+        for interface in ifaces.iter().filter(|interface| interface.addr.ip().is_ipv6()) {
+            assert!(interface.valid_lft.is_some());
+            assert!(interface.preferred_lft.is_some());
+        }
+    }
+}
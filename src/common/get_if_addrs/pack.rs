@@ -0,0 +1,180 @@
+// A compact binary encoding of interfaces, for exchanging them with peers at less cost than
+// JSON. Each interface is a family byte (4 or 6), the raw bytes of the IP, the prefix length, the
+// length of the name and its UTF-8 bytes, the index, the flags and the address flags, one after
+// the other with nothing in between. Every number but the family is an unsigned LEB128 varint.
+
+use std::io::{self, ErrorKind};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::str;
+
+use common::get_if_addrs::{AddrFlags, IfFlags, Interface, InterfaceBuilder};
+
+/// Encode `ifaces` for `unpack()`.
+///
+/// Only the name, the IP and prefix length, the index and the flags of the interface and its
+/// address are packed: broadcast addresses, lifetimes and the details only some platforms know are
+/// left out. A netmask which isn't contiguous is packed as a prefix length of 0.
+pub fn pack(ifaces: &[Interface]) -> Vec<u8> {
+    let mut buf = Vec::new();
+    for interface in ifaces {
+        match interface.addr.ip() {
+            IpAddr::V4(ip) => {
+                buf.push(4);
+                buf.extend_from_slice(&ip.octets());
+            }
+            IpAddr::V6(ip) => {
+                buf.push(6);
+                buf.extend_from_slice(&ip.octets());
+            }
+        }
+        write_varint(&mut buf, u32::from(interface.addr.prefix_len().unwrap_or(0)));
+        write_varint(&mut buf, interface.name.len() as u32);
+        buf.extend_from_slice(interface.name.as_bytes());
+        write_varint(&mut buf, interface.index);
+        write_varint(&mut buf, interface.flags.bits());
+        write_varint(&mut buf, interface.addr_flags.bits());
+    }
+    buf
+}
+
+/// Decode the interfaces `pack()` encoded into `buf`. Fails with `io::ErrorKind::InvalidData` if
+/// `buf` isn't exactly a sequence of well-formed interfaces.
+pub fn unpack(buf: &[u8]) -> io::Result<Vec<Interface>> {
+    let mut rest = buf;
+    let mut ifaces = Vec::new();
+    while !rest.is_empty() {
+        let ip = match try!(take(&mut rest, 1))[0] {
+            4 => {
+                let mut octets = [0; 4];
+                octets.copy_from_slice(try!(take(&mut rest, 4)));
+                IpAddr::V4(Ipv4Addr::from(octets))
+            }
+            6 => {
+                let mut octets = [0; 16];
+                octets.copy_from_slice(try!(take(&mut rest, 16)));
+                IpAddr::V6(Ipv6Addr::from(octets))
+            }
+            _ => return Err(invalid("Unknown address family")),
+        };
+        let prefix_len = try!(read_varint(&mut rest));
+        let netmask = match ip {
+            IpAddr::V4(_) if prefix_len <= 32 => {
+                IpAddr::V4(Ipv4Addr::from((!0u32).checked_shl(32 - prefix_len).unwrap_or(0)))
+            }
+            IpAddr::V6(_) if prefix_len <= 128 => {
+                IpAddr::V6(Ipv6Addr::from((!0u128).checked_shl(128 - prefix_len).unwrap_or(0)))
+            }
+            _ => return Err(invalid("Prefix length too long for the family")),
+        };
+        let name_len = try!(read_varint(&mut rest)) as usize;
+        let name = try!(str::from_utf8(try!(take(&mut rest, name_len)))
+            .map_err(|_| invalid("Name is not valid UTF-8")));
+        let index = try!(read_varint(&mut rest));
+        let flags = IfFlags(try!(read_varint(&mut rest)));
+        let addr_flags = AddrFlags(try!(read_varint(&mut rest)));
+        ifaces.push(InterfaceBuilder::new()
+            .name(name)
+            .addr(ip)
+            .netmask(netmask)
+            .index(index)
+            .flags(flags)
+            .addr_flags(addr_flags)
+            .build());
+    }
+    Ok(ifaces)
+}
+
+fn write_varint(buf: &mut Vec<u8>, mut value: u32) {
+    while value >= 0x80 {
+        buf.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    buf.push(value as u8);
+}
+
+// Read a varint of at most 5 bytes, the most a `u32` takes, off the front of `rest`.
+fn read_varint(rest: &mut &[u8]) -> io::Result<u32> {
+    let mut value = 0u64;
+    for shift in 0..5 {
+        let byte = try!(take(rest, 1))[0];
+        value |= u64::from(byte & 0x7f) << (7 * shift);
+        if byte & 0x80 == 0 {
+            if value > u64::from(u32::max_value()) {
+                return Err(invalid("Varint overflows"));
+            }
+            return Ok(value as u32);
+        }
+    }
+    Err(invalid("Varint too long"))
+}
+
+// Take `len` bytes off the front of `rest`.
+fn take<'a>(rest: &mut &'a [u8], len: usize) -> io::Result<&'a [u8]> {
+    if rest.len() < len {
+        return Err(invalid("Truncated interface"));
+    }
+    let (taken, remaining) = rest.split_at(len);
+    *rest = remaining;
+    Ok(taken)
+}
+
+fn invalid(what: &str) -> io::Error {
+    io::Error::new(ErrorKind::InvalidData, what)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use common::get_if_addrs::test_util;
+
+    fn interface(name: &str, ip: &str, netmask: &str, index: u32) -> Interface {
+        Interface {
+            index: index,
+            flags: IfFlags::UP | IfFlags::RUNNING | IfFlags::MULTICAST,
+            ..test_util::interface(name, ip, netmask)
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut temporary = interface("wlan0", "2001:db8::5", "ffff:ffff:ffff:ffff::", 300);
+        temporary.addr_flags = AddrFlags::TEMPORARY;
+        let ifaces = vec![interface("eth0", "192.168.1.5", "255.255.255.0", 2),
+                          interface("lo", "127.0.0.1", "255.0.0.0", 1),
+                          temporary,
+                          interface("wlan0", "fe80::5", "ffff:ffff:ffff:ffff::", 300),
+                          interface("", "::1", "ffff:ffff:ffff:ffff:ffff:ffff:ffff:ffff", 1)];
+
+        let buf = pack(&ifaces);
+        assert_eq!(unwrap!(unpack(&buf)), ifaces);
+        // Family, IP, prefix, name, two bytes of index, flags and address flags.
+        assert_eq!(pack(&ifaces[2..3]).len(), 1 + 16 + 1 + 1 + 5 + 2 + 1 + 1);
+        assert!(unwrap!(unpack(&pack(&[]))).is_empty());
+    }
+
+    #[test]
+    fn malformed_input() {
+        let buf = pack(&[interface("eth0", "192.168.1.5", "255.255.255.0", 2)]);
+        let mut unknown_family = buf.clone();
+        unknown_family[0] = 5;
+        let mut long_prefix = buf.clone();
+        long_prefix[5] = 33;
+        let mut bad_name = buf.clone();
+        bad_name[7] = 0xff;
+        let mut endless_varint = buf[..buf.len() - 1].to_vec();
+        endless_varint.extend_from_slice(&[0xff; 6]);
+
+        for buf in &[&buf[..3],
+                     &buf[..buf.len() - 1],
+                     &unknown_family[..],
+                     &long_prefix[..],
+                     &bad_name[..],
+                     &endless_varint[..]] {
+            match unpack(buf) {
+                Err(ref e) if e.kind() == ErrorKind::InvalidData => (),
+                result => panic!("Unexpected {:?}", result),
+            }
+        }
+    }
+}
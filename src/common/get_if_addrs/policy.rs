@@ -0,0 +1,264 @@
+// A single configurable entry point to the filters and orders the specialised functions of
+// `select` each apply one of, for callers which decide once how to pick interfaces.
+
+use std::io;
+use std::net::IpAddr;
+
+use common::get_if_addrs::{AddrFlags, EnumOrder, Family, Interface, Routability, get_if_addrs};
+use common::get_if_addrs::select::{is_virtual_bridge, network_of, ordered};
+
+/// Which interface addresses `select()` picks, and in which order.
+///
+/// By default loopback and link-local addresses are left out, those of bridges for containers and
+/// virtual machines are kept, both families are picked, no network is excluded, temporary IPv6
+/// addresses are kept and addresses are listed in the order the OS returns them in.
+///
+/// ```
+/// # extern crate crust;
+/// # fn main() {
+/// use crust::get_if_addrs::{EnumOrder, Family, SelectionPolicy};
+/// use std::net::{IpAddr, Ipv4Addr};
+///
+/// let policy = SelectionPolicy::new()
+///     .exclude_virtual(true)
+///     .family(Family::V4)
+///     .exclude_network(IpAddr::V4(Ipv4Addr::new(10, 20, 0, 0)), 16)
+///     .order(EnumOrder::ByInterface);
+/// # let _ = policy;
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SelectionPolicy {
+    exclude_loopback: bool,
+    exclude_virtual: bool,
+    family: Option<Family>,
+    include_link_local: bool,
+    excluded_networks: Vec<(IpAddr, u8)>,
+    prefer_stable_v6: bool,
+    order: EnumOrder,
+}
+
+impl SelectionPolicy {
+    /// Start with the default policy.
+    pub fn new() -> SelectionPolicy {
+        SelectionPolicy {
+            exclude_loopback: true,
+            exclude_virtual: false,
+            family: None,
+            include_link_local: false,
+            excluded_networks: Vec::new(),
+            prefer_stable_v6: false,
+            order: EnumOrder::AsReturned,
+        }
+    }
+
+    /// Set whether to leave out loopback addresses.
+    pub fn exclude_loopback(mut self, exclude_loopback: bool) -> SelectionPolicy {
+        self.exclude_loopback = exclude_loopback;
+        self
+    }
+
+    /// Set whether to leave out the addresses of bridges set up for containers or virtual
+    /// machines, such as `docker0` or `virbr0`, as `recommended_bind_addrs()` does.
+    pub fn exclude_virtual(mut self, exclude_virtual: bool) -> SelectionPolicy {
+        self.exclude_virtual = exclude_virtual;
+        self
+    }
+
+    /// Only pick addresses of `family`.
+    pub fn family(mut self, family: Family) -> SelectionPolicy {
+        self.family = Some(family);
+        self
+    }
+
+    /// Set whether to pick link-local addresses, which are only reachable from the same link.
+    pub fn include_link_local(mut self, include_link_local: bool) -> SelectionPolicy {
+        self.include_link_local = include_link_local;
+        self
+    }
+
+    /// Leave out the addresses on the network `ip/prefix_len`, as `listen_addrs_excluding()`
+    /// does. Can be called several times to exclude several networks.
+    pub fn exclude_network(mut self, ip: IpAddr, prefix_len: u8) -> SelectionPolicy {
+        self.excluded_networks.push((ip, prefix_len));
+        self
+    }
+
+    /// Set whether to leave out the temporary (privacy) IPv6 addresses of the interfaces which also
+    /// have a stable IPv6 address beyond link-local, e.g. one derived from their MAC address or per
+    /// RFC 7217, for picking addresses to advertise: temporary addresses rotate, so peers told of
+    /// one soon can't reach it. Those are still worth using as the source of outgoing connections,
+    /// which is what they are for, as `ordered_source_addrs()` does.
+    pub fn prefer_stable_v6(mut self, prefer_stable_v6: bool) -> SelectionPolicy {
+        self.prefer_stable_v6 = prefer_stable_v6;
+        self
+    }
+
+    /// List the picked addresses in `order`, as `get_if_addrs_ordered()` does.
+    pub fn order(mut self, order: EnumOrder) -> SelectionPolicy {
+        self.order = order;
+        self
+    }
+
+    fn picks(&self, interface: &Interface) -> bool {
+        let routability = interface.addr.routability();
+        if self.exclude_loopback && routability == Routability::Loopback {
+            return false;
+        }
+        if !self.include_link_local && routability == Routability::LinkLocal {
+            return false;
+        }
+        if self.exclude_virtual && is_virtual_bridge(&interface.name) {
+            return false;
+        }
+        if self.family.map_or(false, |family| interface.addr.family() != family) {
+            return false;
+        }
+        let ip = interface.addr.ip();
+        !self.excluded_networks
+            .iter()
+            .any(|&(network, prefix_len)| network_of(network, prefix_len).contains(&ip))
+    }
+}
+
+impl Default for SelectionPolicy {
+    fn default() -> SelectionPolicy {
+        SelectionPolicy::new()
+    }
+}
+
+/// Get the interfaces on this machine, as by `get_if_addrs()`, which `policy` picks, in the order
+/// it tells.
+pub fn select(policy: &SelectionPolicy) -> io::Result<Vec<Interface>> {
+    Ok(selected(try!(get_if_addrs()), policy))
+}
+
+fn selected(ifaces: Vec<Interface>, policy: &SelectionPolicy) -> Vec<Interface> {
+    let mut picked: Vec<Interface> =
+        ifaces.into_iter().filter(|interface| policy.picks(interface)).collect();
+    if policy.prefer_stable_v6 {
+        picked = without_shadowed_temporaries(picked);
+    }
+    ordered(picked, policy.order)
+}
+
+// Drop the temporary IPv6 addresses of the interfaces also among `ifaces` with a stable one which
+// isn't link-local.
+fn without_shadowed_temporaries(ifaces: Vec<Interface>) -> Vec<Interface> {
+    let is_temporary = |interface: &Interface| {
+        interface.addr.family() == Family::V6 &&
+        interface.addr_flags.contains(AddrFlags::TEMPORARY)
+    };
+    let stable_names: Vec<String> = ifaces.iter()
+        .filter(|interface| interface.addr.family() == Family::V6 && !is_temporary(interface))
+        .filter(|interface| interface.addr.routability() > Routability::LinkLocal)
+        .map(|interface| interface.name.clone())
+        .collect();
+    ifaces.into_iter()
+        .filter(|interface| !is_temporary(interface) || !stable_names.contains(&interface.name))
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::str::FromStr;
+
+    use common::get_if_addrs::test_util::interface;
+
+    // A host with loopback, two physical interfaces with IPv4, link-local and global IPv6
+    // addresses, a Docker bridge and a management interface.
+    fn mock_host() -> Vec<Interface> {
+        let v6_netmask = "ffff:ffff:ffff:ffff::";
+        vec![interface("lo", "127.0.0.1", "255.0.0.0"),
+             interface("lo", "::1", "ffff:ffff:ffff:ffff:ffff:ffff:ffff:ffff"),
+             interface("eth0", "192.168.1.5", "255.255.255.0"),
+             interface("eth0", "fe80::5", v6_netmask),
+             interface("eth0", "2001:db8::5", v6_netmask),
+             interface("docker0", "172.17.0.1", "255.255.0.0"),
+             interface("wlan0", "10.0.0.5", "255.255.255.0"),
+             interface("wlan0", "2001:db8:1::5", v6_netmask),
+             interface("mgmt0", "10.20.30.40", "255.255.0.0")]
+    }
+
+    fn names_and_ips(ifaces: Vec<Interface>) -> Vec<(String, String)> {
+        ifaces.into_iter()
+            .map(|interface| (interface.name, interface.addr.ip().to_string()))
+            .collect()
+    }
+
+    fn pairs(expected: &[(&str, &str)]) -> Vec<(String, String)> {
+        expected.iter().map(|&(name, ip)| (name.to_owned(), ip.to_owned())).collect()
+    }
+
+    #[test]
+    fn default_policy() {
+        assert_eq!(names_and_ips(selected(mock_host(), &SelectionPolicy::default())),
+                   pairs(&[("eth0", "192.168.1.5"),
+                           ("eth0", "2001:db8::5"),
+                           ("docker0", "172.17.0.1"),
+                           ("wlan0", "10.0.0.5"),
+                           ("wlan0", "2001:db8:1::5"),
+                           ("mgmt0", "10.20.30.40")]));
+    }
+
+    #[test]
+    fn combined_filters() {
+        let policy = SelectionPolicy::new()
+            .exclude_virtual(true)
+            .family(Family::V4)
+            .exclude_network(unwrap!(IpAddr::from_str("10.20.0.0")), 16);
+        assert_eq!(names_and_ips(selected(mock_host(), &policy)),
+                   pairs(&[("eth0", "192.168.1.5"), ("wlan0", "10.0.0.5")]));
+
+        let policy = SelectionPolicy::new()
+            .exclude_loopback(false)
+            .include_link_local(true)
+            .family(Family::V6)
+            .exclude_network(unwrap!(IpAddr::from_str("2001:db8:1::")), 48);
+        assert_eq!(names_and_ips(selected(mock_host(), &policy)),
+                   pairs(&[("lo", "::1"), ("eth0", "fe80::5"), ("eth0", "2001:db8::5")]));
+    }
+
+    #[test]
+    fn filters_then_order() {
+        let policy = SelectionPolicy::new()
+            .exclude_virtual(true)
+            .exclude_network(unwrap!(IpAddr::from_str("10.20.30.40")), 32)
+            .order(EnumOrder::V6First);
+        assert_eq!(names_and_ips(selected(mock_host(), &policy)),
+                   pairs(&[("eth0", "2001:db8::5"),
+                           ("wlan0", "2001:db8:1::5"),
+                           ("eth0", "192.168.1.5"),
+                           ("wlan0", "10.0.0.5")]));
+
+        let policy = SelectionPolicy::new().include_link_local(true).order(EnumOrder::ByInterface);
+        let ifaces = selected(mock_host(), &policy);
+        let names: Vec<&str> = ifaces.iter().map(|interface| &interface.name[..]).collect();
+        assert_eq!(names,
+                   vec!["eth0", "eth0", "eth0", "docker0", "wlan0", "wlan0", "mgmt0"]);
+    }
+
+    #[test]
+    fn stable_v6_addresses_are_preferred() {
+        let temporary = |name, ip| {
+            Interface {
+                addr_flags: AddrFlags::TEMPORARY,
+                ..interface(name, ip, "ffff:ffff:ffff:ffff::")
+            }
+        };
+        let mut ifaces = mock_host();
+        // A temporary address of `eth0` next to its stable one, and `wlan1` with only a temporary
+        // one, which is kept for lack of a better one.
+        ifaces.insert(5, temporary("eth0", "2001:db8::d1f3:8c2a:91b7:4e05"));
+        ifaces.push(temporary("wlan1", "2001:db8:2:0:b2c4:77e1:5a09:13f2"));
+        let policy = SelectionPolicy::new().family(Family::V6);
+        assert_eq!(selected(ifaces.clone(), &policy).len(), 4);
+
+        assert_eq!(names_and_ips(selected(ifaces, &policy.prefer_stable_v6(true))),
+                   pairs(&[("eth0", "2001:db8::5"),
+                           ("wlan0", "2001:db8:1::5"),
+                           ("wlan1", "2001:db8:2:0:b2c4:77e1:5a09:13f2")]));
+    }
+}
@@ -0,0 +1,643 @@
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::{io, mem, ptr};
+use std::mem::size_of;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::ffi::CStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+#[cfg(any(target_os = "linux", target_os = "android"))]
+use std::path::Path;
+
+use c_linked_list::CLinkedListMut;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+use common::get_if_addrs::{ifconf, vlan};
+use common::get_if_addrs::{AddrFlags, AddrOrigin, IfAddr, IfFlags, Ifv4Addr, Ifv6Addr,
+                           Interface, InterfaceBuilder, InterfaceRef, Limit};
+use common::get_if_addrs::sockaddr::{ipv4_from_in_addr, ipv6_from_in6_addr};
+use libc::{self, AF_INET, AF_INET6, c_uint};
+#[cfg(any(target_os = "linux", target_os = "android"))]
+use libc::{ENOSYS, EOPNOTSUPP};
+use libc::if_nametoindex as posix_if_nametoindex;
+use libc::getifaddrs as posix_getifaddrs;
+use libc::freeifaddrs as posix_freeifaddrs;
+use libc::ifaddrs as posix_ifaddrs;
+use libc::sockaddr as posix_sockaddr;
+use libc::sockaddr_in as posix_sockaddr_in;
+use libc::sockaddr_in6 as posix_sockaddr_in6;
+
+// The `IFF_*` values of `ifa_flags`. These are the same on all the supported platforms apart
+// from `IFF_MULTICAST`.
+const IFF_UP: c_uint = 0x1;
+const IFF_BROADCAST: c_uint = 0x2;
+const IFF_LOOPBACK: c_uint = 0x8;
+const IFF_POINTOPOINT: c_uint = 0x10;
+const IFF_RUNNING: c_uint = 0x40;
+#[cfg(any(target_os = "linux", target_os = "android", target_os = "nacl"))]
+const IFF_MULTICAST: c_uint = 0x1000;
+#[cfg(any(target_os = "freebsd",
+          target_os = "ios",
+          target_os = "macos",
+          target_os = "openbsd"))]
+const IFF_MULTICAST: c_uint = 0x8000;
+
+pub fn convert_flags(ifa_flags: c_uint) -> IfFlags {
+    let mut flags = IfFlags::empty();
+    for &(iff, flag) in &[(IFF_UP, IfFlags::UP),
+                          (IFF_BROADCAST, IfFlags::BROADCAST),
+                          (IFF_LOOPBACK, IfFlags::LOOPBACK),
+                          (IFF_POINTOPOINT, IfFlags::POINT_TO_POINT),
+                          (IFF_RUNNING, IfFlags::RUNNING),
+                          (IFF_MULTICAST, IfFlags::MULTICAST)] {
+        if ifa_flags & iff != 0 {
+            flags.insert(flag);
+        }
+    }
+    flags
+}
+
+#[allow(unsafe_code)]
+pub fn sockaddr_to_ipaddr(sockaddr: *const posix_sockaddr) -> Option<IpAddr> {
+    if sockaddr.is_null() {
+        return None;
+    }
+    sockaddr_to_ipaddr_bounded(sockaddr, sockaddr_len(sockaddr), false)
+}
+
+// The same as `sockaddr_to_ipaddr()` for the netmask at `sockaddr`, which may be missing bytes.
+#[allow(unsafe_code)]
+pub fn netmask_to_ipaddr(sockaddr: *const posix_sockaddr) -> Option<IpAddr> {
+    if sockaddr.is_null() {
+        return None;
+    }
+    sockaddr_to_ipaddr_bounded(sockaddr, sockaddr_len(sockaddr), true)
+}
+
+// How many bytes of the `sockaddr` at `sockaddr` are valid, where the platform tells.
+#[cfg(any(target_os = "freebsd",
+          target_os = "ios",
+          target_os = "macos",
+          target_os = "openbsd"))]
+#[allow(unsafe_code)]
+fn sockaddr_len(sockaddr: *const posix_sockaddr) -> Option<usize> {
+    Some(unsafe { (*sockaddr).sa_len } as usize)
+}
+
+#[cfg(not(any(target_os = "freebsd",
+              target_os = "ios",
+              target_os = "macos",
+              target_os = "openbsd")))]
+fn sockaddr_len(_sockaddr: *const posix_sockaddr) -> Option<usize> {
+    None
+}
+
+// Read the address at `sockaddr` without reading past its first `len` bytes, if given. An address
+// too short to even have a family isn't an address at all. The BSDs trim the trailing zeroes off
+// netmasks, so when `is_netmask` is set the missing bytes are taken to be zero, but any other
+// address missing some of its bytes is left out rather than made up.
+#[allow(unsafe_code)]
+fn sockaddr_to_ipaddr_bounded(sockaddr: *const posix_sockaddr,
+                              len: Option<usize>,
+                              is_netmask: bool)
+                              -> Option<IpAddr> {
+    if len.map_or(false, |len| len < FAMILY_END) {
+        return None;
+    }
+    let complete = |end| is_netmask || len.map_or(true, |len| len >= end);
+    let family = unsafe { read_bounded::<posix_sockaddr>(sockaddr, len) }.sa_family as u32;
+    if family == AF_INET as u32 && complete(V4_ADDR_END) {
+        let sa = unsafe { read_bounded::<posix_sockaddr_in>(sockaddr, len) };
+        Some(IpAddr::V4(ipv4_from_in_addr(sa.sin_addr.s_addr)))
+    } else if family == AF_INET6 as u32 && complete(V6_ADDR_END) {
+        let sa = unsafe { read_bounded::<posix_sockaddr_in6>(sockaddr, len) };
+        Some(IpAddr::V6(ipv6_from_in6_addr(sa.sin6_addr.s6_addr)))
+    } else {
+        None
+    }
+}
+
+// Where the family field of a `sockaddr` ends, i.e. how long one has to be to tell its family.
+const FAMILY_END: usize = 2;
+// Where the address of a `sockaddr_in` and of a `sockaddr_in6` ends: both start with the family
+// and the port, and a `sockaddr_in6` has its flow info before its address.
+const V4_ADDR_END: usize = 2 + 2 + 4;
+const V6_ADDR_END: usize = 2 + 2 + 4 + 16;
+
+// Copy the `T` at `sockaddr`, taking any bytes past the first `len` to be zero.
+#[allow(unsafe_code, trivial_casts)]
+unsafe fn read_bounded<T: Copy>(sockaddr: *const posix_sockaddr, len: Option<usize>) -> T {
+    let len = len.map_or(size_of::<T>(), |len| len.min(size_of::<T>()));
+    let mut value: T = mem::zeroed();
+    ptr::copy_nonoverlapping(sockaddr as *const u8, &mut value as *mut T as *mut u8, len);
+    value
+}
+
+// The address in the field which holds the broadcast address, or on point-to-point links the peer.
+#[cfg(any(target_os = "linux", target_os = "android", target_os = "nacl"))]
+fn broadcast_or_peer(ifaddr: &posix_ifaddrs) -> Option<IpAddr> {
+    sockaddr_to_ipaddr(ifaddr.ifa_ifu)
+}
+
+#[cfg(any(target_os = "freebsd",
+          target_os = "ios",
+          target_os = "macos",
+          target_os = "openbsd"))]
+fn broadcast_or_peer(ifaddr: &posix_ifaddrs) -> Option<IpAddr> {
+    sockaddr_to_ipaddr(ifaddr.ifa_dstaddr)
+}
+
+/// Return a vector of IP details for all the valid interfaces on this host
+pub fn get_if_addrs() -> io::Result<Vec<Interface>> {
+    get_if_addrs_limited(Limit::none())
+}
+
+/// Return a vector of IP details for the valid interfaces on this host, stopping at `limit`
+pub fn get_if_addrs_limited(limit: Limit) -> io::Result<Vec<Interface>> {
+    collect(false, limit)
+}
+
+/// Return a vector of only the names and IPs of all the valid interfaces on this host
+pub fn get_if_addrs_minimal() -> io::Result<Vec<Interface>> {
+    collect(true, Limit::none())
+}
+
+/// Hand the IP details of the valid interfaces on this host to `visit` one at a time, stopping as
+/// soon as it returns false
+pub fn for_each_interface(visit: &mut dyn FnMut(Interface) -> bool) -> io::Result<()> {
+    or_fallback(enumerate(false, &mut *visit), visit)
+}
+
+/// Hand the IP details of the valid interfaces on this host to `visit` one at a time, with the
+/// names borrowed from the list the OS returns, stopping as soon as it returns false
+pub fn for_each_interface_ref(visit: &mut dyn FnMut(InterfaceRef) -> bool) -> io::Result<()> {
+    let result = enumerate_borrowed(false, ipv6_available(), &mut |interface, _, _| {
+        visit(interface)
+    });
+    or_fallback(result, &mut |interface| visit(InterfaceRef::owning(interface)))
+}
+
+fn collect(minimal: bool, limit: Limit) -> io::Result<Vec<Interface>> {
+    let mut ret = Vec::new();
+    {
+        let mut visit = |interface| limit.push(&mut ret, interface);
+        try!(or_fallback(enumerate(minimal, &mut visit), &mut visit));
+    }
+    Ok(ret)
+}
+
+// If `getifaddrs` turned out not to be implemented, enumerate through `SIOCGIFCONF` instead, at
+// the cost of the IPv6 addresses.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn or_fallback(result: io::Result<()>, visit: &mut dyn FnMut(Interface) -> bool) -> io::Result<()> {
+    match result {
+        Err(ref e) if is_unavailable(e) => {
+            for interface in try!(ifconf::get_if_addrs_ifconf()) {
+                if !visit(interface) {
+                    break;
+                }
+            }
+            Ok(())
+        }
+        result => result,
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+fn or_fallback(result: io::Result<()>,
+               _visit: &mut dyn FnMut(Interface) -> bool)
+               -> io::Result<()> {
+    result
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn is_unavailable(e: &io::Error) -> bool {
+    match e.raw_os_error() {
+        Some(code) => code == ENOSYS || code == EOPNOTSUPP,
+        None => false,
+    }
+}
+
+// The VLAN ids of the VLAN interfaces by name, which only Linux reports.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn vlan_ids() -> HashMap<String, u16> {
+    vlan::vlan_ids(Path::new(vlan::PROC_NET_VLAN))
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+fn vlan_ids() -> HashMap<String, u16> {
+    HashMap::new()
+}
+
+// The name of `ifaddr`, the entry at `position` in the list `getifaddrs()` returned. No OS is
+// known to leave the name out, but should one do so the entry gets a name made up from its
+// position, rather than being dereferenced through a null pointer.
+#[allow(unsafe_code)]
+#[allow(trivial_casts)]
+fn name_of<'a>(ifaddr: &'a posix_ifaddrs, position: usize) -> Cow<'a, str> {
+    if ifaddr.ifa_name.is_null() {
+        return Cow::Owned(format!("unnamed{}", position));
+    }
+    unsafe { CStr::from_ptr(ifaddr.ifa_name as *const _) }.to_string_lossy()
+}
+
+fn enumerate(minimal: bool, visit: &mut dyn FnMut(Interface) -> bool) -> io::Result<()> {
+    let vlan_ids = if minimal { HashMap::new() } else { vlan_ids() };
+    enumerate_borrowed(minimal, ipv6_available(), &mut |interface, peer, addr_flags| {
+        let name = interface.name.into_owned();
+        visit(Interface {
+            vlan_id: vlan_ids.get(&name).cloned(),
+            name: name,
+            addr: interface.addr,
+            index: interface.index,
+            flags: interface.flags,
+            preferred_lft: None,
+            valid_lft: None,
+            addr_flags: addr_flags,
+            dns_suffix: None,
+            description: None,
+            peer: peer,
+            origin: AddrOrigin::Other,
+            metric: None,
+        })
+    })
+}
+
+// Whether this host supports IPv6 at all, which is found out once by opening an `AF_INET6`
+// socket. Only a kernel without IPv6 refusing the address family settles that it doesn't, so
+// other failures to open the socket are retried next time.
+#[allow(unsafe_code)]
+fn ipv6_available() -> bool {
+    // Nothing is known yet while `IPV6` is still zero.
+    const AVAILABLE: usize = 1;
+    const UNAVAILABLE: usize = 2;
+    static IPV6: AtomicUsize = AtomicUsize::new(0);
+
+    match IPV6.load(Ordering::Relaxed) {
+        AVAILABLE => return true,
+        UNAVAILABLE => return false,
+        _ => (),
+    }
+    let fd = unsafe { libc::socket(AF_INET6, libc::SOCK_DGRAM, 0) };
+    if fd >= 0 {
+        let _ = unsafe { libc::close(fd) };
+        IPV6.store(AVAILABLE, Ordering::Relaxed);
+        true
+    } else if io::Error::last_os_error().raw_os_error() == Some(libc::EAFNOSUPPORT) {
+        IPV6.store(UNAVAILABLE, Ordering::Relaxed);
+        false
+    } else {
+        true
+    }
+}
+
+// Walk through the list `getifaddrs()` returns, handing `visit` views of the entries whose names
+// borrow from the list, along with the peer of point-to-point entries. The list is freed once the
+// walk is done. Unless `ipv6` is set, IPv6 entries are skipped without being looked into.
+#[allow(unsafe_code)]
+fn enumerate_borrowed(minimal: bool,
+                      ipv6: bool,
+                      visit: &mut dyn FnMut(InterfaceRef, Option<IpAddr>, AddrFlags) -> bool)
+                      -> io::Result<()> {
+    let mut ifaddrs: *mut posix_ifaddrs;
+    unsafe {
+        ifaddrs = mem::uninitialized();
+        if -1 == posix_getifaddrs(&mut ifaddrs) {
+            return Err(io::Error::last_os_error());
+        }
+    }
+
+    walk(ifaddrs, minimal, ipv6, visit);
+    // A host without any interfaces gets an empty list, which is a null pointer not every OS
+    // accepts freeing.
+    if !ifaddrs.is_null() {
+        unsafe {
+            posix_freeifaddrs(ifaddrs);
+        }
+    }
+    Ok(())
+}
+
+// Hand `visit` the entries of the list `ifaddrs` as `enumerate_borrowed()` does, none if it is
+// null.
+#[allow(unsafe_code)]
+fn walk(ifaddrs: *mut posix_ifaddrs,
+        minimal: bool,
+        ipv6: bool,
+        visit: &mut dyn FnMut(InterfaceRef, Option<IpAddr>, AddrFlags) -> bool) {
+    let list = unsafe { CLinkedListMut::from_ptr(ifaddrs, |a| a.ifa_next) };
+    for (position, ifaddr) in list.iter().enumerate() {
+        if ifaddr.ifa_addr.is_null() {
+            continue;
+        }
+        if !ipv6 && unsafe { (*ifaddr.ifa_addr).sa_family } as i32 == AF_INET6 {
+            continue;
+        }
+        if let Some((interface, peer, addr_flags)) = interface_of(ifaddr, position, minimal) {
+            if !visit(interface, peer, addr_flags) {
+                break;
+            }
+        }
+    }
+}
+
+
+// The view of `ifaddr`, the entry at `position` in the list `getifaddrs()` returned, the address
+// of its peer if it is a point-to-point link, and `AddrFlags::NO_NETMASK` if it has no netmask.
+// Point-to-point links have no broadcast address, the field the OS otherwise reports it in holding
+// the peer instead.
+#[allow(unsafe_code)]
+fn interface_of<'a>(ifaddr: &'a posix_ifaddrs,
+                    position: usize,
+                    minimal: bool)
+                    -> Option<(InterfaceRef<'a>, Option<IpAddr>, AddrFlags)> {
+    let name = name_of(ifaddr, position);
+    let ip = match sockaddr_to_ipaddr(ifaddr.ifa_addr) {
+        Some(ip) => ip,
+        None => return None,
+    };
+    if minimal {
+        let interface = InterfaceRef {
+            name: name,
+            addr: InterfaceBuilder::new().addr(ip).build().addr,
+            index: 0,
+            flags: IfFlags::empty(),
+        };
+        return Some((interface, None, AddrFlags::empty()));
+    }
+    let index = if ifaddr.ifa_name.is_null() {
+        0
+    } else {
+        unsafe { posix_if_nametoindex(ifaddr.ifa_name) }
+    };
+    let point_to_point = (ifaddr.ifa_flags & IFF_POINTOPOINT) != 0;
+    let broadcast = if (ifaddr.ifa_flags & IFF_BROADCAST) != 0 && !point_to_point {
+        broadcast_or_peer(ifaddr)
+    } else {
+        None
+    };
+    let peer = if point_to_point {
+        broadcast_or_peer(ifaddr)
+    } else {
+        None
+    };
+    let netmask = netmask_to_ipaddr(ifaddr.ifa_netmask);
+    let addr_flags = match (netmask, ip) {
+        (Some(IpAddr::V4(_)), IpAddr::V4(_)) |
+        (Some(IpAddr::V6(_)), IpAddr::V6(_)) => AddrFlags::empty(),
+        _ => {
+            debug!("No netmask for {} on {}", ip, name);
+            AddrFlags::NO_NETMASK
+        }
+    };
+    let addr = match ip {
+        IpAddr::V4(ipv4_addr) => {
+            let netmask = match netmask {
+                Some(IpAddr::V4(netmask)) => netmask,
+                _ => Ipv4Addr::new(0, 0, 0, 0),
+            };
+            IfAddr::V4(Ifv4Addr {
+                ip: ipv4_addr,
+                netmask: netmask,
+                broadcast: match broadcast {
+                    Some(IpAddr::V4(broadcast)) => Some(broadcast),
+                    _ => None,
+                },
+            })
+        }
+        IpAddr::V6(ipv6_addr) => {
+            let netmask = match netmask {
+                Some(IpAddr::V6(netmask)) => netmask,
+                _ => Ipv6Addr::new(0, 0, 0, 0, 0, 0, 0, 0),
+            };
+            IfAddr::V6(Ifv6Addr {
+                ip: ipv6_addr,
+                netmask: netmask,
+                broadcast: match broadcast {
+                    Some(IpAddr::V6(broadcast)) => Some(broadcast),
+                    _ => None,
+                },
+            })
+        }
+    };
+    let interface = InterfaceRef {
+        name: name,
+        addr: addr,
+        index: index,
+        flags: convert_flags(ifaddr.ifa_flags),
+    };
+    Some((interface, peer, addr_flags))
+}
+
+#[cfg(test)]
+mod test {
+    #[test]
+    #[allow(unsafe_code, trivial_casts)]
+    fn truncated_sockaddrs() {
+        use super::*;
+
+        let mut sa: posix_sockaddr_in = unsafe { mem::zeroed() };
+        sa.sin_family = AF_INET as _;
+        sa.sin_addr.s_addr = u32::from(Ipv4Addr::new(255, 255, 255, 0)).to_be();
+        let ptr = &sa as *const posix_sockaddr_in as *const posix_sockaddr;
+        let addr_end = &sa.sin_addr as *const _ as usize + 4 - ptr as usize;
+        assert_eq!(addr_end, V4_ADDR_END);
+
+        let netmask = IpAddr::V4(Ipv4Addr::new(255, 255, 255, 0));
+        assert_eq!(sockaddr_to_ipaddr_bounded(ptr, None, false), Some(netmask));
+        assert_eq!(sockaddr_to_ipaddr_bounded(ptr, Some(size_of::<posix_sockaddr_in>()), false),
+                   Some(netmask));
+        assert_eq!(sockaddr_to_ipaddr_bounded(ptr, Some(8), false), Some(netmask));
+        // Without the trailing zero, as the BSDs report netmasks.
+        assert_eq!(sockaddr_to_ipaddr_bounded(ptr, Some(7), true), Some(netmask));
+        // Which no other address is missing.
+        assert_eq!(sockaddr_to_ipaddr_bounded(ptr, Some(7), false), None);
+        assert_eq!(sockaddr_to_ipaddr_bounded(ptr, Some(5), false), None);
+        assert_eq!(sockaddr_to_ipaddr_bounded(ptr, Some(1), true), None);
+        assert_eq!(sockaddr_to_ipaddr_bounded(ptr, Some(0), true), None);
+
+        let mut sa6: posix_sockaddr_in6 = unsafe { mem::zeroed() };
+        sa6.sin6_family = AF_INET6 as _;
+        sa6.sin6_addr.s6_addr = Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 5).octets();
+        let ptr = &sa6 as *const posix_sockaddr_in6 as *const posix_sockaddr;
+        let addr_end = &sa6.sin6_addr as *const _ as usize + 16 - ptr as usize;
+        assert_eq!(addr_end, V6_ADDR_END);
+        assert_eq!(sockaddr_to_ipaddr(ptr),
+                   Some(IpAddr::V6(Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 5))));
+        assert_eq!(sockaddr_to_ipaddr_bounded(ptr, Some(23), false), None);
+        assert_eq!(sockaddr_to_ipaddr_bounded(ptr, Some(1), true), None);
+        assert_eq!(sockaddr_to_ipaddr(ptr::null()), None);
+        assert_eq!(netmask_to_ipaddr(ptr::null()), None);
+    }
+
+    #[test]
+    #[allow(unsafe_code)]
+    fn null_names() {
+        use super::*;
+
+        let mut ifaddr: posix_ifaddrs = unsafe { mem::zeroed() };
+        assert!(ifaddr.ifa_name.is_null());
+        assert_eq!(name_of(&ifaddr, 3), "unnamed3");
+
+        let name = b"eth0\0";
+        ifaddr.ifa_name = name.as_ptr() as *mut _;
+        assert_eq!(name_of(&ifaddr, 3), "eth0");
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android", target_os = "nacl"))]
+    fn set_broadcast_or_peer(ifaddr: &mut super::posix_ifaddrs,
+                             sockaddr: *mut super::posix_sockaddr) {
+        ifaddr.ifa_ifu = sockaddr;
+    }
+
+    #[cfg(any(target_os = "freebsd",
+              target_os = "ios",
+              target_os = "macos",
+              target_os = "openbsd"))]
+    fn set_broadcast_or_peer(ifaddr: &mut super::posix_ifaddrs,
+                             sockaddr: *mut super::posix_sockaddr) {
+        ifaddr.ifa_dstaddr = sockaddr;
+    }
+
+    #[test]
+    #[allow(unsafe_code, trivial_casts)]
+    fn point_to_point_entries() {
+        use super::*;
+
+        fn sockaddr(ip: Ipv4Addr) -> posix_sockaddr_in {
+            let mut sa: posix_sockaddr_in = unsafe { mem::zeroed() };
+            sa.sin_family = AF_INET as _;
+            sa.sin_addr.s_addr = u32::from(ip).to_be();
+            sa
+        }
+
+        let name = b"utun3\0";
+        let mut addr = sockaddr(Ipv4Addr::new(10, 8, 0, 2));
+        let mut netmask = sockaddr(Ipv4Addr::new(255, 255, 255, 255));
+        let mut dstaddr = sockaddr(Ipv4Addr::new(10, 8, 0, 1));
+        let mut ifaddr: posix_ifaddrs = unsafe { mem::zeroed() };
+        ifaddr.ifa_name = name.as_ptr() as *mut _;
+        ifaddr.ifa_flags = IFF_UP | IFF_POINTOPOINT;
+        ifaddr.ifa_addr = &mut addr as *mut posix_sockaddr_in as *mut posix_sockaddr;
+        ifaddr.ifa_netmask = &mut netmask as *mut posix_sockaddr_in as *mut posix_sockaddr;
+        set_broadcast_or_peer(&mut ifaddr,
+                              &mut dstaddr as *mut posix_sockaddr_in as *mut posix_sockaddr);
+
+        let expected = IfAddr::V4(Ifv4Addr {
+            ip: Ipv4Addr::new(10, 8, 0, 2),
+            netmask: Ipv4Addr::new(255, 255, 255, 255),
+            broadcast: None,
+        });
+        let (interface, peer, addr_flags) = unwrap!(interface_of(&ifaddr, 0, false));
+        assert_eq!(interface.name, "utun3");
+        assert_eq!(interface.addr, expected);
+        assert_eq!(interface.flags, IfFlags::UP | IfFlags::POINT_TO_POINT);
+        assert_eq!(peer, Some(IpAddr::V4(Ipv4Addr::new(10, 8, 0, 1))));
+        assert_eq!(addr_flags, AddrFlags::empty());
+
+        // Some drivers set the broadcast flag on point-to-point links too.
+        ifaddr.ifa_flags |= IFF_BROADCAST;
+        let (interface, peer, _) = unwrap!(interface_of(&ifaddr, 0, false));
+        assert_eq!(interface.addr, expected);
+        assert_eq!(peer, Some(IpAddr::V4(Ipv4Addr::new(10, 8, 0, 1))));
+    }
+
+    #[test]
+    #[allow(unsafe_code, trivial_casts)]
+    fn entries_without_netmask() {
+        use super::*;
+
+        let name = b"eth0\0";
+        let mut addr: posix_sockaddr_in = unsafe { mem::zeroed() };
+        addr.sin_family = AF_INET as _;
+        addr.sin_addr.s_addr = u32::from_ne_bytes([192, 168, 1, 5]);
+        let mut ifaddr: posix_ifaddrs = unsafe { mem::zeroed() };
+        ifaddr.ifa_name = name.as_ptr() as *mut _;
+        ifaddr.ifa_flags = IFF_UP;
+        ifaddr.ifa_addr = &mut addr as *mut posix_sockaddr_in as *mut posix_sockaddr;
+        ifaddr.ifa_netmask = ptr::null_mut();
+
+        let mut ifaces = Vec::new();
+        walk(&mut ifaddr, false, true, &mut |interface, _, addr_flags| {
+            ifaces.push(InterfaceBuilder::new()
+                .name(&interface.name)
+                .addr(interface.addr.ip())
+                .addr_flags(addr_flags)
+                .build());
+            true
+        });
+        assert_eq!(ifaces.len(), 1);
+        assert_eq!(ifaces[0].addr.ip(), IpAddr::V4(Ipv4Addr::new(192, 168, 1, 5)));
+        assert_eq!(ifaces[0].addr_flags, AddrFlags::NO_NETMASK);
+        assert_eq!(ifaces[0].netmask(), None);
+
+        let mut netmask = addr;
+        netmask.sin_addr.s_addr = u32::from_ne_bytes([255, 255, 255, 0]);
+        ifaddr.ifa_netmask = &mut netmask as *mut posix_sockaddr_in as *mut posix_sockaddr;
+        let (_, _, addr_flags) = unwrap!(interface_of(&ifaddr, 0, false));
+        assert_eq!(addr_flags, AddrFlags::empty());
+    }
+
+    #[test]
+    fn empty_list_is_no_interfaces() {
+        use super::*;
+
+        // What `getifaddrs()` returns on a host without any interfaces.
+        let mut ifaces = Vec::new();
+        walk(ptr::null_mut(), false, true, &mut |interface, _, _| {
+            ifaces.push(interface.name.into_owned());
+            true
+        });
+        assert_eq!(ifaces, Vec::<String>::new());
+    }
+
+    #[test]
+    fn skips_ipv6_when_unavailable() {
+        use super::*;
+
+        fn enumerated(ipv6: bool) -> Vec<Interface> {
+            let mut ifaces = Vec::new();
+            unwrap!(enumerate_borrowed(false, ipv6, &mut |interface, _, _| {
+                ifaces.push(InterfaceBuilder::new()
+                    .name(&interface.name)
+                    .addr(interface.addr.ip())
+                    .build());
+                true
+            }));
+            ifaces
+        }
+
+        let all = enumerated(true);
+        let v4_only = enumerated(false);
+        assert!(v4_only.iter().any(Interface::is_loopback));
+        assert_eq!(v4_only,
+                   all.into_iter()
+                       .filter(|interface| interface.addr.ip().is_ipv4())
+                       .collect::<Vec<_>>());
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    #[test]
+    fn falls_back_when_getifaddrs_is_unavailable() {
+        use super::*;
+
+        use libc::EACCES;
+        use std::net::{IpAddr, Ipv4Addr};
+
+        fn fallback(code: i32, limit: Limit) -> io::Result<Vec<Interface>> {
+            let mut ifaces = Vec::new();
+            try!(or_fallback(Err(io::Error::from_raw_os_error(code)),
+                             &mut |interface| limit.push(&mut ifaces, interface)));
+            Ok(ifaces)
+        }
+
+        let ifaces = unwrap!(fallback(ENOSYS, Limit::none()));
+        let localhost = IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1));
+        assert!(ifaces.iter().any(|interface| interface.addr.ip() == localhost));
+        assert!(ifaces.iter().all(|interface| interface.addr.ip().is_ipv4()));
+
+        let limited = unwrap!(fallback(ENOSYS, Limit::new(1, |_| true)));
+        assert_eq!(limited, ifaces[..1].to_vec());
+
+        let e = unwrap!(fallback(EACCES, Limit::none()).err());
+        assert_eq!(e.raw_os_error(), Some(EACCES));
+    }
+}
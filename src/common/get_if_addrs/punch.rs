@@ -0,0 +1,45 @@
+// Sockets for UDP hole punching, which has to go through the same interface the peer sees our
+// traffic come from.
+
+use std::io;
+use std::net::UdpSocket;
+
+use common::get_if_addrs::{Family, IfAddr, default_interface};
+
+/// Bind a UDP socket to `port` on the interface `family` traffic to the internet leaves from, as
+/// told by `default_interface()`. The interface address is returned with the socket so that the
+/// right address can be advertised to peers.
+///
+/// Fails with `io::ErrorKind::AddrNotAvailable` if there is no route to the internet for
+/// `family`.
+pub fn hole_punch_socket(port: u16, family: Family) -> io::Result<(UdpSocket, IfAddr)> {
+    let interface = match try!(default_interface(family)) {
+        Some(interface) => interface,
+        None => {
+            return Err(io::Error::new(io::ErrorKind::AddrNotAvailable,
+                                      format!("No route to the internet over {:?}", family)))
+        }
+    };
+    let socket = try!(UdpSocket::bind(interface.socket_addr(port)));
+    Ok((socket, interface.addr))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn binds_on_default_interface() {
+        for &family in &[Family::V4, Family::V6] {
+            let (socket, addr) = match hole_punch_socket(0, family) {
+                Ok(bound) => bound,
+                Err(ref e) if e.kind() == io::ErrorKind::AddrNotAvailable => continue,
+                Err(e) => panic!("Binding failed: {}", e),
+            };
+            assert_eq!(addr.family(), family);
+            let local_addr = unwrap!(socket.local_addr());
+            assert_eq!(local_addr.ip(), addr.ip());
+            assert!(local_addr.port() != 0);
+        }
+    }
+}
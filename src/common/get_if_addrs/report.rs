@@ -0,0 +1,138 @@
+// A plain-text dump of what crust sees of the network on this machine, for pasting into bug
+// reports.
+
+use std::env::consts::{ARCH, OS};
+use std::io;
+
+use common::get_if_addrs::{IfFlags, Interface, get_if_addrs_all};
+
+/// Describe the crate version, the OS and architecture, and every interface on this machine,
+/// including those which are down, one address per line with its flags, MTU and the other details
+/// known on this platform.
+///
+/// This never fails: if the interfaces can't be enumerated, the report says why instead.
+pub fn diagnostic_report() -> String {
+    render(get_if_addrs_all())
+}
+
+// The names the flags are listed by, in the order they are listed in.
+const FLAG_NAMES: [(IfFlags, &'static str); 6] = [(IfFlags::UP, "UP"),
+                                                  (IfFlags::BROADCAST, "BROADCAST"),
+                                                  (IfFlags::LOOPBACK, "LOOPBACK"),
+                                                  (IfFlags::POINT_TO_POINT, "POINTOPOINT"),
+                                                  (IfFlags::RUNNING, "RUNNING"),
+                                                  (IfFlags::MULTICAST, "MULTICAST")];
+
+fn render(ifaces: io::Result<Vec<Interface>>) -> String {
+    let mut text = format!("crust {} on {}/{}\n", env!("CARGO_PKG_VERSION"), OS, ARCH);
+    let ifaces = match ifaces {
+        Ok(ifaces) => ifaces,
+        Err(e) => {
+            text.push_str(&format!("could not enumerate interfaces: {}\n", e));
+            return text;
+        }
+    };
+    if ifaces.is_empty() {
+        text.push_str("no interfaces\n");
+    }
+    for interface in &ifaces {
+        text.push_str(&line(interface, mtu(&interface.name)));
+        text.push('\n');
+    }
+    text
+}
+
+fn line(interface: &Interface, mtu: Option<u32>) -> String {
+    let ip = interface.addr.ip();
+    let addr = match (interface.netmask(), interface.addr.prefix_len()) {
+        (None, _) => format!("{} no netmask", ip),
+        (Some(_), Some(prefix_len)) => format!("{}/{}", ip, prefix_len),
+        // A non-contiguous netmask has no prefix length.
+        (Some(netmask), None) => format!("{} mask {}", ip, netmask),
+    };
+    let flags: Vec<&str> = FLAG_NAMES.iter()
+        .filter(|&&(flag, _)| interface.flags.contains(flag))
+        .map(|&(_, name)| name)
+        .collect();
+    let mut line = format!("{} index {} {} <{}>",
+                           interface.name,
+                           interface.index,
+                           addr,
+                           flags.join(","));
+    match mtu {
+        Some(mtu) => line.push_str(&format!(" mtu {}", mtu)),
+        None => line.push_str(" mtu ?"),
+    }
+    line.push_str(&format!(" origin {:?}", interface.origin));
+    if let Some(metric) = interface.metric {
+        line.push_str(&format!(" metric {}", metric));
+    }
+    if let Some(vlan_id) = interface.vlan_id {
+        line.push_str(&format!(" vlan {}", vlan_id));
+    }
+    if let Some(peer) = interface.peer {
+        line.push_str(&format!(" peer {}", peer));
+    }
+    line
+}
+
+// The MTU of the interface `name`, which is only known on Linux. Aliases such as `eth0:0` have
+// that of the interface before the colon.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn mtu(name: &str) -> Option<u32> {
+    use common::get_if_addrs::sysfs;
+
+    let name = name.split(':').next().unwrap_or("");
+    sysfs::mtu(::std::path::Path::new(sysfs::SYS_CLASS_NET), name)
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+fn mtu(_name: &str) -> Option<u32> {
+    None
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::net::{IpAddr, Ipv4Addr};
+
+    use common::get_if_addrs::{AddrFlags, InterfaceBuilder};
+
+    #[test]
+    fn report_has_the_version_and_the_interfaces() {
+        let report = diagnostic_report();
+        let mut lines = report.lines();
+        assert!(unwrap!(lines.next()).contains(env!("CARGO_PKG_VERSION")));
+        let ifaces = unwrap!(get_if_addrs_all());
+        assert!(!ifaces.is_empty());
+        assert_eq!(lines.filter(|line| ifaces.iter().any(|i| line.starts_with(&i.name))).count(),
+                   ifaces.len());
+    }
+
+    #[test]
+    fn interface_lines() {
+        let interface = InterfaceBuilder::new()
+            .name("eth0")
+            .addr(IpAddr::V4(Ipv4Addr::new(192, 168, 1, 2)))
+            .netmask(IpAddr::V4(Ipv4Addr::new(255, 255, 255, 0)))
+            .index(2)
+            .flags(IfFlags::UP | IfFlags::BROADCAST | IfFlags::RUNNING)
+            .metric(100)
+            .build();
+        assert_eq!(line(&interface, Some(1500)),
+                   "eth0 index 2 192.168.1.2/24 <UP,BROADCAST,RUNNING> mtu 1500 origin Other \
+                    metric 100");
+        assert!(line(&interface, None).contains(" mtu ? "));
+
+        let interface = InterfaceBuilder::new()
+            .name("ppp0")
+            .addr(IpAddr::V4(Ipv4Addr::new(10, 64, 0, 2)))
+            .addr_flags(AddrFlags::NO_NETMASK)
+            .build();
+        assert!(line(&interface, None).starts_with("ppp0 index 0 10.64.0.2 no netmask <>"));
+
+        let error = io::Error::new(io::ErrorKind::Other, "no netlink");
+        assert!(render(Err(error)).ends_with("could not enumerate interfaces: no netlink\n"));
+    }
+}
@@ -0,0 +1,634 @@
+// The default gateways of this machine, read from its routing table. Linux exposes the table as
+// text in `/proc/net/route` and `/proc/net/ipv6_route`, the BSDs as routing messages through the
+// `NET_RT_DUMP` sysctl and Windows through `GetIpForwardTable2`. The text and the messages are
+// parsed from plain readers and buffers so tests can feed them samples.
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+use std::fs::File;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+use std::io::{self, BufRead, BufReader};
+use std::net::IpAddr;
+#[cfg(any(target_os = "linux", target_os = "android", target_os = "freebsd",
+          target_os = "macos", target_os = "ios"))]
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+/// Get the gateways of the default routes of this machine, each with the name of the interface,
+/// as named by `get_if_addrs()`, the route goes through.
+///
+/// Reading the routing table is implemented on Linux, Android, FreeBSD, macOS, iOS and Windows:
+/// elsewhere this is always empty.
+pub fn default_gateways() -> Vec<(IpAddr, String)> {
+    read_gateways()
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn read_gateways() -> Vec<(IpAddr, String)> {
+    default_routes().into_iter().map(|(gateway, name, _)| (gateway, name)).collect()
+}
+
+#[cfg(any(target_os = "freebsd", target_os = "macos", target_os = "ios"))]
+fn read_gateways() -> Vec<(IpAddr, String)> {
+    match bsd::dump_routes() {
+        Ok(buf) => name_routes(parse_route_dump(&buf)),
+        Err(e) => {
+            warn!("Could not dump the routing table: {:?}", e);
+            Vec::new()
+        }
+    }
+}
+
+#[cfg(windows)]
+fn read_gateways() -> Vec<(IpAddr, String)> {
+    name_routes(windows::default_routes())
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android", target_os = "freebsd",
+              target_os = "macos", target_os = "ios", windows)))]
+fn read_gateways() -> Vec<(IpAddr, String)> {
+    Vec::new()
+}
+
+/// Get the default routes of this machine, each as its gateway, the name of its interface and its
+/// metric, for finding out which interfaces the OS prefers for reaching the internet.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub fn default_routes() -> Vec<(IpAddr, String, u32)> {
+    let mut routes = read_table("/proc/net/route", parse_proc_net_route);
+    routes.extend(read_table("/proc/net/ipv6_route", parse_proc_net_ipv6_route));
+    routes
+}
+
+/// Get the lowest metric of the `routes` through the interface `name` of the family of `ip`, as
+/// given by `default_routes()`. Aliases such as `eth0:0` get that of the interface before the
+/// colon.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub fn default_route_metric(routes: &[(IpAddr, String, u32)],
+                            name: &str,
+                            ip: &IpAddr)
+                            -> Option<u32> {
+    let name = name.split(':').next().unwrap_or("");
+    routes.iter()
+        .filter(|&&(ref gateway, ref route_name, _)| {
+            route_name == name && gateway.is_ipv4() == ip.is_ipv4()
+        })
+        .map(|&(_, _, metric)| metric)
+        .min()
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn read_table(path: &str, parse: fn(BufReader<File>) -> Vec<(IpAddr, String, u32)>)
+              -> Vec<(IpAddr, String, u32)> {
+    match File::open(path) {
+        Ok(file) => parse(BufReader::new(file)),
+        // As it is without IPv6, or in a sandbox without `/proc`.
+        Err(ref e) if e.kind() == io::ErrorKind::NotFound => Vec::new(),
+        Err(e) => {
+            warn!("Could not read {}: {:?}", path, e);
+            Vec::new()
+        }
+    }
+}
+
+// The route flags of Linux and the BSDs telling a route is up and goes through a gateway.
+#[cfg(any(target_os = "linux", target_os = "android", target_os = "freebsd",
+          target_os = "macos", target_os = "ios"))]
+const RTF_UP: u32 = 0x1;
+#[cfg(any(target_os = "linux", target_os = "android", target_os = "freebsd",
+          target_os = "macos", target_os = "ios"))]
+const RTF_GATEWAY: u32 = 0x2;
+
+// Find the default routes in the contents of `/proc/net/route`, which after a header line has a
+// line like `eth0 00000000 010200C0 0003 0 0 100 00000000 0 0 0` per route, the metric being the
+// decimal `100`. Addresses and masks are the bytes of the address in network order, printed in
+// hex as a native `u32`.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn parse_proc_net_route<R: BufRead>(reader: R) -> Vec<(IpAddr, String, u32)> {
+    let mut gateways = Vec::new();
+    for line in reader.lines().skip(1) {
+        let line = match line {
+            Ok(line) => line,
+            Err(_) => break,
+        };
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.len() < 8 {
+            continue;
+        }
+        let hex = |field: &str| u32::from_str_radix(field, 16).ok();
+        let (destination, gateway, flags, mask) =
+            match (hex(fields[1]), hex(fields[2]), hex(fields[3]), hex(fields[7])) {
+                (Some(destination), Some(gateway), Some(flags), Some(mask)) => {
+                    (destination, gateway, flags, mask)
+                }
+                _ => continue,
+            };
+        let metric = match fields[6].parse() {
+            Ok(metric) => metric,
+            Err(_) => continue,
+        };
+        if destination != 0 || mask != 0 || flags & (RTF_UP | RTF_GATEWAY) != RTF_UP | RTF_GATEWAY {
+            continue;
+        }
+        let gateway = Ipv4Addr::from(u32::from_be(gateway));
+        gateways.push((IpAddr::V4(gateway), fields[0].to_owned(), metric));
+    }
+    gateways
+}
+
+// Find the default routes in the contents of `/proc/net/ipv6_route`, which has a line per route
+// giving the destination and its prefix length, the source and its prefix length, the next hop,
+// the metric in hex, the reference and use counts, the flags and the interface, e.g.
+// `00000000000000000000000000000000 00 00000000000000000000000000000000 00
+// fd000000000000000000000000000001 00000400 00000001 00000000 00000003 eth0`. Addresses are the
+// bytes of the address in network order, in hex.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn parse_proc_net_ipv6_route<R: BufRead>(reader: R) -> Vec<(IpAddr, String, u32)> {
+    fn parse_ipv6(field: &str) -> Option<Ipv6Addr> {
+        if field.len() != 32 {
+            return None;
+        }
+        let mut octets = [0u8; 16];
+        for (i, octet) in octets.iter_mut().enumerate() {
+            *octet = match field.get(2 * i..2 * i + 2).map(|hex| u8::from_str_radix(hex, 16)) {
+                Some(Ok(octet)) => octet,
+                _ => return None,
+            };
+        }
+        Some(Ipv6Addr::from(octets))
+    }
+
+    let mut gateways = Vec::new();
+    for line in reader.lines() {
+        let line = match line {
+            Ok(line) => line,
+            Err(_) => break,
+        };
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.len() < 10 || fields[1] != "00" {
+            continue;
+        }
+        let hex = |field: &str| u32::from_str_radix(field, 16).ok();
+        let (destination, gateway, metric, flags) =
+            match (parse_ipv6(fields[0]), parse_ipv6(fields[4]), hex(fields[5]), hex(fields[8])) {
+                (Some(destination), Some(gateway), Some(metric), Some(flags)) => {
+                    (destination, gateway, metric, flags)
+                }
+                _ => continue,
+            };
+        if !destination.is_unspecified() || gateway.is_unspecified() ||
+           flags & (RTF_UP | RTF_GATEWAY) != RTF_UP | RTF_GATEWAY {
+            continue;
+        }
+        gateways.push((IpAddr::V6(gateway), fields[9].to_owned(), metric));
+    }
+    gateways
+}
+
+// Name the interfaces of routes given with the index of their interface.
+#[cfg(any(target_os = "freebsd", target_os = "macos", target_os = "ios", windows))]
+fn name_routes(routes: Vec<(IpAddr, u32)>) -> Vec<(IpAddr, String)> {
+    use common::get_if_addrs::get_if_addrs_all;
+
+    let ifaces = match get_if_addrs_all() {
+        Ok(ifaces) => ifaces,
+        Err(e) => {
+            warn!("Could not enumerate interfaces: {:?}", e);
+            return Vec::new();
+        }
+    };
+    routes.into_iter()
+        .filter_map(|(gateway, index)| {
+            ifaces.iter()
+                .find(|interface| interface.index == index)
+                .map(|interface| (gateway, interface.name.clone()))
+        })
+        .collect()
+}
+
+// The size of `struct rt_msghdr`, after which the addresses of a routing message start.
+#[cfg(any(target_os = "macos", target_os = "ios"))]
+const RT_MSGHDR_LEN: usize = 92;
+#[cfg(target_os = "freebsd")]
+const RT_MSGHDR_LEN: usize = 32 + 15 * ::std::mem::size_of::<::libc::c_ulong>();
+
+// What the lengths of the addresses of a routing message are rounded up to.
+#[cfg(any(target_os = "macos", target_os = "ios"))]
+const SOCKADDR_ALIGN: usize = 4;
+#[cfg(target_os = "freebsd")]
+const SOCKADDR_ALIGN: usize = 8;
+
+// Find the default routes in a `NET_RT_DUMP` of the routing table, giving the index of the
+// interface of each. The dump is a sequence of routing messages, each a `struct rt_msghdr`
+// holding the length of the message at offset 0, the interface index at 4, the flags at 8 and
+// the bit set of the addresses that follow at 12. The addresses are `sockaddr`s starting with
+// their length and family, in the order of their bits.
+#[cfg(any(target_os = "freebsd", target_os = "macos", target_os = "ios"))]
+fn parse_route_dump(buf: &[u8]) -> Vec<(IpAddr, u32)> {
+    use byteorder::{ByteOrder, NativeEndian};
+
+    const RTA_DST: u32 = 0x1;
+    const RTA_GATEWAY: u32 = 0x2;
+    const RTA_NETMASK: u32 = 0x4;
+
+    let mut routes = Vec::new();
+    let mut offset = 0;
+    while offset + RT_MSGHDR_LEN <= buf.len() {
+        let msg_len = NativeEndian::read_u16(&buf[offset..]) as usize;
+        if msg_len < RT_MSGHDR_LEN || offset + msg_len > buf.len() {
+            break;
+        }
+        let msg = &buf[offset..offset + msg_len];
+        offset += msg_len;
+
+        let index = NativeEndian::read_u16(&msg[4..]) as u32;
+        let flags = NativeEndian::read_u32(&msg[8..]);
+        let addrs = NativeEndian::read_u32(&msg[12..]);
+        if flags & (RTF_UP | RTF_GATEWAY) != RTF_UP | RTF_GATEWAY {
+            continue;
+        }
+
+        let (mut destination, mut gateway) = (None, None);
+        // The netmask of a default route is often left out, or cut down to no address at all.
+        let mut netmask_is_zero = true;
+        let mut sockaddr_offset = RT_MSGHDR_LEN;
+        for bit in (0..8).map(|shift| 1 << shift).filter(|bit| addrs & bit != 0) {
+            if sockaddr_offset >= msg.len() {
+                break;
+            }
+            let sa_len = msg[sockaddr_offset] as usize;
+            let sockaddr = &msg[sockaddr_offset..(sockaddr_offset + sa_len).min(msg.len())];
+            match bit {
+                RTA_DST => destination = sockaddr_ip(sockaddr),
+                RTA_GATEWAY => gateway = sockaddr_ip(sockaddr),
+                RTA_NETMASK => {
+                    netmask_is_zero = sockaddr.iter().skip(2).all(|&byte| byte == 0)
+                }
+                _ => (),
+            }
+            sockaddr_offset += if sa_len == 0 {
+                SOCKADDR_ALIGN
+            } else {
+                (sa_len + SOCKADDR_ALIGN - 1) & !(SOCKADDR_ALIGN - 1)
+            };
+        }
+
+        let is_default = match destination {
+            Some(IpAddr::V4(ip)) => ip.is_unspecified(),
+            Some(IpAddr::V6(ip)) => ip.is_unspecified(),
+            None => false,
+        };
+        match gateway {
+            Some(gateway) if is_default && netmask_is_zero => routes.push((gateway, index)),
+            _ => (),
+        }
+    }
+    routes
+}
+
+// Read the address of a `sockaddr` of a routing message, whose trailing zeros may have been left
+// out.
+#[cfg(any(target_os = "freebsd", target_os = "macos", target_os = "ios"))]
+fn sockaddr_ip(sockaddr: &[u8]) -> Option<IpAddr> {
+    use common::get_if_addrs::without_scope;
+    use libc::{AF_INET, AF_INET6};
+
+    if sockaddr.len() < 2 {
+        return None;
+    }
+    let mut padded = [0u8; 28];
+    let len = sockaddr.len().min(padded.len());
+    padded[..len].copy_from_slice(&sockaddr[..len]);
+    if padded[1] as i32 == AF_INET {
+        let mut octets = [0u8; 4];
+        octets.copy_from_slice(&padded[4..8]);
+        Some(IpAddr::V4(Ipv4Addr::from(octets)))
+    } else if padded[1] as i32 == AF_INET6 {
+        let mut octets = [0u8; 16];
+        octets.copy_from_slice(&padded[8..24]);
+        Some(without_scope(&IpAddr::V6(Ipv6Addr::from(octets))))
+    } else {
+        None
+    }
+}
+
+#[cfg(any(target_os = "freebsd", target_os = "macos", target_os = "ios"))]
+mod bsd {
+    use std::{io, ptr};
+
+    use libc::{self, c_int};
+
+    const CTL_NET: c_int = 4;
+    const PF_ROUTE: c_int = 17;
+    const NET_RT_DUMP: c_int = 1;
+
+    // Dump the routes of all families. Routes can be added between asking for the size of the dump
+    // and making it, so keep asking as long as the buffer is too small.
+    #[allow(unsafe_code)]
+    pub fn dump_routes() -> io::Result<Vec<u8>> {
+        let mut mib = [CTL_NET, PF_ROUTE, 0, 0, NET_RT_DUMP, 0];
+        loop {
+            let mut len = 0;
+            if unsafe {
+                libc::sysctl(mib.as_mut_ptr(), 6, ptr::null_mut(), &mut len, ptr::null_mut(), 0)
+            } != 0 {
+                return Err(io::Error::last_os_error());
+            }
+            let mut buf = vec![0u8; len];
+            if unsafe {
+                libc::sysctl(mib.as_mut_ptr(),
+                             6,
+                             buf.as_mut_ptr() as *mut _,
+                             &mut len,
+                             ptr::null_mut(),
+                             0)
+            } == 0 {
+                buf.truncate(len);
+                return Ok(buf);
+            }
+            let e = io::Error::last_os_error();
+            if e.raw_os_error() != Some(libc::ENOMEM) {
+                return Err(e);
+            }
+        }
+    }
+}
+
+#[cfg(windows)]
+mod windows {
+    use std::net::IpAddr;
+    use std::{mem, ptr, slice};
+
+    use common::get_if_addrs::windows::sockaddr_to_ipaddr;
+    use libc::c_int;
+    use winapi::{DWORD, ERROR_SUCCESS, SOCKADDR};
+
+    // `SOCKADDR_INET`, the union of `SOCKADDR_IN` and `SOCKADDR_IN6`.
+    #[repr(C)]
+    #[derive(Clone, Copy)]
+    pub struct SockaddrInet {
+        family: u16,
+        port: u16,
+        rest: [u32; 6],
+    }
+
+    // `MIB_IPFORWARD_ROW2`, with its `IP_ADDRESS_PREFIX` inlined.
+    #[repr(C)]
+    #[derive(Clone, Copy)]
+    pub struct MibIpForwardRow2 {
+        pub interface_luid: u64,
+        pub interface_index: DWORD,
+        pub destination_prefix: SockaddrInet,
+        pub destination_prefix_length: u8,
+        pub next_hop: SockaddrInet,
+        site_prefix_length: u8,
+        valid_lifetime: DWORD,
+        preferred_lifetime: DWORD,
+        metric: DWORD,
+        protocol: c_int,
+        loopback: u8,
+        autoconfigure_address: u8,
+        publish: u8,
+        immortal: u8,
+        age: DWORD,
+        origin: c_int,
+    }
+
+    // `MIB_IPFORWARD_TABLE2`, whose `table` really holds `num_entries` rows.
+    #[repr(C)]
+    struct MibIpForwardTable2 {
+        num_entries: DWORD,
+        table: [MibIpForwardRow2; 1],
+    }
+
+    const AF_UNSPEC: u16 = 0;
+
+    #[link(name="Iphlpapi")]
+    extern "system" {
+        fn GetIpForwardTable2(family: u16, table: *mut *mut MibIpForwardTable2) -> DWORD;
+        fn FreeMibTable(memory: *mut MibIpForwardTable2);
+    }
+
+    // The gateways of the default routes, with the index of the interface each goes through.
+    #[allow(unsafe_code)]
+    pub fn default_routes() -> Vec<(IpAddr, u32)> {
+        let mut table = ptr::null_mut();
+        match unsafe { GetIpForwardTable2(AF_UNSPEC, &mut table) } {
+            ERROR_SUCCESS => (),
+            code => {
+                warn!("GetIpForwardTable2 failed: {}", code);
+                return Vec::new();
+            }
+        }
+        let routes = default_routes_of(unsafe {
+            slice::from_raw_parts((*table).table.as_ptr(), (*table).num_entries as usize)
+        });
+        unsafe { FreeMibTable(table) };
+        routes
+    }
+
+    #[allow(trivial_casts)]
+    pub fn default_routes_of(rows: &[MibIpForwardRow2]) -> Vec<(IpAddr, u32)> {
+        rows.iter()
+            .filter(|row| row.destination_prefix_length == 0)
+            .filter_map(|row| {
+                let next_hop = &row.next_hop as *const SockaddrInet as *const SOCKADDR;
+                match sockaddr_to_ipaddr(next_hop, mem::size_of::<SockaddrInet>() as c_int) {
+                    Some(IpAddr::V4(gateway)) if !gateway.is_unspecified() => {
+                        Some((IpAddr::V4(gateway), row.interface_index))
+                    }
+                    Some(IpAddr::V6(gateway)) if !gateway.is_unspecified() => {
+                        Some((IpAddr::V6(gateway), row.interface_index))
+                    }
+                    _ => None,
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    #[test]
+    fn parses_proc_net_route() {
+        let sample = "\
+Iface\tDestination\tGateway \tFlags\tRefCnt\tUse\tMetric\tMask\t\tMTU\tWindow\tIRTT
+eth0\t00000000\t010200C0\t0003\t0\t0\t0\t00000000\t0\t0\t0
+eth0\t000200C0\t00000000\t0001\t0\t0\t0\t00FFFFFF\t0\t0\t0
+wlan0\t00000000\t0101A8C0\t0003\t0\t0\t600\t00000000\t0\t0\t0
+tun0\t00000000\t0100080A\t0001\t0\t0\t0\t00000000\t0\t0\t0
+bad\tzz\t0101A8C0\t0003\t0\t0\t0\t00000000\t0\t0\t0
+";
+        let gateway = |ip: &str| unwrap!(ip.parse::<Ipv4Addr>());
+        assert_eq!(parse_proc_net_route(sample.as_bytes()),
+                   vec![(IpAddr::V4(gateway("192.0.2.1")), "eth0".to_owned(), 0),
+                        (IpAddr::V4(gateway("192.168.1.1")), "wlan0".to_owned(), 600)]);
+        assert!(parse_proc_net_route(&b"Iface\tDestination\n"[..]).is_empty());
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    #[test]
+    fn parses_proc_net_ipv6_route() {
+        let sample = "\
+fd000000000000000000000000000000 40 00000000000000000000000000000000 00 \
+00000000000000000000000000000000 00000100 00000001 00000000 00000001     eth0
+00000000000000000000000000000000 00 00000000000000000000000000000000 00 \
+fd000000000000000000000000000001 00000400 00000001 00000000 00000003     eth0
+00000000000000000000000000000000 00 00000000000000000000000000000000 00 \
+fe800000000000000000000000000001 00000600 00000001 00000000 00450003    wlan0
+00000000000000000000000000000000 00 00000000000000000000000000000000 00 \
+00000000000000000000000000000000 ffffffff 00000001 00000000 00200200       lo
+00000000000000000000000000000000 00 00000000000000000000000000000000 00 \
+fe80000000000000000000000000001 00000400 00000001 00000000 00000003      bad
+";
+        let gateway = |ip: &str| IpAddr::V6(unwrap!(ip.parse::<Ipv6Addr>()));
+        assert_eq!(parse_proc_net_ipv6_route(sample.as_bytes()),
+                   vec![(gateway("fd00::1"), "eth0".to_owned(), 0x400),
+                        (gateway("fe80::1"), "wlan0".to_owned(), 0x600)]);
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    #[test]
+    fn lowest_default_route_metric_of_the_family() {
+        let ip = |ip: &str| unwrap!(ip.parse::<IpAddr>());
+        let routes = vec![(ip("192.168.1.1"), "eth0".to_owned(), 600),
+                          (ip("192.168.1.2"), "eth0".to_owned(), 100),
+                          (ip("fd00::1"), "eth0".to_owned(), 1024),
+                          (ip("10.0.0.1"), "wlan0".to_owned(), 50)];
+        assert_eq!(default_route_metric(&routes, "eth0", &ip("192.168.1.5")), Some(100));
+        assert_eq!(default_route_metric(&routes, "eth0:1", &ip("192.168.1.6")), Some(100));
+        assert_eq!(default_route_metric(&routes, "eth0", &ip("fd00::5")), Some(1024));
+        assert_eq!(default_route_metric(&routes, "wlan0", &ip("fd00::6")), None);
+        assert_eq!(default_route_metric(&routes, "eth1", &ip("192.168.2.5")), None);
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    #[test]
+    fn interfaces_have_the_metrics_of_their_default_routes() {
+        use common::get_if_addrs::{get_if_addrs_all, get_if_addrs_netlink};
+
+        let routes = default_routes();
+        for interface in unwrap!(get_if_addrs_netlink()) {
+            let ip = interface.addr.ip();
+            assert_eq!(interface.metric, default_route_metric(&routes, &interface.name, &ip));
+            if interface.is_loopback() {
+                assert_eq!(interface.metric, None);
+            }
+        }
+        // Which aren't looked up by plain enumerations.
+        assert!(unwrap!(get_if_addrs_all()).iter().all(|interface| interface.metric.is_none()));
+    }
+
+    #[cfg(any(target_os = "freebsd", target_os = "macos", target_os = "ios"))]
+    #[test]
+    fn parses_route_dump() {
+        use byteorder::{ByteOrder, NativeEndian};
+        use libc::{AF_INET, AF_INET6};
+
+        // A routing message through interface `index` with the given flags and (bit, sockaddr)
+        // pairs of addresses, laid out as the kernel does.
+        fn message(index: u16, flags: u32, addrs: &[(u32, Vec<u8>)]) -> Vec<u8> {
+            let mut msg = vec![0u8; RT_MSGHDR_LEN];
+            NativeEndian::write_u16(&mut msg[4..], index);
+            NativeEndian::write_u32(&mut msg[8..], flags);
+            NativeEndian::write_u32(&mut msg[12..], addrs.iter().fold(0, |bits, a| bits | a.0));
+            for &(_, ref sockaddr) in addrs {
+                msg.extend_from_slice(sockaddr);
+                let padded_len = (msg.len() + SOCKADDR_ALIGN - 1) & !(SOCKADDR_ALIGN - 1);
+                msg.resize(if sockaddr.is_empty() {
+                               padded_len + SOCKADDR_ALIGN
+                           } else {
+                               padded_len
+                           },
+                           0);
+            }
+            let len = msg.len() as u16;
+            NativeEndian::write_u16(&mut msg[0..], len);
+            msg
+        }
+        fn v4(ip: [u8; 4]) -> Vec<u8> {
+            let mut sockaddr = vec![16, AF_INET as u8, 0, 0];
+            sockaddr.extend_from_slice(&ip);
+            sockaddr.extend_from_slice(&[0; 8]);
+            sockaddr
+        }
+        fn v6(ip: [u8; 16]) -> Vec<u8> {
+            let mut sockaddr = vec![28, AF_INET6 as u8, 0, 0, 0, 0, 0, 0];
+            sockaddr.extend_from_slice(&ip);
+            sockaddr.extend_from_slice(&[0; 4]);
+            sockaddr
+        }
+        let link = vec![20, 18, 4, 0, 6, 3, 6, 0, b'e', b'n', b'0', 0, 0, 0, 0, 0, 0, 0, 0, 0];
+        let on_link_netmask = vec![7, 0, 0, 0, 255, 255, 255];
+        // `fe80:4::1`, the link-local address of the router with the scope of `en0` embedded.
+        let mut scoped_router = [0u8; 16];
+        scoped_router[0] = 0xfe;
+        scoped_router[1] = 0x80;
+        scoped_router[3] = 4;
+        scoped_router[15] = 1;
+
+        let dump = [// `default 192.168.1.1 UGSc en0`, with a netmask cut down to nothing.
+                    message(4, 0x803, &[(1, v4([0; 4])), (2, v4([192, 168, 1, 1])), (4, vec![])]),
+                    // `192.168.1 link#4 UCS en0`, an on-link route.
+                    message(4,
+                            0x101,
+                            &[(1, v4([192, 168, 1, 0])), (2, link), (4, on_link_netmask)]),
+                    // `default fe80::1%en0 UGc en0`, without a netmask.
+                    message(4, 0x403, &[(1, v6([0; 16])), (2, v6(scoped_router))]),
+                    // A default route that is down.
+                    message(5, 0x2, &[(1, v4([0; 4])), (2, v4([10, 0, 0, 1]))])]
+            .concat();
+        let mut truncated = dump.clone();
+        truncated.extend_from_slice(&[200, 0]);
+
+        let gateway = |ip: &str| unwrap!(ip.parse::<IpAddr>());
+        let expected = vec![(gateway("192.168.1.1"), 4), (gateway("fe80::1"), 4)];
+        assert_eq!(parse_route_dump(&dump), expected);
+        assert_eq!(parse_route_dump(&truncated), expected);
+    }
+
+    #[cfg(windows)]
+    #[test]
+    #[allow(unsafe_code, trivial_casts)]
+    fn parses_forward_table() {
+        use std::{mem, ptr};
+        use std::net::Ipv4Addr;
+        use winapi::{AF_INET, SOCKADDR_IN};
+
+        use super::windows::{MibIpForwardRow2, SockaddrInet, default_routes_of};
+
+        let sockaddr = |ip: Ipv4Addr| {
+            let mut sa: SOCKADDR_IN = unsafe { mem::zeroed() };
+            sa.sin_family = AF_INET as u16;
+            sa.sin_addr.S_un = u32::from(ip).to_be();
+            let mut sockaddr: SockaddrInet = unsafe { mem::zeroed() };
+            unsafe { ptr::write(&mut sockaddr as *mut SockaddrInet as *mut SOCKADDR_IN, sa) };
+            sockaddr
+        };
+        let row = |prefix_len, next_hop, index| {
+            let mut row: MibIpForwardRow2 = unsafe { mem::zeroed() };
+            row.interface_index = index;
+            row.destination_prefix = sockaddr(Ipv4Addr::new(0, 0, 0, 0));
+            row.destination_prefix_length = prefix_len;
+            row.next_hop = sockaddr(next_hop);
+            row
+        };
+        let rows = [row(0, Ipv4Addr::new(192, 168, 1, 1), 7),
+                    row(24, Ipv4Addr::new(0, 0, 0, 0), 7),
+                    row(0, Ipv4Addr::new(0, 0, 0, 0), 9)];
+        assert_eq!(default_routes_of(&rows),
+                   vec![(IpAddr::V4(Ipv4Addr::new(192, 168, 1, 1)), 7)]);
+    }
+
+    #[test]
+    fn default_gateways_are_on_interfaces() {
+        use common::get_if_addrs::get_if_addrs_all;
+
+        let ifaces = unwrap!(get_if_addrs_all());
+        for (_, name) in default_gateways() {
+            assert!(ifaces.iter().any(|interface| interface.name == name),
+                    "No interface {}",
+                    name);
+        }
+    }
+}
@@ -0,0 +1,1034 @@
+// Helpers choosing among the interfaces on this machine. Each public function enumerates the
+// interfaces and hands them to a private counterpart doing the actual selection, which is what the
+// tests exercise with synthetic interfaces.
+
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, UdpSocket};
+use std::str::FromStr;
+
+use common::get_if_addrs::{AddrFlags, Family, IfAddr, IfFlags, Interface, InterfaceBuilder,
+                           Routability, addr_eq_ignoring_scope, default_gateways, get_if_addrs,
+                           get_if_addrs_detailed};
+
+/// Get the interfaces on the same network as at least one of `peers`, i.e. those through which
+/// the peers can be reached directly rather than via a router. Addresses without a netmask are
+/// left out, as their network isn't known.
+pub fn on_link_interfaces(peers: &[IpAddr]) -> io::Result<Vec<Interface>> {
+    Ok(on_link(try!(get_if_addrs()), peers))
+}
+
+fn on_link(ifaces: Vec<Interface>, peers: &[IpAddr]) -> Vec<Interface> {
+    ifaces.into_iter()
+        .filter(|interface| interface.netmask().is_some())
+        .filter(|interface| peers.iter().any(|peer| interface.addr.contains(peer)))
+        .collect()
+}
+
+/// Get the socket addresses for `port` of the private addresses on this machine on the same
+/// network as one of the private addresses a peer reported, for connecting directly to a peer
+/// behind the same NAT rather than through the NAT's public address, which not every NAT hairpins.
+///
+/// Only private and shared (carrier-grade NAT) addresses count, on either side: global ones don't
+/// need a shared NAT to be reached.
+pub fn same_nat_candidates(peer_private_addrs: &[IpAddr],
+                           port: u16)
+                           -> io::Result<Vec<SocketAddr>> {
+    Ok(same_nat(try!(get_if_addrs()), peer_private_addrs, port))
+}
+
+fn same_nat(ifaces: Vec<Interface>, peer_private_addrs: &[IpAddr], port: u16) -> Vec<SocketAddr> {
+    let behind_nat = |routability| {
+        routability == Routability::Private || routability == Routability::Shared
+    };
+    let peers: Vec<IpAddr> = peer_private_addrs.iter()
+        .cloned()
+        .filter(|peer| behind_nat(Routability::of(peer)))
+        .collect();
+    let private = ifaces.into_iter()
+        .filter(|interface| behind_nat(interface.addr.routability()))
+        .collect();
+    on_link(private, &peers).iter().map(|interface| interface.socket_addr(port)).collect()
+}
+
+/// Get the interface whose network contains the gateway `gw`, i.e. the one through which this
+/// machine reaches that router, or `None` if `gw` isn't on the network of any interface. Where
+/// several networks contain it the most specific one wins, as in routing. Addresses without a
+/// netmask don't contain anything.
+pub fn interface_for_gateway(gw: IpAddr) -> io::Result<Option<Interface>> {
+    Ok(facing(try!(get_if_addrs()), &gw))
+}
+
+fn facing(ifaces: Vec<Interface>, gw: &IpAddr) -> Option<Interface> {
+    let mut best: Option<Interface> = None;
+    let known = ifaces.into_iter().filter(|interface| interface.netmask().is_some());
+    for interface in known.filter(|interface| interface.addr.contains(gw)) {
+        let prefix_len = interface.addr.prefix_len();
+        if best.as_ref().map_or(true, |best| prefix_len > best.addr.prefix_len()) {
+            best = Some(interface);
+        }
+    }
+    best
+}
+
+/// Check whether two hosts with the interface addresses `local` and `remote` share a network, as
+/// told by `IfAddr::same_link()`, so that they could reach each other directly rather than via a
+/// router, e.g. to validate the configuration of a mesh.
+///
+/// Loopback and link-local addresses are left out, as every host has them.
+pub fn interfaces_compatible(local: &[IfAddr], remote: &[IfAddr]) -> bool {
+    let shareable = |addr: &&IfAddr| addr.routability() > Routability::LinkLocal;
+    local.iter()
+        .filter(&shareable)
+        .any(|addr| remote.iter().filter(&shareable).any(|other| addr.same_link(other)))
+}
+
+/// Get the pairs of addresses of different interfaces on this machine whose networks overlap,
+/// e.g. two interfaces both in `192.168.1.0/24`. A host configured like this routes traffic for
+/// the network through only one of them, breaking the assumption that an address is reachable
+/// through the interface whose network contains it.
+///
+/// Aliases such as `eth0:0` count as the interface before the colon, and link-local addresses are
+/// left out, as every interface has the same link-local network, as are addresses without a
+/// netmask.
+pub fn overlapping_subnets() -> io::Result<Vec<(Interface, Interface)>> {
+    Ok(overlapping(try!(get_if_addrs())))
+}
+
+fn overlapping(ifaces: Vec<Interface>) -> Vec<(Interface, Interface)> {
+    let ifaces: Vec<Interface> = ifaces.into_iter()
+        .filter(|interface| interface.addr.routability() != Routability::LinkLocal)
+        .filter(|interface| interface.netmask().is_some())
+        .filter(|interface| interface.addr.prefix_len().is_some())
+        .collect();
+    let device = |interface: &Interface| interface.name.split(':').next().unwrap_or("").to_owned();
+    let mut pairs = Vec::new();
+    for (i, a) in ifaces.iter().enumerate() {
+        for b in &ifaces[i + 1..] {
+            if device(a) != device(b) &&
+               (a.addr.contains(&b.addr.network()) || b.addr.contains(&a.addr.network())) {
+                pairs.push((a.clone(), b.clone()));
+            }
+        }
+    }
+    pairs
+}
+
+/// Get the loopback addresses on this machine, e.g. `127.0.0.1` and `::1`, for when peers are
+/// only ever expected to be local.
+pub fn loopback_interfaces() -> io::Result<Vec<Interface>> {
+    Ok(loopback(try!(get_if_addrs())))
+}
+
+fn loopback(ifaces: Vec<Interface>) -> Vec<Interface> {
+    ifaces.into_iter().filter(Interface::is_loopback).collect()
+}
+
+/// Group interface addresses by the name of their interface, keeping both the groups and the
+/// addresses within each group in the order they were given in.
+///
+/// Each address stays paired with its own netmask and broadcast address, also when an interface
+/// has several addresses on different networks. Aliases labelled like `eth0:0` are names of their
+/// own and get a group of their own.
+pub fn group_by_name(ifaces: Vec<Interface>) -> Vec<(String, Vec<Interface>)> {
+    let mut groups: Vec<(String, Vec<Interface>)> = Vec::new();
+    for interface in ifaces {
+        if let Some(pos) = groups.iter().position(|&(ref name, _)| *name == interface.name) {
+            groups[pos].1.push(interface);
+            continue;
+        }
+        groups.push((interface.name.clone(), vec![interface]));
+    }
+    groups
+}
+
+/// Group interface addresses by their network, as the network address and the prefix length, or
+/// `None` for the netmasks which aren't contiguous. An address without a netmask is a network of
+/// its own, as its address and `None`. Both the groups and the addresses within each group keep
+/// the order they were given in.
+pub fn group_by_network(ifaces: Vec<Interface>) -> Vec<((IpAddr, Option<u8>), Vec<Interface>)> {
+    let mut groups: Vec<((IpAddr, Option<u8>), Vec<Interface>)> = Vec::new();
+    for interface in ifaces {
+        let network = match interface.netmask() {
+            Some(_) => (interface.addr.network(), interface.addr.prefix_len()),
+            None => (interface.addr.ip(), None),
+        };
+        if let Some(pos) = groups.iter().position(|&(ref other, _)| *other == network) {
+            groups[pos].1.push(interface);
+            continue;
+        }
+        groups.push((network, vec![interface]));
+    }
+    groups
+}
+
+/// Get one address per network this machine is on, for advertising the fewest addresses which
+/// still reach every network, e.g. one of the two addresses of an interface on the same `/24`.
+///
+/// Each network is represented by its address most worth advertising, picked as for
+/// `representative_addresses()`. Networks keep the order they are first listed in.
+pub fn subnet_representatives() -> io::Result<Vec<Interface>> {
+    Ok(per_network(try!(get_if_addrs_detailed())))
+}
+
+fn per_network(ifaces: Vec<Interface>) -> Vec<Interface> {
+    group_by_network(ifaces)
+        .into_iter()
+        .filter_map(|(_, group)| most_preferred(group))
+        .collect()
+}
+
+/// Check whether this machine is on two or more networks, counting those of IPv4 and IPv6 apart,
+/// in which case listening on each interface address is worth it rather than on a single address.
+/// Loopback and link-local networks don't count. If the interfaces can't be enumerated this is
+/// `false`.
+pub fn is_multihomed() -> bool {
+    match get_if_addrs() {
+        Ok(ifaces) => multihomed(ifaces),
+        Err(e) => {
+            warn!("Could not enumerate interfaces: {:?}", e);
+            false
+        }
+    }
+}
+
+fn multihomed(ifaces: Vec<Interface>) -> bool {
+    let reachable = ifaces.into_iter()
+        .filter(|interface| interface.addr.routability() > Routability::LinkLocal)
+        .collect();
+    group_by_network(reachable).len() >= 2
+}
+
+/// Get at most one IPv4 and one IPv6 address per interface, picking the one most worth
+/// advertising to peers.
+///
+/// The most reachable address wins, then one that isn't temporary or deprecated, then the one with
+/// the longest valid lifetime (where the platform reports lifetimes). Remaining ties go to the
+/// address listed first.
+pub fn representative_addresses() -> io::Result<Vec<Interface>> {
+    Ok(representatives(try!(get_if_addrs_detailed())))
+}
+
+fn representatives(ifaces: Vec<Interface>) -> Vec<Interface> {
+    let mut ret = Vec::new();
+    for (_, group) in group_by_name(ifaces) {
+        let (v4s, v6s): (Vec<Interface>, Vec<Interface>) =
+            group.into_iter().partition(|interface| interface.addr.ip().is_ipv4());
+        ret.extend(most_preferred(v4s));
+        ret.extend(most_preferred(v6s));
+    }
+    ret
+}
+
+fn most_preferred(ifaces: Vec<Interface>) -> Option<Interface> {
+    let mut best: Option<Interface> = None;
+    for interface in ifaces {
+        let better = match best {
+            Some(ref best) => preference(&interface) > preference(best),
+            None => true,
+        };
+        if better {
+            best = Some(interface);
+        }
+    }
+    best
+}
+
+fn preference(interface: &Interface) -> (Routability, bool, bool, u32) {
+    (interface.addr.routability(),
+     !interface.addr_flags.contains(AddrFlags::TEMPORARY),
+     !interface.addr_flags.contains(AddrFlags::DEPRECATED),
+     interface.valid_lft.unwrap_or(0))
+}
+
+/// Get the interface address of `family` traffic to the internet leaves from, as chosen by the
+/// routing table, or `None` if there is no route to the internet for `family`.
+///
+/// Nothing is sent to find out: the OS is only asked which address it would use as the source.
+pub fn default_interface(family: Family) -> io::Result<Option<Interface>> {
+    let source = match try!(route_source(family)) {
+        Some(source) => source,
+        None => return Ok(None),
+    };
+    Ok(with_ip(try!(get_if_addrs()), source))
+}
+
+fn route_source(family: Family) -> io::Result<Option<IpAddr>> {
+    // Well-known public DNS servers, only used as destinations to look routes up with.
+    let (unspecified, internet) = match family {
+        Family::V4 => ("0.0.0.0:0", "8.8.8.8:53"),
+        Family::V6 => ("[::]:0", "[2001:4860:4860::8888]:53"),
+    };
+    let socket = try!(UdpSocket::bind(unwrap!(SocketAddr::from_str(unspecified))));
+    match socket.connect(unwrap!(SocketAddr::from_str(internet))) {
+        Ok(()) => Ok(Some(try!(socket.local_addr()).ip())),
+        Err(ref e) if is_unroutable(e) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+#[cfg(unix)]
+fn is_unroutable(e: &io::Error) -> bool {
+    use libc::{EADDRNOTAVAIL, ENETUNREACH};
+    e.raw_os_error().map_or(false, |code| code == ENETUNREACH || code == EADDRNOTAVAIL)
+}
+
+#[cfg(windows)]
+fn is_unroutable(e: &io::Error) -> bool {
+    // `WSAENETUNREACH` and `WSAEADDRNOTAVAIL`.
+    e.raw_os_error().map_or(false, |code| code == 10051 || code == 10049)
+}
+
+fn with_ip(ifaces: Vec<Interface>, ip: IpAddr) -> Option<Interface> {
+    ifaces.into_iter().find(|interface| addr_eq_ignoring_scope(&interface.addr.ip(), &ip))
+}
+
+/// Get the interface address of `family` to send STUN binding requests from, i.e. the one most
+/// likely to have a path to the internet, or `None` if none seems to have one.
+///
+/// Global addresses win, ranked as for `representative_addresses()`. Without any, the best
+/// address of an interface through which a default gateway of `family` is reached, as told by
+/// `default_gateways()`, is chosen.
+pub fn stun_source(family: Family) -> io::Result<Option<Interface>> {
+    Ok(stun(try!(get_if_addrs_detailed()), &default_gateways(), family))
+}
+
+fn stun(ifaces: Vec<Interface>,
+        gateways: &[(IpAddr, String)],
+        family: Family)
+        -> Option<Interface> {
+    let (global, others): (Vec<Interface>, Vec<Interface>) = ifaces.into_iter()
+        .filter(|interface| interface.addr.family() == family)
+        .filter(|interface| interface.addr.routability() > Routability::LinkLocal)
+        .partition(|interface| interface.addr.routability() == Routability::Global);
+    most_preferred(global).or_else(|| {
+        most_preferred(others.into_iter()
+            .filter(|interface| {
+                gateways.iter().any(|&(ref gateway, ref name)| {
+                    gateway.is_ipv4() == (family == Family::V4) && *name == interface.name
+                })
+            })
+            .collect())
+    })
+}
+
+/// Get the addresses a peer-to-peer node should bind to on `port` and advertise to its peers.
+///
+/// These are the `representative_addresses()` which can be reached from other machines, leaving
+/// out loopback and link-local addresses as well as the bridges of containers and virtual
+/// machines (`docker0`, `virbr0`, ...). Global addresses come before private ones.
+pub fn recommended_bind_addrs(port: u16) -> io::Result<Vec<SocketAddr>> {
+    Ok(recommended(try!(get_if_addrs_detailed()), port))
+}
+
+fn recommended(ifaces: Vec<Interface>, port: u16) -> Vec<SocketAddr> {
+    let reachable = ifaces.into_iter()
+        .filter(|interface| interface.addr.routability() > Routability::LinkLocal)
+        .filter(|interface| !is_virtual_bridge(&interface.name))
+        .collect();
+    let mut ret = representatives(reachable);
+    // The sort is stable, so interfaces keep their order within each class.
+    ret.sort_by(|a, b| b.addr.routability().cmp(&a.addr.routability()));
+    ret.iter().map(|interface| interface.socket_addr(port)).collect()
+}
+
+/// How the addresses advertised to peers before have to change for the interfaces on this machine
+/// now, as `reconcile_advertised()` tells.
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub struct ReconcileResult {
+    /// The previously advertised addresses still assigned to an interface.
+    pub still_valid: Vec<SocketAddr>,
+    /// The previously advertised addresses no longer assigned to any interface, which peers
+    /// should be told to forget.
+    pub withdrawn: Vec<SocketAddr>,
+    /// The addresses `recommended_bind_addrs()` gives now which weren't advertised before.
+    pub added: Vec<SocketAddr>,
+}
+
+/// Reconcile the addresses advertised to peers before, `previous`, with the interfaces on this
+/// machine now, e.g. after a laptop moved to another network and the addresses it had there are
+/// gone.
+///
+/// The added addresses are those of `recommended_bind_addrs()` on each of the ports of `previous`,
+/// so nothing is added if nothing was advertised before.
+pub fn reconcile_advertised(previous: &[SocketAddr]) -> io::Result<ReconcileResult> {
+    Ok(reconciled(try!(get_if_addrs_detailed()), previous))
+}
+
+fn reconciled(ifaces: Vec<Interface>, previous: &[SocketAddr]) -> ReconcileResult {
+    let (still_valid, withdrawn): (Vec<SocketAddr>, Vec<SocketAddr>) = previous.iter()
+        .partition(|addr| interface_for_addr(&ifaces, &addr.ip()).is_some());
+
+    let mut ports: Vec<u16> = Vec::new();
+    for addr in previous {
+        if !ports.contains(&addr.port()) {
+            ports.push(addr.port());
+        }
+    }
+    let mut added = Vec::new();
+    for port in ports {
+        for addr in recommended(ifaces.clone(), port) {
+            let advertised = previous.iter().any(|previous| {
+                previous.port() == port && addr_eq_ignoring_scope(&previous.ip(), &addr.ip())
+            });
+            if !advertised {
+                added.push(addr);
+            }
+        }
+    }
+
+    ReconcileResult {
+        still_valid: still_valid,
+        withdrawn: withdrawn,
+        added: added,
+    }
+}
+
+// The first of `ifaces` with the address `ip`, whatever the scope it is written with.
+fn interface_for_addr<'a>(ifaces: &'a [Interface], ip: &IpAddr) -> Option<&'a Interface> {
+    ifaces.iter().find(|interface| addr_eq_ignoring_scope(&interface.addr.ip(), ip))
+}
+
+/// Get the addresses on this machine to listen on at `port`, leaving out those in any of the
+/// `excluded` networks, each given as an address and a prefix length, e.g. a management network
+/// nothing should be exposed on.
+pub fn listen_addrs_excluding(excluded: &[(IpAddr, u8)], port: u16) -> io::Result<Vec<SocketAddr>> {
+    Ok(excluding(try!(get_if_addrs()), excluded, port))
+}
+
+fn excluding(ifaces: Vec<Interface>, excluded: &[(IpAddr, u8)], port: u16) -> Vec<SocketAddr> {
+    let excluded: Vec<IfAddr> = excluded.iter()
+        .map(|&(ip, prefix_len)| network_of(ip, prefix_len))
+        .collect();
+    ifaces.iter()
+        .filter(|interface| !excluded.iter().any(|network| network.contains(&interface.addr.ip())))
+        .map(|interface| interface.socket_addr(port))
+        .collect()
+}
+
+/// Get the addresses on this machine to listen on at `port`, leaving out the `excluded` addresses
+/// themselves, e.g. a management address, but not the rest of their networks as
+/// `listen_addrs_excluding()` would. Link-local addresses are excluded whether or not their scope
+/// is embedded in them, as by `addr_eq_ignoring_scope()`.
+pub fn listen_addrs_excluding_addrs(excluded: &[IpAddr],
+                                    port: u16)
+                                    -> io::Result<Vec<SocketAddr>> {
+    Ok(excluding_addrs(try!(get_if_addrs()), excluded, port))
+}
+
+fn excluding_addrs(ifaces: Vec<Interface>, excluded: &[IpAddr], port: u16) -> Vec<SocketAddr> {
+    ifaces.iter()
+        .filter(|interface| {
+            !excluded.iter().any(|ip| addr_eq_ignoring_scope(ip, &interface.addr.ip()))
+        })
+        .map(|interface| interface.socket_addr(port))
+        .collect()
+}
+
+/// The network `ip/prefix_len` as an interface address, so that `IfAddr::contains()` tells what is
+/// on it. Prefix lengths longer than the address are taken to be that of a single address.
+pub fn network_of(ip: IpAddr, prefix_len: u8) -> IfAddr {
+    let netmask = match ip {
+        IpAddr::V4(_) => {
+            let shift = 32u32.saturating_sub(u32::from(prefix_len));
+            IpAddr::V4(Ipv4Addr::from((!0u32).checked_shl(shift).unwrap_or(0)))
+        }
+        IpAddr::V6(_) => {
+            let shift = 128u32.saturating_sub(u32::from(prefix_len));
+            IpAddr::V6(Ipv6Addr::from((!0u128).checked_shl(shift).unwrap_or(0)))
+        }
+    };
+    InterfaceBuilder::new().addr(ip).netmask(netmask).build().addr
+}
+
+/// Whether `name` is that of a bridge set up for containers or virtual machines, going by the
+/// names Docker, libvirt, LXC/LXD, VirtualBox and VMware give them. Such addresses can't be reached
+/// from other machines.
+pub fn is_virtual_bridge(name: &str) -> bool {
+    ["docker", "br-", "veth", "virbr", "lxcbr", "lxdbr", "vboxnet", "vmnet"]
+        .iter()
+        .any(|prefix| name.starts_with(prefix))
+}
+
+/// The order `get_if_addrs_ordered()` lists interface addresses in.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum EnumOrder {
+    /// The order the OS lists them in, as by `get_if_addrs()`.
+    AsReturned,
+    /// All IPv4 addresses, then all IPv6 ones.
+    V4First,
+    /// All IPv6 addresses, then all IPv4 ones.
+    V6First,
+    /// The addresses of each interface together, as by `group_by_name()`.
+    ByInterface,
+}
+
+/// Get the interfaces on this machine, as by `get_if_addrs()`, in the given order. Each order is
+/// stable, so addresses keep the order the OS lists them in where it doesn't tell them apart.
+pub fn get_if_addrs_ordered(order: EnumOrder) -> io::Result<Vec<Interface>> {
+    Ok(ordered(try!(get_if_addrs()), order))
+}
+
+pub fn ordered(mut ifaces: Vec<Interface>, order: EnumOrder) -> Vec<Interface> {
+    match order {
+        EnumOrder::AsReturned => ifaces,
+        EnumOrder::V4First => {
+            ifaces.sort_by_key(|interface| interface.addr.family() != Family::V4);
+            ifaces
+        }
+        EnumOrder::V6First => {
+            ifaces.sort_by_key(|interface| interface.addr.family() != Family::V6);
+            ifaces
+        }
+        EnumOrder::ByInterface => {
+            group_by_name(ifaces).into_iter().flat_map(|(_, group)| group).collect()
+        }
+    }
+}
+
+/// Get the interfaces on this machine, as by `get_if_addrs()`, sorted by `score` from highest to
+/// lowest, for when none of the orders of `EnumOrder` fits the selection policy at hand.
+///
+/// The sort is stable, so addresses with the same score keep the order the OS lists them in.
+pub fn get_if_addrs_scored<F>(score: F) -> io::Result<Vec<Interface>>
+    where F: Fn(&Interface) -> i64
+{
+    Ok(scored(try!(get_if_addrs()), score))
+}
+
+fn scored<F>(mut ifaces: Vec<Interface>, score: F) -> Vec<Interface>
+    where F: Fn(&Interface) -> i64
+{
+    ifaces.sort_by(|a, b| score(b).cmp(&score(a)));
+    ifaces
+}
+
+/// Get the pairs of IPv4 address and broadcast address a beacon is sent from and to, one for each
+/// address on this machine which can broadcast.
+///
+/// The broadcast address is the one the OS reports or, failing that, the one computed from the
+/// netmask. Loopback addresses are left out, as are those on point-to-point links and on networks
+/// too small to have a broadcast address (`/31` and `/32`).
+pub fn beacon_targets() -> io::Result<Vec<(IpAddr, IpAddr)>> {
+    Ok(beacons(try!(get_if_addrs())))
+}
+
+fn beacons(ifaces: Vec<Interface>) -> Vec<(IpAddr, IpAddr)> {
+    ifaces.iter()
+        .filter_map(|interface| {
+            beacon_broadcast(interface)
+                .map(|broadcast| (interface.addr.ip(), IpAddr::V4(broadcast)))
+        })
+        .collect()
+}
+
+// The broadcast address a beacon from `interface` is sent to, if any, as by `beacon_targets()`.
+fn beacon_broadcast(interface: &Interface) -> Option<Ipv4Addr> {
+    let ifv4_addr = match interface.addr {
+        IfAddr::V4(ref ifv4_addr) if !ifv4_addr.is_loopback() => ifv4_addr,
+        _ => return None,
+    };
+    ifv4_addr.broadcast.or_else(|| {
+        if interface.flags.contains(IfFlags::POINT_TO_POINT) {
+            return None;
+        }
+        match ifv4_addr.prefix_len() {
+            Some(prefix_len) if prefix_len <= 30 => {
+                let ip = u32::from(ifv4_addr.ip);
+                let netmask = u32::from(ifv4_addr.netmask);
+                Some(Ipv4Addr::from(ip | !netmask))
+            }
+            _ => None,
+        }
+    })
+}
+
+/// Get the interfaces of `beacon_targets()` the OS actually lets broadcast from.
+///
+/// Some interfaces, such as certain virtual NICs, have a broadcast address but refuse broadcasts
+/// all the same. For each address, a UDP socket is bound to it and `SO_BROADCAST` set on it; only
+/// those for which both succeed are kept. No datagram is sent.
+pub fn broadcast_capable_interfaces() -> io::Result<Vec<Interface>> {
+    Ok(broadcast_capable(try!(get_if_addrs()), |interface| {
+        let socket = try!(UdpSocket::bind(interface.socket_addr(0)));
+        socket.set_broadcast(true)
+    }))
+}
+
+fn broadcast_capable<F>(ifaces: Vec<Interface>, mut set_up: F) -> Vec<Interface>
+    where F: FnMut(&Interface) -> io::Result<()>
+{
+    ifaces.into_iter()
+        .filter(|interface| beacon_broadcast(interface).is_some())
+        .filter(|interface| set_up(interface).is_ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::net::{IpAddr, Ipv4Addr};
+    use std::str::FromStr;
+
+    use common::get_if_addrs::{AddrFlags, Interface, InterfaceBuilder};
+    use common::get_if_addrs::test_util::interface;
+
+    fn v4(a: u8, b: u8, c: u8, d: u8) -> IpAddr {
+        IpAddr::V4(Ipv4Addr::new(a, b, c, d))
+    }
+
+    fn ip(ip: &str) -> IpAddr {
+        unwrap!(IpAddr::from_str(ip))
+    }
+
+    // An interface address the OS reported no netmask for.
+    fn without_netmask(name: &str, ip: IpAddr) -> Interface {
+        InterfaceBuilder::new().name(name).addr(ip).addr_flags(AddrFlags::NO_NETMASK).build()
+    }
+
+    #[test]
+    fn overlapping_subnets_of_two_interfaces() {
+        let mask24 = "255.255.255.0";
+        let eth0 = interface("eth0", "192.168.1.5", mask24);
+        let wlan0 = interface("wlan0", "192.168.1.6", mask24);
+        let ifaces = vec![eth0.clone(),
+                          interface("eth0:1", "192.168.1.7", mask24),
+                          wlan0.clone(),
+                          interface("eth1", "192.168.2.5", mask24),
+                          interface("tun0", "192.168.0.1", "255.255.0.0"),
+                          interface("eth0", "fe80::5", "ffff:ffff:ffff:ffff::"),
+                          interface("wlan0", "fe80::6", "ffff:ffff:ffff:ffff::")];
+        let pairs = overlapping(ifaces.clone());
+        assert_eq!(pairs[0], (eth0, wlan0));
+        assert_eq!(pairs.iter()
+                       .map(|&(ref a, ref b)| (&a.name[..], &b.name[..]))
+                       .collect::<Vec<_>>(),
+                   vec![("eth0", "wlan0"),
+                        ("eth0", "tun0"),
+                        ("eth0:1", "wlan0"),
+                        ("eth0:1", "tun0"),
+                        ("wlan0", "tun0"),
+                        ("eth1", "tun0")]);
+        assert!(overlapping(ifaces[..2].to_vec()).is_empty());
+        assert!(overlapping(vec![ifaces[0].clone(), ifaces[3].clone()]).is_empty());
+        assert!(overlapping(vec![ifaces[0].clone(), without_netmask("ppp0", v4(192, 168, 1, 9))])
+            .is_empty());
+    }
+
+    #[test]
+    fn on_link_with_peers_in_one_subnet() {
+        let ifaces = vec![interface("eth0", "192.168.1.5", "255.255.255.0"),
+                          interface("eth1", "10.0.0.5", "255.0.0.0")];
+        let peers = [v4(192, 168, 1, 20), v4(172, 16, 0, 1)];
+
+        assert_eq!(on_link(ifaces.clone(), &peers), vec![ifaces[0].clone()]);
+        assert!(on_link(ifaces, &[v4(172, 16, 0, 1)]).is_empty());
+        assert!(on_link(vec![without_netmask("ppp0", v4(10, 64, 0, 2))], &peers).is_empty());
+    }
+
+    #[test]
+    fn reconciled_after_a_network_move() {
+        let sock = |ip, port| SocketAddr::new(ip, port);
+        let mask24 = "255.255.255.0";
+        let v6_netmask = "ffff:ffff:ffff:ffff::";
+        let at_home = vec![interface("lo", "127.0.0.1", "255.0.0.0"),
+                           interface("wlan0", "192.168.1.5", mask24),
+                           interface("wlan0", "2001:db8:1::5", v6_netmask),
+                           interface("eth0", "10.0.0.5", mask24)];
+        let advertised = recommended(at_home.clone(), 5483);
+        let unchanged = reconciled(at_home, &advertised);
+        assert_eq!(unchanged.still_valid, advertised);
+        assert!(unchanged.withdrawn.is_empty());
+        assert!(unchanged.added.is_empty());
+
+        // At work the wireless network is another one, but the wired one is the same.
+        let at_work = vec![interface("lo", "127.0.0.1", "255.0.0.0"),
+                           interface("wlan0", "172.16.4.20", mask24),
+                           interface("wlan0", "2001:db8:2::20", v6_netmask),
+                           interface("eth0", "10.0.0.5", mask24)];
+        let result = reconciled(at_work, &advertised);
+        assert_eq!(result.still_valid, vec![sock(v4(10, 0, 0, 5), 5483)]);
+        assert_eq!(result.withdrawn,
+                   vec![sock(ip("2001:db8:1::5"), 5483), sock(v4(192, 168, 1, 5), 5483)]);
+        assert_eq!(result.added,
+                   vec![sock(ip("2001:db8:2::20"), 5483), sock(v4(172, 16, 4, 20), 5483)]);
+
+        assert_eq!(reconciled(Vec::new(), &[]),
+                   ReconcileResult {
+                       still_valid: Vec::new(),
+                       withdrawn: Vec::new(),
+                       added: Vec::new(),
+                   });
+    }
+
+    #[test]
+    fn candidates_behind_the_same_nat() {
+        let ifaces = vec![interface("eth0", "192.168.0.5", "255.255.255.0"),
+                          interface("eth0", "2001:db8::5", "ffff:ffff:ffff:ffff::"),
+                          interface("wlan0", "10.0.0.5", "255.255.255.0")];
+        let peers = [v4(192, 168, 0, 20), ip("2001:db8::20"), v4(172, 16, 0, 1)];
+        assert_eq!(same_nat(ifaces.clone(), &peers, 5483),
+                   vec![ifaces[0].socket_addr(5483)]);
+
+        assert!(same_nat(ifaces.clone(), &[v4(192, 168, 1, 20), v4(10, 0, 1, 5)], 5483)
+            .is_empty());
+        assert!(same_nat(ifaces, &[], 5483).is_empty());
+        assert!(same_nat(vec![without_netmask("ppp0", v4(192, 168, 0, 9))], &peers, 5483)
+            .is_empty());
+    }
+
+    #[test]
+    fn interface_facing_a_gateway() {
+        let ifaces = vec![interface("eth1", "10.0.0.5", "255.0.0.0"),
+                          interface("eth0", "192.168.1.5", "255.255.255.0"),
+                          interface("tun0", "192.168.0.1", "255.255.0.0"),
+                          interface("eth0", "2001:db8::5", "ffff:ffff:ffff:ffff::")];
+        assert_eq!(facing(ifaces.clone(), &v4(192, 168, 1, 1)), Some(ifaces[1].clone()));
+        assert_eq!(facing(ifaces.clone(), &v4(192, 168, 2, 1)), Some(ifaces[2].clone()));
+        assert_eq!(facing(ifaces.clone(), &v4(10, 10, 0, 1)), Some(ifaces[0].clone()));
+        assert_eq!(facing(ifaces.clone(), &ip("2001:db8::1")), Some(ifaces[3].clone()));
+        assert_eq!(facing(ifaces.clone(), &v4(172, 16, 0, 1)), None);
+
+        let mut with_unknown = vec![without_netmask("ppp0", v4(192, 168, 1, 9))];
+        with_unknown.extend(ifaces.clone());
+        assert_eq!(facing(with_unknown.clone(), &v4(192, 168, 1, 1)), Some(ifaces[1].clone()));
+        assert_eq!(facing(with_unknown, &v4(172, 16, 0, 1)), None);
+    }
+
+    #[test]
+    fn compatibility_of_subnet_sets() {
+        let addr = |ip, netmask| interface("", ip, netmask).addr;
+        let v6_netmask = "ffff:ffff:ffff:ffff::";
+        let local = vec![addr("127.0.0.1", "255.0.0.0"),
+                         addr("fe80::5", v6_netmask),
+                         addr("192.168.1.5", "255.255.255.0"),
+                         addr("2001:db8:1::5", v6_netmask)];
+        let overlapping = vec![addr("10.0.0.5", "255.0.0.0"),
+                               addr("192.168.1.20", "255.255.255.0")];
+        let disjoint = vec![addr("127.0.0.1", "255.0.0.0"),
+                            addr("fe80::20", v6_netmask),
+                            addr("192.168.1.20", "255.255.255.128"),
+                            addr("2001:db8:2::20", v6_netmask)];
+        let v6_overlapping = vec![addr("2001:db8:1::20", v6_netmask)];
+
+        assert!(interfaces_compatible(&local, &overlapping));
+        assert!(interfaces_compatible(&overlapping, &local));
+        assert!(interfaces_compatible(&local, &v6_overlapping));
+        assert!(!interfaces_compatible(&local, &disjoint));
+        assert!(!interfaces_compatible(&local, &[]));
+    }
+
+    #[test]
+    fn grouping_keeps_netmasks_with_their_addresses() {
+        let with_broadcast = |name, ip, netmask, broadcast| {
+            InterfaceBuilder::new()
+                .name(name)
+                .addr(ip)
+                .netmask(netmask)
+                .broadcast(broadcast)
+                .build()
+        };
+        let primary = with_broadcast("eth0",
+                                     v4(192, 168, 1, 5),
+                                     v4(255, 255, 255, 0),
+                                     v4(192, 168, 1, 255));
+        let secondary = with_broadcast("eth0",
+                                       v4(10, 0, 0, 5),
+                                       v4(255, 255, 255, 0),
+                                       v4(10, 0, 0, 255));
+        let alias = with_broadcast("eth0:0",
+                                   v4(172, 16, 0, 5),
+                                   v4(255, 255, 0, 0),
+                                   v4(172, 16, 255, 255));
+
+        let groups = group_by_name(vec![primary.clone(), alias.clone(), secondary.clone()]);
+        assert_eq!(groups,
+                   vec![("eth0".to_string(), vec![primary, secondary]),
+                        ("eth0:0".to_string(), vec![alias])]);
+        for interface in groups.iter().flat_map(|&(_, ref group)| group) {
+            assert!(interface.addr.contains(&interface.addr.ip()));
+            assert_eq!(interface.addr.prefix_len(),
+                       Some(if interface.name == "eth0" { 24 } else { 16 }));
+        }
+    }
+
+    #[test]
+    fn listen_addrs_outside_excluded_networks() {
+        let ifaces = vec![interface("eth0", "192.168.1.5", "255.255.255.0"),
+                          interface("mgmt0", "10.20.30.40", "255.255.0.0"),
+                          interface("mgmt0", "2001:db8:1::5", "ffff:ffff:ffff:ffff::"),
+                          interface("eth1", "10.20.31.40", "255.255.0.0")];
+        let excluded = [(v4(10, 20, 30, 0), 24), (ip("2001:db8:1::"), 48)];
+        assert_eq!(excluding(ifaces.clone(), &excluded, 5483),
+                   vec![ifaces[0].socket_addr(5483), ifaces[3].socket_addr(5483)]);
+
+        assert_eq!(excluding(ifaces.clone(), &[(v4(10, 20, 30, 40), 40)], 5483).len(), 3);
+        assert!(excluding(ifaces.clone(), &[(v4(0, 0, 0, 0), 0), (ip("::"), 0)], 5483).is_empty());
+        assert_eq!(excluding(ifaces.clone(), &[], 5483).len(), ifaces.len());
+
+        // Whether an address is excluded only depends on the address.
+        let unknown = vec![without_netmask("ppp0", v4(10, 20, 30, 41)),
+                           without_netmask("ppp1", v4(10, 20, 40, 41))];
+        assert_eq!(excluding(unknown.clone(), &excluded, 5483), vec![unknown[1].socket_addr(5483)]);
+    }
+
+    #[test]
+    fn listen_addrs_other_than_excluded_addresses() {
+        let ifaces = vec![interface("eth0", "192.168.1.5", "255.255.255.0"),
+                          interface("mgmt0", "10.20.30.40", "255.255.0.0"),
+                          interface("mgmt0", "10.20.30.41", "255.255.0.0"),
+                          interface("eth1", "2001:db8::5", "ffff:ffff:ffff:ffff::"),
+                          interface("eth1", "fe80:2::5", "ffff:ffff:ffff:ffff::")];
+        assert_eq!(excluding_addrs(ifaces.clone(), &[v4(10, 20, 30, 40)], 5483),
+                   vec![ifaces[0].socket_addr(5483),
+                        ifaces[2].socket_addr(5483),
+                        ifaces[3].socket_addr(5483),
+                        ifaces[4].socket_addr(5483)]);
+        assert_eq!(excluding_addrs(ifaces.clone(), &[v4(10, 20, 30, 0)], 5483).len(), 5);
+        assert_eq!(excluding_addrs(ifaces.clone(), &[], 5483).len(), ifaces.len());
+        // The BSDs report link-local addresses with their scope embedded.
+        assert_eq!(excluding_addrs(ifaces.clone(), &[ip("fe80::5")], 5483),
+                   excluding_addrs(ifaces[..4].to_vec(), &[], 5483));
+    }
+
+    #[test]
+    fn scored_by_subnet() {
+        let ifaces = vec![interface("eth0", "192.168.1.5", "255.255.255.0"),
+                          interface("eth1", "10.0.0.5", "255.0.0.0"),
+                          interface("eth1", "2001:db8::5", "ffff:ffff:ffff:ffff::"),
+                          interface("eth2", "10.0.0.6", "255.0.0.0")];
+        let subnet = interface("", "10.0.0.0", "255.0.0.0").addr;
+        let in_subnet = |interface: &Interface| {
+            if subnet.contains(&interface.addr.ip()) { 1 } else { 0 }
+        };
+        assert_eq!(scored(ifaces.clone(), in_subnet),
+                   vec![ifaces[1].clone(),
+                        ifaces[3].clone(),
+                        ifaces[0].clone(),
+                        ifaces[2].clone()]);
+
+        let all = unwrap!(get_if_addrs());
+        assert_eq!(unwrap!(get_if_addrs_scored(|_| 0)), all);
+    }
+
+    #[test]
+    fn beacons_of_broadcastable_addresses() {
+        let ifaces = vec![InterfaceBuilder::new()
+                              .name("eth0")
+                              .addr(v4(192, 168, 1, 5))
+                              .netmask(v4(255, 255, 255, 0))
+                              .broadcast(v4(192, 168, 1, 255))
+                              .build(),
+                          interface("eth1", "10.1.2.3", "255.255.0.0"),
+                          interface("tun0", "10.8.0.2", "255.255.255.255"),
+                          InterfaceBuilder::new()
+                              .name("ppp0")
+                              .addr(v4(100, 64, 0, 2))
+                              .netmask(v4(255, 255, 255, 0))
+                              .flags(IfFlags::POINT_TO_POINT)
+                              .build(),
+                          interface("eth0", "2001:db8::5", "ffff:ffff:ffff:ffff::"),
+                          interface("lo", "127.0.0.1", "255.0.0.0")];
+        assert_eq!(beacons(ifaces),
+                   vec![(v4(192, 168, 1, 5), v4(192, 168, 1, 255)),
+                        (v4(10, 1, 2, 3), v4(10, 1, 255, 255))]);
+    }
+
+    #[test]
+    fn broadcast_capable_only() {
+        let ifaces = vec![interface("eth0", "192.168.1.5", "255.255.255.0"),
+                          interface("vnic0", "10.1.2.3", "255.255.0.0"),
+                          interface("lo", "127.0.0.1", "255.0.0.0")];
+        let mut attempted = Vec::new();
+        let capable = broadcast_capable(ifaces.clone(), |interface| {
+            attempted.push(interface.name.clone());
+            if interface.name == "vnic0" {
+                Err(io::Error::new(io::ErrorKind::PermissionDenied, "No broadcasts"))
+            } else {
+                Ok(())
+            }
+        });
+        assert_eq!(capable, vec![ifaces[0].clone()]);
+        assert_eq!(attempted, vec!["eth0".to_owned(), "vnic0".to_owned()]);
+
+        let capable = unwrap!(broadcast_capable_interfaces());
+        let targets = unwrap!(beacon_targets());
+        assert!(capable.len() <= targets.len());
+        for interface in &capable {
+            assert!(!interface.is_loopback());
+            assert!(targets.iter().any(|&(ip, _)| ip == interface.addr.ip()));
+        }
+    }
+
+    #[test]
+    fn loopbacks_only() {
+        let ifaces = vec![interface("eth0", "192.168.1.5", "255.255.255.0"),
+                          interface("lo", "127.0.0.1", "255.0.0.0"),
+                          interface("lo", "::1", "ffff:ffff:ffff:ffff::")];
+        assert_eq!(loopback(ifaces.clone()), ifaces[1..].to_vec());
+
+        let loopbacks = unwrap!(loopback_interfaces());
+        assert!(!loopbacks.is_empty());
+        assert!(loopbacks.iter().all(Interface::is_loopback));
+    }
+
+    #[test]
+    fn one_representative_per_network() {
+        let mask24 = "255.255.255.0";
+        let mut temporary = interface("eth0", "2001:db8::1234", "ffff:ffff:ffff:ffff::");
+        temporary.addr_flags = AddrFlags::TEMPORARY;
+        let ifaces = vec![interface("eth0", "192.168.1.5", mask24),
+                          interface("eth0", "192.168.1.6", mask24),
+                          interface("wlan0", "192.168.1.7", "255.255.0.0"),
+                          temporary,
+                          interface("eth0", "2001:db8::5", "ffff:ffff:ffff:ffff::"),
+                          interface("eth1", "10.0.0.5", "255.0.0.0")];
+
+        let groups = group_by_network(ifaces.clone());
+        let sizes: Vec<_> =
+            groups.iter().map(|&(network, ref group)| (network, group.len())).collect();
+        assert_eq!(sizes,
+                   vec![((v4(192, 168, 1, 0), Some(24)), 2),
+                        ((v4(192, 168, 0, 0), Some(16)), 1),
+                        ((ip("2001:db8::"), Some(64)), 2),
+                        ((v4(10, 0, 0, 0), Some(8)), 1)]);
+        assert_eq!(per_network(ifaces.clone()),
+                   vec![ifaces[0].clone(),
+                        ifaces[2].clone(),
+                        ifaces[4].clone(),
+                        ifaces[5].clone()]);
+        assert_eq!(per_network(ifaces[..2].to_vec()), vec![ifaces[0].clone()]);
+
+        let unknown = vec![without_netmask("ppp0", v4(192, 168, 1, 8)),
+                           without_netmask("ppp1", v4(192, 168, 1, 9))];
+        let networks: Vec<_> =
+            group_by_network(unknown).into_iter().map(|(network, _)| network).collect();
+        assert_eq!(networks, vec![(v4(192, 168, 1, 8), None), (v4(192, 168, 1, 9), None)]);
+    }
+
+    #[test]
+    fn multihomed_hosts() {
+        let mask24 = "255.255.255.0";
+        let single = vec![interface("lo", "127.0.0.1", "255.0.0.0"),
+                          interface("eth0", "192.168.1.5", mask24),
+                          interface("eth0", "192.168.1.6", mask24),
+                          interface("eth0", "fe80::5", "ffff:ffff:ffff:ffff::")];
+        assert!(!multihomed(single.clone()));
+        assert!(!multihomed(Vec::new()));
+
+        let mut dual = single;
+        dual.push(interface("eth1", "10.0.0.5", "255.0.0.0"));
+        assert!(multihomed(dual));
+    }
+
+    #[test]
+    fn one_representative_per_family_per_interface() {
+        let temporary = InterfaceBuilder::new()
+            .name("eth0")
+            .addr(ip("2001:db8::aaaa"))
+            .addr_flags(AddrFlags::TEMPORARY)
+            .lifetimes(600, 3600)
+            .build();
+        let stable = InterfaceBuilder::new()
+            .name("eth0")
+            .addr(ip("2001:db8::1"))
+            .lifetimes(600, 3600)
+            .build();
+        let longer_lived = InterfaceBuilder::new()
+            .name("eth0")
+            .addr(ip("2001:db8::2"))
+            .lifetimes(600, 7200)
+            .build();
+        let ifaces = vec![InterfaceBuilder::new().name("eth0").addr(ip("169.254.3.3")).build(),
+                          InterfaceBuilder::new().name("eth0").addr(ip("192.168.1.5")).build(),
+                          temporary,
+                          stable,
+                          longer_lived.clone(),
+                          InterfaceBuilder::new().name("eth1").addr(ip("10.0.0.5")).build(),
+                          InterfaceBuilder::new().name("eth1").addr(ip("10.0.0.6")).build()];
+
+        let addrs: Vec<(String, IpAddr)> = representatives(ifaces)
+            .into_iter()
+            .map(|interface| (interface.name, interface.addr.ip()))
+            .collect();
+        assert_eq!(addrs,
+                   vec![("eth0".to_string(), ip("192.168.1.5")),
+                        ("eth0".to_string(), longer_lived.addr.ip()),
+                        ("eth1".to_string(), ip("10.0.0.5"))]);
+    }
+
+    #[test]
+    fn default_interfaces() {
+        let ifaces = vec![interface("lo", "127.0.0.1", "255.0.0.0"),
+                          interface("eth0", "192.168.1.5", "255.255.255.0")];
+        assert_eq!(with_ip(ifaces.clone(), v4(192, 168, 1, 5)), Some(ifaces[1].clone()));
+        assert_eq!(with_ip(ifaces, v4(10, 0, 0, 5)), None);
+
+        for &family in &[Family::V4, Family::V6] {
+            if let Some(interface) = unwrap!(default_interface(family)) {
+                assert_eq!(interface.addr.family(), family);
+                assert!(!interface.is_loopback());
+            }
+        }
+    }
+
+    #[test]
+    fn orders_of_a_mixed_set() {
+        let ifaces = vec![interface("eth0", "192.168.1.5", "255.255.255.0"),
+                          interface("wlan0", "2001:db8::6", "ffff:ffff:ffff:ffff::"),
+                          interface("eth0", "2001:db8::5", "ffff:ffff:ffff:ffff::"),
+                          interface("wlan0", "10.0.0.6", "255.255.255.0"),
+                          interface("eth0", "192.168.1.7", "255.255.255.0")];
+        let in_order = |order, indices: &[usize]| {
+            assert_eq!(ordered(ifaces.clone(), order),
+                       indices.iter().map(|&i| ifaces[i].clone()).collect::<Vec<_>>());
+        };
+
+        in_order(EnumOrder::AsReturned, &[0, 1, 2, 3, 4]);
+        in_order(EnumOrder::V4First, &[0, 3, 4, 1, 2]);
+        in_order(EnumOrder::V6First, &[1, 2, 0, 3, 4]);
+        in_order(EnumOrder::ByInterface, &[0, 2, 4, 1, 3]);
+    }
+
+    #[test]
+    fn stun_sources_of_a_dual_stack_host() {
+        let v6_netmask = "ffff:ffff:ffff:ffff::";
+        let ifaces = vec![interface("lo", "127.0.0.1", "255.0.0.0"),
+                          interface("wlan0", "10.0.0.5", "255.255.255.0"),
+                          interface("eth0", "192.168.1.5", "255.255.255.0"),
+                          interface("eth0", "fe80::5", v6_netmask),
+                          interface("eth0", "2001:db8::5", v6_netmask)];
+        let gateways = vec![(v4(192, 168, 1, 1), "eth0".to_owned()),
+                            (ip("fe80::1"), "eth0".to_owned())];
+
+        // The private address on the way to the gateway, as there is no global one.
+        assert_eq!(stun(ifaces.clone(), &gateways, Family::V4), Some(ifaces[2].clone()));
+        assert_eq!(stun(ifaces.clone(), &[], Family::V4), None);
+        assert_eq!(stun(ifaces.clone(), &gateways, Family::V6), Some(ifaces[4].clone()));
+        // Link-local addresses don't count, even on the way to the gateway.
+        assert_eq!(stun(ifaces[..4].to_vec(), &gateways, Family::V6), None);
+
+        let mut with_global_v4 = ifaces.clone();
+        with_global_v4.push(interface("ppp0", "203.0.113.5", "255.255.255.255"));
+        assert_eq!(stun(with_global_v4.clone(), &gateways, Family::V4),
+                   Some(with_global_v4[5].clone()));
+    }
+
+    #[test]
+    fn recommended_on_a_typical_host() {
+        let ifaces = vec![interface("lo", "127.0.0.1", "255.0.0.0"),
+                          interface("lo", "::1", "ffff:ffff:ffff:ffff::"),
+                          interface("eth0", "192.168.1.5", "255.255.255.0"),
+                          interface("eth0", "fe80::5", "ffff:ffff:ffff:ffff::"),
+                          interface("eth0", "2001:db8::5", "ffff:ffff:ffff:ffff::"),
+                          interface("eth0", "169.254.3.3", "255.255.0.0"),
+                          interface("docker0", "172.17.0.1", "255.255.0.0"),
+                          interface("wlan0", "10.0.0.5", "255.255.255.0"),
+                          interface("wlan0", "10.0.0.6", "255.255.255.0")];
+
+        assert_eq!(recommended(ifaces, 5483),
+                   vec![unwrap!(SocketAddr::from_str("[2001:db8::5]:5483")),
+                        unwrap!(SocketAddr::from_str("192.168.1.5:5483")),
+                        unwrap!(SocketAddr::from_str("10.0.0.5:5483"))]);
+    }
+}
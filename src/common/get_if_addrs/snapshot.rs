@@ -0,0 +1,117 @@
+// Snapshots of the interfaces on this machine kept in a file, for a daemon to tell what changed
+// since its last run, across reboots too. Snapshots are in the format of `pack()`.
+
+use std::ffi::OsString;
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+
+use common::get_if_addrs::{Interface, get_if_addrs, pack, unpack};
+
+/// Save the interfaces `get_if_addrs()` returns to the file at `path`, replacing any previous
+/// snapshot, with the details `pack()` keeps.
+///
+/// The snapshot is written to `<path>.tmp` first and then renamed over `path`, so that should the
+/// process be interrupted, `path` still holds the whole previous snapshot rather than part of the
+/// new one.
+pub fn save_snapshot(path: &Path) -> io::Result<()> {
+    save(&try!(get_if_addrs()), path)
+}
+
+/// Load the interfaces `save_snapshot()` saved to the file at `path`. Fails with
+/// `io::ErrorKind::InvalidData` if the file isn't a snapshot.
+pub fn load_snapshot(path: &Path) -> io::Result<Vec<Interface>> {
+    let mut buf = Vec::new();
+    let _ = try!(try!(File::open(path)).read_to_end(&mut buf));
+    unpack(&buf)
+}
+
+fn save(ifaces: &[Interface], path: &Path) -> io::Result<()> {
+    let tmp_path = tmp_path_of(path);
+    {
+        let mut file = try!(File::create(&tmp_path));
+        try!(file.write_all(&pack(ifaces)));
+        // Otherwise a crash right after the rename could leave `path` empty.
+        try!(file.sync_all());
+    }
+    fs::rename(&tmp_path, path)
+}
+
+// Where a snapshot is written before it is renamed to `path`, beside it so that both are on the
+// same file system, which renaming needs.
+fn tmp_path_of(path: &Path) -> PathBuf {
+    let mut tmp_path = OsString::from(path.as_os_str());
+    tmp_path.push(".tmp");
+    PathBuf::from(tmp_path)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use common::get_if_addrs::{IfFlags, test_util};
+
+    fn interface(name: &str, ip: &str, netmask: &str) -> Interface {
+        Interface {
+            index: 2,
+            flags: IfFlags::UP | IfFlags::RUNNING,
+            ..test_util::interface(name, ip, netmask)
+        }
+    }
+
+    fn mock_dir() -> PathBuf {
+        let dir = test_util::temp_path("snapshot");
+        unwrap!(fs::create_dir_all(&dir));
+        dir
+    }
+
+    #[test]
+    fn round_trip() {
+        let dir = mock_dir();
+        let path = dir.join("interfaces");
+        let ifaces = vec![interface("eth0", "192.168.1.5", "255.255.255.0"),
+                          interface("eth0", "2001:db8::5", "ffff:ffff:ffff:ffff::")];
+
+        unwrap!(save(&ifaces, &path));
+        assert_eq!(unwrap!(load_snapshot(&path)), ifaces);
+        assert!(!tmp_path_of(&path).exists());
+
+        // A later snapshot replaces the earlier one.
+        unwrap!(save(&ifaces[..1], &path));
+        assert_eq!(unwrap!(load_snapshot(&path)), &ifaces[..1]);
+
+        unwrap!(save_snapshot(&path));
+        assert_eq!(unwrap!(load_snapshot(&path)).len(), unwrap!(get_if_addrs()).len());
+
+        unwrap!(fs::remove_dir_all(dir));
+    }
+
+    #[test]
+    fn interrupted_saves_keep_the_previous_snapshot() {
+        let dir = mock_dir();
+        let path = dir.join("interfaces");
+        let ifaces = vec![interface("eth0", "192.168.1.5", "255.255.255.0")];
+        unwrap!(save(&ifaces, &path));
+
+        // What a save interrupted before the rename leaves behind: part of a new snapshot.
+        unwrap!(unwrap!(File::create(tmp_path_of(&path))).write_all(&[6, 0x20, 0x01]));
+        assert_eq!(unwrap!(load_snapshot(&path)), ifaces);
+        // Which the next save overwrites.
+        let later = vec![interface("wlan0", "10.0.0.5", "255.0.0.0")];
+        unwrap!(save(&later, &path));
+        assert_eq!(unwrap!(load_snapshot(&path)), later);
+
+        // A save failing to write leaves the previous snapshot alone.
+        unwrap!(fs::create_dir(tmp_path_of(&path)));
+        assert!(save(&ifaces, &path).is_err());
+        assert_eq!(unwrap!(load_snapshot(&path)), later);
+
+        unwrap!(fs::write(&path, &[5]));
+        match load_snapshot(&path) {
+            Err(ref e) if e.kind() == io::ErrorKind::InvalidData => (),
+            result => panic!("Unexpected {:?}", result),
+        }
+
+        unwrap!(fs::remove_dir_all(dir));
+    }
+}
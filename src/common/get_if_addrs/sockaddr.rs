@@ -0,0 +1,51 @@
+// The addresses held by the raw `in_addr` and `in6_addr` of socket addresses, shared by the
+// platform enumerations. Both hold the bytes of the address in network order in memory whatever
+// the byte order of the platform, so they are copied out as they are rather than shifted.
+
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+/// The address an `in_addr` holds, given its `s_addr` (`S_un` on Windows).
+pub fn ipv4_from_in_addr(s_addr: u32) -> Ipv4Addr {
+    Ipv4Addr::from(s_addr.to_ne_bytes())
+}
+
+/// The address an `in6_addr` holds, given its `s6_addr`.
+pub fn ipv6_from_in6_addr(s6_addr: [u8; 16]) -> Ipv6Addr {
+    Ipv6Addr::from(s6_addr)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn raw_ipv4_addresses() {
+        for &(raw, ip) in &[([192, 168, 1, 5], Ipv4Addr::new(192, 168, 1, 5)),
+                            ([127, 0, 0, 1], Ipv4Addr::new(127, 0, 0, 1)),
+                            ([255, 255, 255, 0], Ipv4Addr::new(255, 255, 255, 0)),
+                            ([10, 0, 0, 255], Ipv4Addr::new(10, 0, 0, 255))] {
+            // How the bytes of an `in_addr` read as its `u32` on this platform.
+            assert_eq!(ipv4_from_in_addr(u32::from_ne_bytes(raw)), ip);
+        }
+        // Which on a little-endian platform puts the first byte last.
+        if cfg!(target_endian = "little") {
+            assert_eq!(ipv4_from_in_addr(0x0501a8c0), Ipv4Addr::new(192, 168, 1, 5));
+        } else {
+            assert_eq!(ipv4_from_in_addr(0xc0a80105), Ipv4Addr::new(192, 168, 1, 5));
+        }
+    }
+
+    #[test]
+    fn raw_ipv6_addresses() {
+        let mut raw = [0u8; 16];
+        raw[..4].copy_from_slice(&[0x20, 0x01, 0x0d, 0xb8]);
+        raw[15] = 5;
+        assert_eq!(ipv6_from_in6_addr(raw), Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 5));
+        let mut loopback = [0u8; 16];
+        loopback[15] = 1;
+        assert_eq!(ipv6_from_in6_addr(loopback), Ipv6Addr::new(0, 0, 0, 0, 0, 0, 0, 1));
+        assert_eq!(ipv6_from_in6_addr([0xfe, 0x80, 0, 0, 0, 0, 0, 0, 0x52, 0x54, 0, 0xff, 0xfe,
+                                       0x12, 0x34, 0x56]),
+                   Ipv6Addr::new(0xfe80, 0, 0, 0, 0x5254, 0xff, 0xfe12, 0x3456));
+    }
+}
@@ -0,0 +1,243 @@
+// Source address selection per RFC 6724: ranking the addresses of this machine by how suitable
+// they are for reaching a given destination. The rules which need more than the interfaces know
+// about themselves (home addresses, the outgoing interface and the prefixes next hops advertise)
+// are left out.
+
+use std::cmp::Reverse;
+use std::io;
+use std::net::IpAddr;
+
+use common::get_if_addrs::{AddrFlags, Interface, get_if_addrs_detailed_with_link_local};
+use common::get_if_addrs::classify::is_unique_local;
+
+/// Get the addresses on this machine of the same family as `dest`, ordered by the RFC 6724 source
+/// address selection rules from the most to the least suitable for reaching `dest`, e.g. for
+/// binding before connecting to a specific peer.
+///
+/// In order, addresses are preferred which are `dest` itself, are of the narrowest scope at least
+/// as wide as that of `dest`, are not deprecated, have the same label as `dest` in the default
+/// policy table, are temporary, and share the longest prefix with `dest`. Addresses ranking the
+/// same keep the order they are enumerated in. Link-local addresses are among them, as they are
+/// the ones to reach link-local destinations from.
+pub fn ordered_source_addrs(dest: &IpAddr) -> io::Result<Vec<IpAddr>> {
+    Ok(ordered_sources(try!(get_if_addrs_detailed_with_link_local()), dest))
+}
+
+fn ordered_sources(ifaces: Vec<Interface>, dest: &IpAddr) -> Vec<IpAddr> {
+    let mut candidates: Vec<Interface> = ifaces.into_iter()
+        .filter(|interface| interface.addr.ip().is_ipv4() == dest.is_ipv4())
+        .collect();
+    // The sort is stable, so addresses ranking the same keep their order.
+    candidates.sort_by_key(|interface| {
+        let ip = interface.addr.ip();
+        let flags = interface.addr_flags;
+        (ip != *dest,
+         scope_preference(scope(&ip), scope(dest)),
+         flags.contains(AddrFlags::DEPRECATED),
+         label(&ip) != label(dest),
+         !flags.contains(AddrFlags::TEMPORARY),
+         Reverse(common_prefix_len(&ip, dest, interface.addr.prefix_len())))
+    });
+    candidates.into_iter().map(|interface| interface.addr.ip()).collect()
+}
+
+// Rule 2, as a key sorting the preferred scopes first: the narrowest scope wide enough for the
+// destination, or failing any, the widest of those which are too narrow.
+fn scope_preference(source: u8, dest: u8) -> (bool, i16) {
+    if source >= dest {
+        (false, i16::from(source))
+    } else {
+        (true, -i16::from(source))
+    }
+}
+
+// The scope of `ip` as RFC 6724 defines it, using the values of the multicast scopes: 2 for
+// link-local, 5 for site-local and 0xe for global. IPv4 loopback and link-local addresses have
+// link-local scope, as does the IPv6 loopback address.
+fn scope(ip: &IpAddr) -> u8 {
+    match *ip {
+        IpAddr::V4(ip) => {
+            if ip.is_loopback() || ip.is_link_local() {
+                0x2
+            } else {
+                0xe
+            }
+        }
+        IpAddr::V6(ip) => {
+            let first = ip.segments()[0];
+            if first & 0xff00 == 0xff00 {
+                (first & 0xf) as u8
+            } else if ip.is_loopback() || first & 0xffc0 == 0xfe80 {
+                0x2
+            } else if first & 0xffc0 == 0xfec0 {
+                0x5
+            } else {
+                0xe
+            }
+        }
+    }
+}
+
+// The label of `ip` in the default policy table of RFC 6724, which is that of the longest prefix
+// of the table matching its IPv6 form.
+fn label(ip: &IpAddr) -> u8 {
+    let ip = match *ip {
+        IpAddr::V4(ip) => ip.to_ipv6_mapped(),
+        IpAddr::V6(ip) => ip,
+    };
+    let segments = ip.segments();
+    if ip.is_loopback() {
+        0
+    } else if segments[..5] == [0; 5] && segments[5] == 0xffff {
+        // `::ffff:0:0/96`, the IPv4-mapped addresses.
+        4
+    } else if segments[..6] == [0; 6] {
+        // `::/96`, the IPv4-compatible addresses.
+        3
+    } else if segments[0] == 0x2001 && segments[1] == 0 {
+        // `2001::/32`, Teredo.
+        5
+    } else if segments[0] == 0x2002 {
+        // `2002::/16`, 6to4.
+        2
+    } else if segments[0] == 0x3ffe {
+        // `3ffe::/16`, the former 6bone.
+        12
+    } else if segments[0] & 0xffc0 == 0xfec0 {
+        11
+    } else if is_unique_local(&ip) {
+        13
+    } else {
+        1
+    }
+}
+
+// The number of leading bits `source` and `dest` have in common, up to the length of the prefix
+// of `source` where it is known.
+fn common_prefix_len(source: &IpAddr, dest: &IpAddr, prefix_len: Option<u8>) -> u32 {
+    let common = match (*source, *dest) {
+        (IpAddr::V4(source), IpAddr::V4(dest)) => {
+            (u32::from(source) ^ u32::from(dest)).leading_zeros()
+        }
+        (IpAddr::V6(source), IpAddr::V6(dest)) => {
+            (u128::from(source) ^ u128::from(dest)).leading_zeros()
+        }
+        _ => 0,
+    };
+    match prefix_len {
+        Some(prefix_len) => common.min(u32::from(prefix_len)),
+        None => common,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::net::{Ipv4Addr, Ipv6Addr};
+    use std::str::FromStr;
+
+    use common::get_if_addrs::Routability;
+    use common::get_if_addrs::test_util::interface;
+
+    fn ip(ip: &str) -> IpAddr {
+        unwrap!(IpAddr::from_str(ip))
+    }
+
+    // An address of `eth0` written as `ip/prefix_len`.
+    fn source(ip_and_prefix: &str, addr_flags: AddrFlags) -> Interface {
+        let mut parts = ip_and_prefix.split('/');
+        let addr = unwrap!(parts.next());
+        let prefix_len: u32 = unwrap!(unwrap!(parts.next()).parse());
+        let netmask = match ip(addr) {
+            IpAddr::V4(_) => {
+                IpAddr::V4(Ipv4Addr::from((!0u32).checked_shl(32 - prefix_len).unwrap_or(0)))
+            }
+            IpAddr::V6(_) => {
+                IpAddr::V6(Ipv6Addr::from((!0u128).checked_shl(128 - prefix_len).unwrap_or(0)))
+            }
+        };
+        Interface { addr_flags: addr_flags, ..interface("eth0", addr, &netmask.to_string()) }
+    }
+
+    fn ordered(ifaces: &[(&str, AddrFlags)], dest: &str) -> Vec<IpAddr> {
+        let ifaces = ifaces.iter().map(|&(addr, flags)| source(addr, flags)).collect();
+        ordered_sources(ifaces, &ip(dest))
+    }
+
+    #[test]
+    fn longest_matching_prefix_wins() {
+        let none = AddrFlags::empty();
+        assert_eq!(ordered(&[("192.168.1.5/24", none),
+                             ("10.0.0.5/8", none),
+                             ("192.168.2.5/24", none)],
+                           "192.168.2.9"),
+                   vec![ip("192.168.2.5"), ip("192.168.1.5"), ip("10.0.0.5")]);
+        // Only up to the prefix of the source, so the interface identifiers don't count.
+        assert_eq!(ordered(&[("2001:db8:1::1/64", none), ("2001:db8:1::ffff/64", none)],
+                           "2001:db8:1::ff00"),
+                   vec![ip("2001:db8:1::1"), ip("2001:db8:1::ffff")]);
+    }
+
+    #[test]
+    fn deprecated_last() {
+        let none = AddrFlags::empty();
+        assert_eq!(ordered(&[("2001:db8:1::5/64", AddrFlags::DEPRECATED),
+                             ("2001:db8:2::5/64", none)],
+                           "2001:db8:1::9"),
+                   vec![ip("2001:db8:2::5"), ip("2001:db8:1::5")]);
+    }
+
+    #[test]
+    fn ordering_rules() {
+        let none = AddrFlags::empty();
+        // The destination itself comes first, and other families are left out.
+        assert_eq!(ordered(&[("2001:db8::1/64", none),
+                             ("192.168.1.5/24", none),
+                             ("2001:db8::2/64", none)],
+                           "2001:db8::2"),
+                   vec![ip("2001:db8::2"), ip("2001:db8::1")]);
+        // Link-local sources for link-local destinations, global ones for global destinations.
+        let ifaces = [("fe80::5/64", none), ("2001:db8::5/64", none)];
+        assert_eq!(ordered(&ifaces, "fe80::9"), vec![ip("fe80::5"), ip("2001:db8::5")]);
+        assert_eq!(ordered(&ifaces, "2001:db9::9"), vec![ip("2001:db8::5"), ip("fe80::5")]);
+        // Unique local sources for unique local destinations, by their label.
+        assert_eq!(ordered(&[("2001:db8::5/64", none), ("fd00::5/64", none)], "fd12::9"),
+                   vec![ip("fd00::5"), ip("2001:db8::5")]);
+        // Temporary addresses over stable ones.
+        assert_eq!(ordered(&[("2001:db8::5/64", none), ("2001:db8::6/64", AddrFlags::TEMPORARY)],
+                           "2001:db9::9"),
+                   vec![ip("2001:db8::6"), ip("2001:db8::5")]);
+    }
+
+    #[test]
+    fn sources_of_this_machine() {
+        let loopback = ip("127.0.0.1");
+        assert_eq!(unwrap!(ordered_source_addrs(&loopback)).first(), Some(&loopback));
+
+        let link_local = unwrap!(get_if_addrs_detailed_with_link_local())
+            .into_iter()
+            .filter(|interface| interface.addr.routability() == Routability::LinkLocal)
+            .map(|interface| interface.addr.ip());
+        for ip in link_local {
+            assert_eq!(unwrap!(ordered_source_addrs(&ip)).first(), Some(&ip));
+        }
+    }
+
+    #[test]
+    fn scopes_and_labels() {
+        assert_eq!(scope(&ip("127.0.0.1")), 0x2);
+        assert_eq!(scope(&ip("169.254.1.1")), 0x2);
+        assert_eq!(scope(&ip("192.168.1.5")), 0xe);
+        assert_eq!(scope(&ip("::1")), 0x2);
+        assert_eq!(scope(&ip("fec0::1")), 0x5);
+        assert_eq!(scope(&ip("ff05::2")), 0x5);
+        assert_eq!(scope(&ip("fd00::1")), 0xe);
+        assert_eq!(label(&ip("::1")), 0);
+        assert_eq!(label(&ip("192.168.1.5")), 4);
+        assert_eq!(label(&ip("2001::1")), 5);
+        assert_eq!(label(&ip("2001:db8::1")), 1);
+        assert_eq!(label(&ip("2002:c000:205::1")), 2);
+        assert_eq!(label(&ip("fd00::1")), 13);
+    }
+}
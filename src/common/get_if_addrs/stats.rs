@@ -0,0 +1,337 @@
+// Traffic counters of the interfaces on this machine. Linux exposes them as text in
+// `/proc/net/dev`, which is parsed from any reader so tests can feed it samples, the BSDs as the
+// `struct if_data` of the link-level entries of `getifaddrs` and Windows through `GetIfEntry2`.
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+use std::io::BufRead;
+
+/// How much traffic an interface has seen since it was brought up.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Default)]
+pub struct IfStats {
+    /// The number of bytes received.
+    pub rx_bytes: u64,
+    /// The number of bytes sent.
+    pub tx_bytes: u64,
+    /// The number of packets received.
+    pub rx_packets: u64,
+    /// The number of packets sent.
+    pub tx_packets: u64,
+}
+
+/// Get the traffic counters of the interface `name`, as named by `get_if_addrs()`, or `None` if
+/// there is no such interface.
+///
+/// Reading them is only implemented on Linux, Android, FreeBSD, macOS, iOS and Windows: elsewhere
+/// this is always `None`.
+pub fn interface_stats(name: &str) -> Option<IfStats> {
+    read_stats(name)
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn read_stats(name: &str) -> Option<IfStats> {
+    use std::fs::File;
+    use std::io::BufReader;
+
+    match File::open("/proc/net/dev") {
+        Ok(file) => parse_proc_net_dev(BufReader::new(file), name),
+        Err(e) => {
+            warn!("Could not read /proc/net/dev: {:?}", e);
+            None
+        }
+    }
+}
+
+#[cfg(any(target_os = "freebsd", target_os = "macos", target_os = "ios"))]
+fn read_stats(name: &str) -> Option<IfStats> {
+    match bsd::link_stats(name) {
+        Ok(stats) => stats,
+        Err(e) => {
+            warn!("Could not enumerate interfaces: {:?}", e);
+            None
+        }
+    }
+}
+
+#[cfg(windows)]
+fn read_stats(name: &str) -> Option<IfStats> {
+    use common::get_if_addrs::get_if_addrs_all;
+
+    let index = match get_if_addrs_all() {
+        Ok(ifaces) => {
+            match ifaces.into_iter().find(|interface| interface.name == name) {
+                Some(interface) => interface.index,
+                None => return None,
+            }
+        }
+        Err(e) => {
+            warn!("Could not enumerate interfaces: {:?}", e);
+            return None;
+        }
+    };
+    windows::if_entry_stats(index)
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android", target_os = "freebsd",
+              target_os = "macos", target_os = "ios", windows)))]
+fn read_stats(_name: &str) -> Option<IfStats> {
+    None
+}
+
+// Find the counters of `name` in the contents of `/proc/net/dev`, which after two header lines
+// has a line like `  eth0: 1230 18 0 0 0 0 0 0 2239 33 0 0 0 0 0 0` per interface.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn parse_proc_net_dev<R: BufRead>(reader: R, name: &str) -> Option<IfStats> {
+    // Aliases such as `eth0:0` share the counters of the interface before the colon.
+    let name = name.split(':').next().unwrap_or("");
+    for line in reader.lines().skip(2) {
+        let line = match line {
+            Ok(line) => line,
+            Err(_) => return None,
+        };
+        let mut parts = line.splitn(2, ':');
+        if parts.next().map(str::trim) != Some(name) {
+            continue;
+        }
+        let counters: Vec<u64> = match parts.next()
+            .unwrap_or("")
+            .split_whitespace()
+            .map(str::parse)
+            .collect() {
+            Ok(counters) => counters,
+            Err(_) => return None,
+        };
+        if counters.len() < 10 {
+            return None;
+        }
+        return Some(IfStats {
+            rx_bytes: counters[0],
+            rx_packets: counters[1],
+            tx_bytes: counters[8],
+            tx_packets: counters[9],
+        });
+    }
+    None
+}
+
+#[cfg(any(target_os = "freebsd", target_os = "macos", target_os = "ios"))]
+mod bsd {
+    use std::ffi::CStr;
+    use std::{io, ptr};
+
+    use c_linked_list::CLinkedListMut;
+    use libc::{self, AF_LINK};
+
+    use super::IfStats;
+
+    // The leading fields of `struct if_data`, up to the counters read here. macOS and iOS only
+    // have 32-bit counters in it, which wrap around.
+    #[cfg(any(target_os = "macos", target_os = "ios"))]
+    #[repr(C)]
+    struct IfData {
+        ifi_type: u8,
+        ifi_typelen: u8,
+        ifi_physical: u8,
+        ifi_addrlen: u8,
+        ifi_hdrlen: u8,
+        ifi_recvquota: u8,
+        ifi_xmitquota: u8,
+        ifi_unused1: u8,
+        ifi_mtu: u32,
+        ifi_metric: u32,
+        ifi_baudrate: u32,
+        ifi_ipackets: u32,
+        ifi_ierrors: u32,
+        ifi_opackets: u32,
+        ifi_oerrors: u32,
+        ifi_collisions: u32,
+        ifi_ibytes: u32,
+        ifi_obytes: u32,
+    }
+
+    // As of FreeBSD 11.
+    #[cfg(target_os = "freebsd")]
+    #[repr(C)]
+    struct IfData {
+        ifi_type: u8,
+        ifi_physical: u8,
+        ifi_addrlen: u8,
+        ifi_hdrlen: u8,
+        ifi_link_state: u8,
+        ifi_vhid: u8,
+        ifi_datalen: u16,
+        ifi_mtu: u32,
+        ifi_metric: u32,
+        ifi_baudrate: u64,
+        ifi_ipackets: u64,
+        ifi_ierrors: u64,
+        ifi_opackets: u64,
+        ifi_oerrors: u64,
+        ifi_collisions: u64,
+        ifi_ibytes: u64,
+        ifi_obytes: u64,
+    }
+
+    // The counters of the link-level entry `getifaddrs()` lists for the interface `name`, if any.
+    // Each interface has exactly one, whose `ifa_data` points at its `struct if_data`.
+    #[allow(unsafe_code, trivial_casts)]
+    pub fn link_stats(name: &str) -> io::Result<Option<IfStats>> {
+        let mut ifaddrs: *mut libc::ifaddrs = ptr::null_mut();
+        if -1 == unsafe { libc::getifaddrs(&mut ifaddrs) } {
+            return Err(io::Error::last_os_error());
+        }
+        if ifaddrs.is_null() {
+            return Ok(None);
+        }
+
+        let stats = unsafe { CLinkedListMut::from_ptr(ifaddrs, |a| a.ifa_next) }
+            .iter()
+            .filter(|ifaddr| !ifaddr.ifa_addr.is_null() && !ifaddr.ifa_data.is_null())
+            .filter(|ifaddr| unsafe { (*ifaddr.ifa_addr).sa_family } as i32 == AF_LINK)
+            .find(|ifaddr| {
+                !ifaddr.ifa_name.is_null() &&
+                unsafe { CStr::from_ptr(ifaddr.ifa_name) }.to_bytes() == name.as_bytes()
+            })
+            .map(|ifaddr| {
+                let data = unsafe { &*(ifaddr.ifa_data as *const IfData) };
+                IfStats {
+                    rx_bytes: u64::from(data.ifi_ibytes),
+                    tx_bytes: u64::from(data.ifi_obytes),
+                    rx_packets: u64::from(data.ifi_ipackets),
+                    tx_packets: u64::from(data.ifi_opackets),
+                }
+            });
+        unsafe { libc::freeifaddrs(ifaddrs) };
+        Ok(stats)
+    }
+}
+
+#[cfg(windows)]
+mod windows {
+    use std::mem;
+
+    use libc::c_int;
+    use winapi::DWORD;
+
+    use super::IfStats;
+
+    // `MIB_IF_ROW2`.
+    #[repr(C)]
+    struct MibIfRow2 {
+        interface_luid: u64,
+        interface_index: DWORD,
+        interface_guid: [u8; 16],
+        alias: [u16; 257],
+        description: [u16; 257],
+        physical_address_length: DWORD,
+        physical_address: [u8; 32],
+        permanent_physical_address: [u8; 32],
+        mtu: DWORD,
+        if_type: DWORD,
+        tunnel_type: c_int,
+        media_type: c_int,
+        physical_medium_type: c_int,
+        access_type: c_int,
+        direction_type: c_int,
+        interface_and_oper_status_flags: u8,
+        oper_status: c_int,
+        admin_status: c_int,
+        media_connect_state: c_int,
+        network_guid: [u8; 16],
+        connection_type: c_int,
+        transmit_link_speed: u64,
+        receive_link_speed: u64,
+        in_octets: u64,
+        in_ucast_pkts: u64,
+        in_n_ucast_pkts: u64,
+        in_discards: u64,
+        in_errors: u64,
+        in_unknown_protos: u64,
+        in_ucast_octets: u64,
+        in_multicast_octets: u64,
+        in_broadcast_octets: u64,
+        out_octets: u64,
+        out_ucast_pkts: u64,
+        out_n_ucast_pkts: u64,
+        out_discards: u64,
+        out_errors: u64,
+        out_ucast_octets: u64,
+        out_multicast_octets: u64,
+        out_broadcast_octets: u64,
+        out_q_len: u64,
+    }
+
+    #[link(name="Iphlpapi")]
+    extern "system" {
+        fn GetIfEntry2(row: *mut MibIfRow2) -> DWORD;
+    }
+
+    #[allow(unsafe_code)]
+    pub fn if_entry_stats(index: u32) -> Option<IfStats> {
+        // `GetIfEntry2` looks the interface up by its LUID if that is set, by its index otherwise.
+        let mut row: MibIfRow2 = unsafe { mem::zeroed() };
+        row.interface_index = index;
+        match unsafe { GetIfEntry2(&mut row) } {
+            0 => {
+                Some(IfStats {
+                    rx_bytes: row.in_octets,
+                    tx_bytes: row.out_octets,
+                    rx_packets: row.in_ucast_pkts + row.in_n_ucast_pkts,
+                    tx_packets: row.out_ucast_pkts + row.out_n_ucast_pkts,
+                })
+            }
+            code => {
+                warn!("GetIfEntry2 failed for interface {}: {}", index, code);
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    #[test]
+    fn parses_proc_net_dev() {
+        let sample = "\
+Inter-|   Receive                                                |  Transmit
+ face |bytes    packets errs drop fifo frame compressed multicast|bytes    packets errs drop \
+                      fifo colls carrier compressed
+    lo: 48796089    4446    0    0    0     0          0         0 48796089    4446    0    0 \
+                      0     0       0          0
+  eth0:1230      18    0    0    0     0          0         0     2239      33    0    0    0 \
+                      0       0          0
+";
+        assert_eq!(parse_proc_net_dev(sample.as_bytes(), "eth0"),
+                   Some(IfStats {
+                       rx_bytes: 1230,
+                       tx_bytes: 2239,
+                       rx_packets: 18,
+                       tx_packets: 33,
+                   }));
+        assert_eq!(parse_proc_net_dev(sample.as_bytes(), "eth0:0"),
+                   parse_proc_net_dev(sample.as_bytes(), "eth0"));
+        assert_eq!(parse_proc_net_dev(sample.as_bytes(), "lo").map(|stats| stats.rx_packets),
+                   Some(4446));
+        assert_eq!(parse_proc_net_dev(sample.as_bytes(), "eth1"), None);
+        assert_eq!(parse_proc_net_dev(&b"header\nheader\n  eth0: 1 2 x\n"[..], "eth0"), None);
+    }
+
+    #[test]
+    fn stats_of_loopback() {
+        use common::get_if_addrs::get_if_addrs;
+
+        let ifaces = unwrap!(get_if_addrs());
+        let loopback = unwrap!(ifaces.iter().find(|interface| interface.is_loopback()));
+        if cfg!(any(target_os = "linux",
+                    target_os = "android",
+                    target_os = "freebsd",
+                    target_os = "macos",
+                    target_os = "ios",
+                    windows)) {
+            assert!(interface_stats(&loopback.name).is_some());
+        }
+        assert_eq!(interface_stats("no-such-interface"), None);
+    }
+}
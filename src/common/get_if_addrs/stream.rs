@@ -0,0 +1,191 @@
+// The interfaces on this machine as an async stream of snapshots, for async code which rebinds
+// whenever they change. Like `get_if_addrs_async()` this doesn't depend on any executor: a thread
+// of its own waits for changes and wakes the stream up. On Linux it waits for the notifications
+// netlink sends about changes, elsewhere it re-enumerates periodically.
+
+use std::io;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+use std::thread;
+use std::time::Duration;
+
+use futures_core::stream::Stream;
+
+use common::get_if_addrs::{Interface, get_if_addrs};
+#[cfg(any(target_os = "linux", target_os = "android"))]
+use common::get_if_addrs::netlink::ChangeMonitor;
+
+/// How often the thread behind `interface_stream()` checks whether the stream was dropped, and
+/// where netlink isn't available, how often it re-enumerates the interfaces.
+pub const STREAM_POLL_MS: u64 = 500;
+
+/// The stream returned by `interface_stream()`.
+pub struct InterfaceStream {
+    shared: Arc<Mutex<Shared>>,
+}
+
+struct Shared {
+    // The latest snapshot not yet yielded. Snapshots which are superseded before being yielded
+    // are skipped, as only the current interfaces are of interest.
+    pending: Option<Vec<Interface>>,
+    waker: Option<Waker>,
+    dropped: bool,
+}
+
+/// Get a stream of the interfaces on this machine, as `get_if_addrs()` enumerates them, yielding
+/// them first as they are now and then again each time they change, e.g. for rebinding with
+/// `while let Some(ifaces) = stream.next().await`.
+///
+/// A consumer slower than the changes only gets the latest interfaces. Enumerations which fail
+/// are skipped, and the stream never ends.
+pub fn interface_stream() -> io::Result<InterfaceStream> {
+    start_stream()
+}
+
+// Re-enumerate whenever netlink notifies about a change.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn start_stream() -> io::Result<InterfaceStream> {
+    let monitor = try!(ChangeMonitor::new());
+    let timeout = Duration::from_millis(STREAM_POLL_MS);
+    InterfaceStream::start_with(get_if_addrs, move || match monitor.wait(timeout) {
+        Ok(changed) => changed,
+        Err(e) => {
+            warn!("Could not wait for interface changes: {:?}", e);
+            thread::sleep(timeout);
+            true
+        }
+    })
+}
+
+// Re-enumerate periodically, as there are no notifications to wait for.
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+fn start_stream() -> io::Result<InterfaceStream> {
+    InterfaceStream::start_with(get_if_addrs, || {
+        thread::sleep(Duration::from_millis(STREAM_POLL_MS));
+        true
+    })
+}
+
+impl InterfaceStream {
+    // Enumerate the interfaces with `poll` whenever `wait`, which mustn't block for longer than
+    // `STREAM_POLL_MS`, returns that they may have changed.
+    fn start_with<F, W>(mut poll: F, mut wait: W) -> io::Result<InterfaceStream>
+        where F: FnMut() -> io::Result<Vec<Interface>> + Send + 'static,
+              W: FnMut() -> bool + Send + 'static
+    {
+        let mut previous = try!(poll());
+        let shared = Arc::new(Mutex::new(Shared {
+            pending: Some(previous.clone()),
+            waker: None,
+            dropped: false,
+        }));
+
+        let shared_clone = shared.clone();
+        let _ = try!(thread::Builder::new().name("InterfaceStream".to_string()).spawn(move || {
+            loop {
+                let changed = wait();
+                if unwrap!(shared_clone.lock()).dropped {
+                    return;
+                }
+                if !changed {
+                    continue;
+                }
+                let current = match poll() {
+                    Ok(current) => current,
+                    Err(e) => {
+                        warn!("Could not enumerate interfaces: {:?}", e);
+                        continue;
+                    }
+                };
+                if current == previous {
+                    continue;
+                }
+                let mut shared = unwrap!(shared_clone.lock());
+                shared.pending = Some(current.clone());
+                if let Some(waker) = shared.waker.take() {
+                    waker.wake();
+                }
+                previous = current;
+            }
+        }));
+
+        Ok(InterfaceStream { shared: shared })
+    }
+}
+
+impl Stream for InterfaceStream {
+    type Item = Vec<Interface>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Vec<Interface>>> {
+        let mut shared = unwrap!(self.shared.lock());
+        match shared.pending.take() {
+            Some(ifaces) => Poll::Ready(Some(ifaces)),
+            None => {
+                shared.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+impl Drop for InterfaceStream {
+    fn drop(&mut self) {
+        // The thread notices the next time it is done waiting, so dropping doesn't block.
+        unwrap!(self.shared.lock()).dropped = true;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::net::{IpAddr, Ipv4Addr};
+    use std::sync::mpsc;
+
+    use common::get_if_addrs::InterfaceBuilder;
+    use common::get_if_addrs::test_util::{block_on, poll_fn};
+
+    fn next(stream: &mut InterfaceStream) -> Option<Vec<Interface>> {
+        block_on(poll_fn(|cx| Pin::new(&mut *stream).poll_next(cx)))
+    }
+
+    fn interface(last_octet: u8) -> Interface {
+        InterfaceBuilder::new()
+            .name("eth0")
+            .addr(IpAddr::V4(Ipv4Addr::new(192, 168, 1, last_octet)))
+            .build()
+    }
+
+    #[test]
+    fn yields_a_snapshot_per_change() {
+        // Each enumeration takes the next of these, repeating the last one.
+        let snapshots = vec![vec![interface(2)],
+                             vec![interface(2)],
+                             vec![interface(2), interface(4)],
+                             vec![interface(4)]];
+        let (change_tx, change_rx) = mpsc::channel::<()>();
+        let mut polls = 0;
+        let provider_snapshots = snapshots.clone();
+        let provider = move || {
+            polls += 1;
+            Ok(provider_snapshots[polls.min(provider_snapshots.len()) - 1].clone())
+        };
+        let wait = move || change_rx.recv_timeout(Duration::from_millis(10)).is_ok();
+        let mut stream = unwrap!(InterfaceStream::start_with(provider, wait));
+
+        assert_eq!(next(&mut stream), Some(snapshots[0].clone()));
+        // The second enumeration finds nothing new, so the next snapshot is that of the third.
+        unwrap!(change_tx.send(()));
+        unwrap!(change_tx.send(()));
+        assert_eq!(next(&mut stream), Some(snapshots[2].clone()));
+        unwrap!(change_tx.send(()));
+        assert_eq!(next(&mut stream), Some(snapshots[3].clone()));
+    }
+
+    #[test]
+    fn current_interfaces_come_first() {
+        let mut stream = unwrap!(interface_stream());
+        assert_eq!(next(&mut stream), Some(unwrap!(get_if_addrs())));
+    }
+}
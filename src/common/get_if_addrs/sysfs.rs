@@ -0,0 +1,197 @@
+// Readers for the per-interface information Linux exposes under `/sys/class/net`. The root is
+// always passed in so tests can point these at a mock tree.
+
+use std::collections::HashSet;
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::Path;
+
+use common::get_if_addrs::Interface;
+
+/// Where the kernel exposes its network interfaces.
+pub const SYS_CLASS_NET: &'static str = "/sys/class/net";
+
+/// The name of the interface `name` is enslaved to (e.g. the bond or team it is a member of),
+/// if any.
+pub fn master(sys_class_net: &Path, name: &str) -> Option<String> {
+    // `master` is a symlink to the master's own directory, e.g. `../bond0`.
+    fs::read_link(sys_class_net.join(name).join("master"))
+        .ok()
+        .and_then(|target| target.file_name().map(|name| name.to_string_lossy().into_owned()))
+}
+
+/// The hardware address of the interface `name`, if it has a 6-byte one.
+pub fn mac_address(sys_class_net: &Path, name: &str) -> Option<[u8; 6]> {
+    let mut contents = String::new();
+    match File::open(sys_class_net.join(name).join("address")) {
+        Ok(mut file) => {
+            if file.read_to_string(&mut contents).is_err() {
+                return None;
+            }
+        }
+        Err(_) => return None,
+    }
+    // E.g. `52:54:00:12:34:56\n`.
+    let mut octets = contents.trim().split(':');
+    let mut mac = [0; 6];
+    for byte in &mut mac {
+        *byte = match octets.next().and_then(|octet| u8::from_str_radix(octet, 16).ok()) {
+            Some(byte) => byte,
+            None => return None,
+        };
+    }
+    if octets.next().is_some() {
+        return None;
+    }
+    Some(mac)
+}
+
+/// The MTU of the interface `name`, if known.
+pub fn mtu(sys_class_net: &Path, name: &str) -> Option<u32> {
+    let mut contents = String::new();
+    match File::open(sys_class_net.join(name).join("mtu")) {
+        Ok(mut file) => {
+            if file.read_to_string(&mut contents).is_err() {
+                return None;
+            }
+        }
+        Err(_) => return None,
+    }
+    contents.trim().parse().ok()
+}
+
+/// The first of `ifaces` on the interface with the hardware address `mac`, if any. Aliases such as
+/// `eth0:0` have the hardware address of the interface before the colon.
+pub fn find_by_mac(ifaces: Vec<Interface>,
+                   sys_class_net: &Path,
+                   mac: [u8; 6])
+                   -> Option<Interface> {
+    ifaces.into_iter().find(|interface| {
+        let name = interface.name.split(':').next().unwrap_or("");
+        mac_address(sys_class_net, name) == Some(mac)
+    })
+}
+
+/// Drop the interfaces whose master is also among `ifaces`.
+pub fn drop_enslaved(ifaces: Vec<Interface>, sys_class_net: &Path) -> Vec<Interface> {
+    let names: HashSet<String> = ifaces.iter().map(|interface| interface.name.clone()).collect();
+    ifaces.into_iter()
+        .filter(|interface| match master(sys_class_net, &interface.name) {
+            Some(ref master) => !names.contains(master),
+            None => true,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::fs;
+    use std::io::Write;
+    use std::net::{IpAddr, Ipv4Addr};
+    use std::os::unix::fs::symlink;
+    use std::path::PathBuf;
+
+    use common::get_if_addrs::InterfaceBuilder;
+    use common::get_if_addrs::test_util::temp_path;
+
+    // Lays out `<root>/<name>` for every interface and `<root>/<member>/master -> ../<master>` for
+    // every `(member, master)` pair.
+    fn mock_sys_class_net(names: &[&str], members: &[(&str, &str)]) -> PathBuf {
+        let root = temp_path("sysfs");
+        for name in names {
+            unwrap!(fs::create_dir_all(root.join(name)));
+        }
+        for &(member, master) in members {
+            unwrap!(symlink(format!("../{}", master), root.join(member).join("master")));
+        }
+        root
+    }
+
+    fn interface(name: &str, last_octet: u8) -> Interface {
+        InterfaceBuilder::new()
+            .name(name)
+            .addr(IpAddr::V4(Ipv4Addr::new(192, 168, 1, last_octet)))
+            .build()
+    }
+
+    #[test]
+    fn members_of_present_master_are_dropped() {
+        let root = mock_sys_class_net(&["bond0", "eth0", "eth1", "wlan0"],
+                                      &[("eth0", "bond0"), ("eth1", "bond0")]);
+        let ifaces = vec![interface("bond0", 2),
+                          interface("eth0", 2),
+                          interface("eth1", 2),
+                          interface("wlan0", 3)];
+
+        let names: Vec<String> = drop_enslaved(ifaces, &root)
+            .into_iter()
+            .map(|interface| interface.name)
+            .collect();
+        assert_eq!(names, vec!["bond0".to_string(), "wlan0".to_string()]);
+
+        unwrap!(fs::remove_dir_all(root));
+    }
+
+    #[test]
+    fn mac_addresses() {
+        let root = mock_sys_class_net(&["eth0", "tun0", "lo"], &[]);
+        let write_address = |name: &str, address: &str| {
+            let mut file = unwrap!(File::create(root.join(name).join("address")));
+            unwrap!(file.write_all(address.as_bytes()));
+        };
+        write_address("eth0", "52:54:00:12:34:5a\n");
+        write_address("tun0", "\n");
+
+        assert_eq!(mac_address(&root, "eth0"),
+                   Some([0x52, 0x54, 0x00, 0x12, 0x34, 0x5a]));
+        assert_eq!(mac_address(&root, "tun0"), None);
+        assert_eq!(mac_address(&root, "lo"), None);
+
+        unwrap!(fs::remove_dir_all(root));
+    }
+
+    #[test]
+    fn found_by_mac() {
+        let root = mock_sys_class_net(&["eth0", "eth1", "lo"], &[]);
+        let write_address = |name: &str, address: &str| {
+            let mut file = unwrap!(File::create(root.join(name).join("address")));
+            unwrap!(file.write_all(address.as_bytes()));
+        };
+        write_address("eth0", "52:54:00:12:34:5a\n");
+        write_address("eth1", "52:54:00:12:34:5b\n");
+        write_address("lo", "00:00:00:00:00:00\n");
+        let ifaces = vec![interface("lo", 1), interface("eth0:0", 2), interface("eth1", 3)];
+
+        assert_eq!(find_by_mac(ifaces.clone(), &root, [0x52, 0x54, 0x00, 0x12, 0x34, 0x5b]),
+                   Some(ifaces[2].clone()));
+        assert_eq!(find_by_mac(ifaces.clone(), &root, [0x52, 0x54, 0x00, 0x12, 0x34, 0x5a]),
+                   Some(ifaces[1].clone()));
+        assert_eq!(find_by_mac(ifaces, &root, [0x52, 0x54, 0x00, 0x12, 0x34, 0x5c]), None);
+
+        unwrap!(fs::remove_dir_all(root));
+    }
+
+    #[test]
+    fn members_of_absent_master_are_kept() {
+        let root = mock_sys_class_net(&["bond0", "eth0"], &[("eth0", "bond0")]);
+        let ifaces = vec![interface("eth0", 2)];
+
+        assert_eq!(drop_enslaved(ifaces.clone(), &root), ifaces);
+
+        unwrap!(fs::remove_dir_all(root));
+    }
+
+    #[test]
+    fn mtus() {
+        let root = mock_sys_class_net(&["eth0", "lo"], &[]);
+        let mut file = unwrap!(File::create(root.join("eth0").join("mtu")));
+        unwrap!(file.write_all(b"1500\n"));
+
+        assert_eq!(mtu(&root, "eth0"), Some(1500));
+        assert_eq!(mtu(&root, "lo"), None);
+
+        unwrap!(fs::remove_dir_all(root));
+    }
+}
@@ -0,0 +1,112 @@
+// Helpers shared by the tests of the modules of `get_if_addrs`.
+
+use std::env;
+use std::net::IpAddr;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use common::get_if_addrs::{Interface, InterfaceBuilder};
+use rand;
+
+#[cfg(feature = "async")]
+pub use self::executor::{Ready, block_on, poll_fn, ready};
+
+// The interface `name` with the address `ip` and the netmask `netmask`, every other field left at
+// what `InterfaceBuilder` defaults it to.
+pub fn interface(name: &str, ip: &str, netmask: &str) -> Interface {
+    InterfaceBuilder::new()
+        .name(name)
+        .addr(unwrap!(IpAddr::from_str(ip)))
+        .netmask(unwrap!(IpAddr::from_str(netmask)))
+        .build()
+}
+
+// A path under the temporary directory no other test uses, named after `name`. It's left to the
+// test to create and remove.
+pub fn temp_path(name: &str) -> PathBuf {
+    env::temp_dir().join(format!("crust-{}-{:016x}", name, rand::random::<u64>()))
+}
+
+#[cfg(feature = "async")]
+mod executor {
+    use std::future::Future;
+    use std::mem;
+    use std::pin::Pin;
+    use std::sync::Arc;
+    use std::task::{Context, Poll, RawWaker, RawWakerVTable, Waker};
+    use std::thread::{self, Thread};
+
+    // The simplest executor there is: poll on this thread, parking it in between.
+    #[allow(unsafe_code)]
+    pub fn block_on<F: Future>(future: F) -> F::Output {
+        let waker = unsafe { Waker::from_raw(unparker(Arc::new(thread::current()))) };
+        let mut cx = Context::from_waker(&waker);
+        let mut future = Box::pin(future);
+        loop {
+            match future.as_mut().poll(&mut cx) {
+                Poll::Ready(output) => return output,
+                Poll::Pending => thread::park(),
+            }
+        }
+    }
+
+    // A waker unparking `thread`, which it holds a reference to.
+    fn unparker(thread: Arc<Thread>) -> RawWaker {
+        RawWaker::new(Arc::into_raw(thread) as *const (), &UNPARKER)
+    }
+
+    static UNPARKER: RawWakerVTable = RawWakerVTable::new(clone, wake, wake_by_ref, drop);
+
+    #[allow(unsafe_code)]
+    unsafe fn clone(thread: *const ()) -> RawWaker {
+        let thread = Arc::from_raw(thread as *const Thread);
+        let clone = unparker(thread.clone());
+        mem::forget(thread);
+        clone
+    }
+
+    #[allow(unsafe_code)]
+    unsafe fn wake(thread: *const ()) {
+        Arc::from_raw(thread as *const Thread).unpark();
+    }
+
+    #[allow(unsafe_code)]
+    unsafe fn wake_by_ref(thread: *const ()) {
+        (*(thread as *const Thread)).unpark();
+    }
+
+    #[allow(unsafe_code)]
+    unsafe fn drop(thread: *const ()) {
+        mem::drop(Arc::from_raw(thread as *const Thread));
+    }
+
+    // Stand-ins for `std::future::ready()` and `std::future::poll_fn()`, which are newer than the
+    // toolchain this builds with.
+    pub struct Ready<T>(Option<T>);
+
+    pub fn ready<T>(value: T) -> Ready<T> {
+        Ready(Some(value))
+    }
+
+    impl<T: Unpin> Future for Ready<T> {
+        type Output = T;
+
+        fn poll(mut self: Pin<&mut Self>, _cx: &mut Context) -> Poll<T> {
+            Poll::Ready(unwrap!(self.0.take()))
+        }
+    }
+
+    pub struct PollFn<F>(F);
+
+    pub fn poll_fn<T, F: FnMut(&mut Context) -> Poll<T>>(f: F) -> PollFn<F> {
+        PollFn(f)
+    }
+
+    impl<T, F: FnMut(&mut Context) -> Poll<T> + Unpin> Future for PollFn<F> {
+        type Output = T;
+
+        fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<T> {
+            (self.0)(cx)
+        }
+    }
+}
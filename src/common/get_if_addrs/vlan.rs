@@ -0,0 +1,84 @@
+// The 802.1Q VLAN ids of interfaces, as Linux reports them under `/proc/net/vlan`. The directory
+// is always passed in so tests can point this at a mock one.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+/// Where the kernel lists its VLAN interfaces, all of them in `config` and then one file per
+/// interface.
+pub const PROC_NET_VLAN: &'static str = "/proc/net/vlan";
+
+/// The VLAN ids of the VLAN interfaces, by name. Empty if there are none, as there is no
+/// `/proc/net/vlan` at all until the 8021q module is loaded.
+///
+/// All of them are read from `config` at once, so that enumerations look up each address in the
+/// same map rather than opening a file per address.
+pub fn vlan_ids(proc_net_vlan: &Path) -> HashMap<String, u16> {
+    let mut contents = String::new();
+    match File::open(proc_net_vlan.join("config")) {
+        Ok(mut file) => {
+            if file.read_to_string(&mut contents).is_err() {
+                return HashMap::new();
+            }
+        }
+        Err(_) => return HashMap::new(),
+    }
+    parse_config(&contents)
+}
+
+// After two header lines, `config` has a line like `eth0.100       | 100  | eth0` per VLAN
+// interface, giving its name, its id and the interface it is on.
+fn parse_config(contents: &str) -> HashMap<String, u16> {
+    contents.lines()
+        .skip(2)
+        .filter_map(|line| {
+            let mut fields = line.split('|').map(str::trim);
+            match (fields.next(), fields.next().and_then(|vid| vid.parse().ok())) {
+                (Some(name), Some(vid)) if !name.is_empty() => Some((name.to_owned(), vid)),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::fs;
+    use std::io::Write;
+
+    use common::get_if_addrs::test_util::temp_path;
+
+    #[test]
+    fn vlan_ids_of_config() {
+        let root = temp_path("vlan");
+        assert_eq!(vlan_ids(&root), HashMap::new());
+
+        unwrap!(fs::create_dir_all(&root));
+        let mut file = unwrap!(File::create(root.join("config")));
+        unwrap!(file.write_all(b"VLAN Dev name\t | VLAN ID\n\
+                                 Name-Type: VLAN_NAME_TYPE_RAW_PLUS_VID_NO_PAD\n\
+                                 eth0.100       | 100  | eth0\n\
+                                 vlan7          | 7  | eth1\n"));
+        let ids = vlan_ids(&root);
+        assert_eq!(ids.len(), 2);
+        assert_eq!(ids.get("eth0.100"), Some(&100));
+        assert_eq!(ids.get("vlan7"), Some(&7));
+        assert_eq!(ids.get("eth0"), None);
+
+        unwrap!(fs::remove_dir_all(root));
+    }
+
+    #[test]
+    fn malformed_config() {
+        let header = "VLAN Dev name\t | VLAN ID\nName-Type: VLAN_NAME_TYPE\n";
+        assert_eq!(parse_config(""), HashMap::new());
+        assert_eq!(parse_config(header), HashMap::new());
+        for line in &["eth0.100", "eth0.100 | ", "eth0.100 | 70000 | eth0", " | 100 | eth0"] {
+            assert_eq!(parse_config(&format!("{}{}\n", header, line)), HashMap::new());
+        }
+    }
+}
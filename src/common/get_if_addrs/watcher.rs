@@ -0,0 +1,790 @@
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use std::io;
+use std::net::IpAddr;
+use std::sync::{Arc, Mutex, Weak};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::time::{Duration, Instant};
+
+use common::get_if_addrs::{self, Family, IfAddr, IfFlags, Interface, Routability,
+                           addr_eq_ignoring_scope};
+use maidsafe_utilities::thread::{self, RaiiThreadJoiner};
+
+/// A change to the interfaces on this machine.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum IfEvent {
+    /// An address has been assigned to an interface.
+    Added(Interface),
+    /// An address has been removed from an interface.
+    Removed(Interface),
+    /// An address is still assigned to the same interface but its details, e.g. its prefix
+    /// length, have changed.
+    Changed {
+        /// The address before the change.
+        old: Interface,
+        /// The address after the change.
+        new: Interface,
+    },
+    /// The flags of an interface have changed.
+    FlagsChanged {
+        /// The name of the interface.
+        name: String,
+        /// The flags before the change.
+        old: IfFlags,
+        /// The flags after the change.
+        new: IfFlags,
+    },
+}
+
+/// Work out the events that turn the snapshot `old` into the snapshot `new`.
+///
+/// Addresses are matched up by interface name and IP. Removals are reported first, then changes to
+/// the netmask or broadcast address of an address, then flag changes and finally additions, so a
+/// consumer rebinding on every event never has two sockets for the same address. Lifetimes aren't
+/// compared since they count down between any two snapshots.
+pub fn diff_events(old: &[Interface], new: &[Interface]) -> Vec<IfEvent> {
+    let mut old_by_key: HashMap<(&str, IpAddr), &Interface> = HashMap::new();
+    for interface in old {
+        let _ = old_by_key.entry(key(interface)).or_insert(interface);
+    }
+    let new_keys: HashSet<(&str, IpAddr)> = new.iter().map(key).collect();
+
+    let mut events: Vec<IfEvent> = old.iter()
+        .filter(|interface| !new_keys.contains(&key(interface)))
+        .map(|interface| IfEvent::Removed(interface.clone()))
+        .collect();
+
+    let mut compared = HashSet::new();
+    for interface in new {
+        if let Some(before) = old_by_key.get(&key(interface)) {
+            if compared.insert(key(interface)) && before.addr != interface.addr {
+                events.push(IfEvent::Changed {
+                    old: (*before).clone(),
+                    new: interface.clone(),
+                });
+            }
+        }
+    }
+
+    let old_flags = flags_by_name(old);
+    let mut reported = HashSet::new();
+    for interface in new {
+        let name = &interface.name[..];
+        if let Some(&before) = old_flags.get(name) {
+            if before != interface.flags && reported.insert(name) {
+                events.push(IfEvent::FlagsChanged {
+                    name: name.to_string(),
+                    old: before,
+                    new: interface.flags,
+                });
+            }
+        }
+    }
+
+    events.extend(new.iter()
+        .filter(|interface| !old_by_key.contains_key(&key(interface)))
+        .map(|interface| IfEvent::Added(interface.clone())));
+    events
+}
+
+quick_error! {
+    /// Why the interfaces on this machine don't match the expected ones.
+    #[derive(Debug)]
+    pub enum InterfacesDiff {
+        /// The interfaces couldn't be enumerated.
+        Enumeration(e: io::Error) {
+            description("Could not enumerate interfaces")
+            display("Could not enumerate interfaces: {}", e)
+            cause(e)
+            from()
+        }
+        /// The interfaces differ from the expected ones in these ways, as told by
+        /// `diff_events()` from the expected interfaces to the actual ones.
+        Differs(events: Vec<IfEvent>) {
+            description("Interfaces don't match the expected ones")
+            display("Interfaces don't match the expected ones: {:?}", events)
+        }
+    }
+}
+
+/// Check that the interfaces on this machine are the same as `expected`, e.g. as saved by an
+/// earlier run, and fail with the differences if they aren't.
+pub fn assert_interfaces_match(expected: &[Interface]) -> Result<(), InterfacesDiff> {
+    compare(expected, &try!(get_if_addrs::get_if_addrs()))
+}
+
+fn compare(expected: &[Interface], actual: &[Interface]) -> Result<(), InterfacesDiff> {
+    let events = diff_events(expected, actual);
+    if events.is_empty() {
+        Ok(())
+    } else {
+        Err(InterfacesDiff::Differs(events))
+    }
+}
+
+fn key(interface: &Interface) -> (&str, IpAddr) {
+    (&interface.name, interface.addr.ip())
+}
+
+// Flags are a property of the interface rather than of each of its addresses, so the first entry
+// seen for a name is taken as representative.
+fn flags_by_name(ifaces: &[Interface]) -> HashMap<&str, IfFlags> {
+    let mut flags = HashMap::new();
+    for interface in ifaces {
+        let _ = flags.entry(&interface.name[..]).or_insert(interface.flags);
+    }
+    flags
+}
+
+/// Block until `addr` is assigned to any interface on this machine if `present` is true, or until
+/// it is assigned to none of them if it is false. Returns whether that happened within `timeout`.
+///
+/// This watches the interfaces the same way `IfWatcher` does, so a change is noticed up to
+/// `WAIT_POLL_MS` ms after it happens.
+pub fn wait_for_addr(addr: IpAddr, present: bool, timeout: Duration) -> bool {
+    wait_for_addr_with(get_if_addrs::get_if_addrs,
+                       addr,
+                       present,
+                       timeout,
+                       Duration::from_millis(WAIT_POLL_MS))
+}
+
+/// How often `wait_for_addr()` enumerates the interfaces.
+pub const WAIT_POLL_MS: u64 = 100;
+
+fn wait_for_addr_with<F>(mut poll: F,
+                         addr: IpAddr,
+                         present: bool,
+                         timeout: Duration,
+                         interval: Duration)
+                         -> bool
+    where F: FnMut() -> io::Result<Vec<Interface>> + Send + 'static
+{
+    let deadline = Instant::now() + timeout;
+    let snapshot = match poll() {
+        Ok(snapshot) => snapshot,
+        Err(e) => {
+            warn!("Could not enumerate interfaces: {:?}", e);
+            return false;
+        }
+    };
+    let has_addr = |interface: &Interface| addr_eq_ignoring_scope(&interface.addr.ip(), &addr);
+    // The address may be assigned to several interfaces at once.
+    let mut count = snapshot.iter().filter(|interface| has_addr(interface)).count();
+    if (count > 0) == present {
+        return true;
+    }
+
+    // Start watching from the snapshot we already have so that no change slips through between
+    // taking it and the watcher taking its own.
+    let mut snapshot = Some(snapshot);
+    let (event_tx, event_rx) = mpsc::channel();
+    let _watcher = match IfWatcher::start_with(move || match snapshot.take() {
+                                                   Some(snapshot) => Ok(snapshot),
+                                                   None => poll(),
+                                               },
+                                               interval,
+                                               event_tx) {
+        Ok(watcher) => watcher,
+        Err(e) => {
+            warn!("Could not watch interfaces: {:?}", e);
+            return false;
+        }
+    };
+
+    loop {
+        let now = Instant::now();
+        if now >= deadline {
+            return false;
+        }
+        match event_rx.recv_timeout(deadline - now) {
+            Ok(IfEvent::Added(ref interface)) if has_addr(interface) => count += 1,
+            Ok(IfEvent::Removed(ref interface)) if has_addr(interface) => count -= 1,
+            Ok(_) => continue,
+            Err(_) => return false,
+        }
+        if (count > 0) == present {
+            return true;
+        }
+    }
+}
+
+/// Get a fingerprint of the network configuration of this machine, which changes whenever an
+/// address is added to or removed from an interface or its prefix changes.
+///
+/// Comparing fingerprints taken at different times is a cheap way to find out whether anything
+/// changed at all, without diffing full snapshots. The fingerprint is the same across processes.
+pub fn network_fingerprint() -> io::Result<u64> {
+    Ok(fingerprint(&try!(get_if_addrs::get_if_addrs())))
+}
+
+fn fingerprint(ifaces: &[Interface]) -> u64 {
+    let mut entries: Vec<(&str, IpAddr, Option<u8>)> = ifaces.iter()
+        .map(|interface| (&interface.name[..], interface.addr.ip(), interface.addr.prefix_len()))
+        .collect();
+    entries.sort();
+    entries.dedup();
+
+    let mut hasher = Fnv1a::default();
+    entries.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// The network configuration as last seen by `changed_since()`.
+///
+/// A new token hasn't seen any configuration yet, so the first `changed_since()` on it returns
+/// true.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct NetworkToken {
+    fingerprint: Option<u64>,
+}
+
+/// Check whether the network configuration of this machine has changed since `token` last saw it,
+/// as told by `network_fingerprint()`, and update `token` to the current one.
+///
+/// If the interfaces can't be enumerated this returns false and leaves `token` as it is.
+pub fn changed_since(token: &mut NetworkToken) -> bool {
+    changed_since_with(token, get_if_addrs::get_if_addrs())
+}
+
+fn changed_since_with(token: &mut NetworkToken, ifaces: io::Result<Vec<Interface>>) -> bool {
+    let current = match ifaces {
+        Ok(ifaces) => fingerprint(&ifaces),
+        Err(e) => {
+            warn!("Could not enumerate interfaces: {:?}", e);
+            return false;
+        }
+    };
+    let changed = token.fingerprint != Some(current);
+    token.fingerprint = Some(current);
+    changed
+}
+
+// 64-bit FNV-1a. Unlike `DefaultHasher` its output doesn't depend on random keys.
+pub struct Fnv1a(u64);
+
+impl Default for Fnv1a {
+    fn default() -> Fnv1a {
+        Fnv1a(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Hasher for Fnv1a {
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= u64::from(*byte);
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+/// Check whether the snapshots `old` and `new` are of the same network, i.e. whether the networks
+/// of the interfaces a default route goes through are the same in both, disregarding any other
+/// changes such as temporary IPv6 addresses rotating.
+///
+/// The default routes are those of `default_gateways()` at the time of the call. A network counts
+/// as default-routed if the gateway is on it or, for the link-local gateways IPv6 routes usually
+/// have, if it is on the interface of the route. Without default routes in either snapshot, all
+/// networks apart from link-local ones are compared.
+pub fn same_network(old: &[Interface], new: &[Interface]) -> bool {
+    same_network_via(old, new, &get_if_addrs::default_gateways())
+}
+
+fn same_network_via(old: &[Interface], new: &[Interface], gateways: &[(IpAddr, String)]) -> bool {
+    let (old_routed, new_routed) = (routed_networks(old, gateways), routed_networks(new, gateways));
+    if old_routed.is_empty() && new_routed.is_empty() {
+        networks(old, |_| true) == networks(new, |_| true)
+    } else {
+        old_routed == new_routed
+    }
+}
+
+fn routed_networks<'a>(ifaces: &'a [Interface],
+                       gateways: &[(IpAddr, String)])
+                       -> Vec<(&'a str, IpAddr, Option<u8>)> {
+    networks(ifaces, |interface| {
+        gateways.iter().any(|&(ref gateway, ref name)| {
+            *name == interface.name &&
+            (interface.addr.contains(gateway) ||
+             gateway.is_ipv6() && interface.addr.family() == Family::V6 &&
+             Routability::of(gateway) == Routability::LinkLocal)
+        })
+    })
+}
+
+// The sorted networks of the addresses among `ifaces` passing `filter`, leaving out link-local
+// ones, which are the same on any network.
+fn networks<'a, F>(ifaces: &'a [Interface], filter: F) -> Vec<(&'a str, IpAddr, Option<u8>)>
+    where F: Fn(&Interface) -> bool
+{
+    let mut networks: Vec<(&str, IpAddr, Option<u8>)> = ifaces.iter()
+        .filter(|interface| interface.addr.routability() != Routability::LinkLocal)
+        .filter(|interface| filter(interface))
+        .map(|interface| {
+            (&interface.name[..], interface.addr.network(), interface.addr.prefix_len())
+        })
+        .collect();
+    networks.sort();
+    networks.dedup();
+    networks
+}
+
+/// The interfaces on this machine as last seen by an `IfWatcher`.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Snapshot {
+    /// How many times the interfaces have changed since the watcher started. It is only
+    /// incremented when an enumeration differs from the previous one, so a consumer which has
+    /// already handled a generation can skip diffing it again.
+    pub generation: u64,
+    /// When this generation was first seen, i.e. when the watcher started for generation 0.
+    pub changed_at: Instant,
+    /// The interfaces.
+    pub interfaces: Vec<Interface>,
+}
+
+/// Get the addresses of `current` which aren't in `previous`, e.g. those a DHCP server newly
+/// leased, each with the time they were first seen, i.e. that of `current`.
+///
+/// Addresses are matched up by interface name and IP, as by `diff_events()`, so it takes a new IP
+/// rather than a new netmask to count as a new address. This is meant for when a list is all
+/// that's wanted; `diff_events()` tells about removals and changes as well.
+pub fn new_addresses(previous: &Snapshot, current: &Snapshot) -> Vec<(IfAddr, Instant)> {
+    let previous_keys: HashSet<(&str, IpAddr)> = previous.interfaces.iter().map(key).collect();
+    current.interfaces
+        .iter()
+        .filter(|interface| !previous_keys.contains(&key(interface)))
+        .map(|interface| (interface.addr.clone(), current.changed_at))
+        .collect()
+}
+
+/// Watches the interfaces on this machine by re-enumerating them periodically and reporting the
+/// differences between consecutive snapshots as `IfEvent`s.
+///
+/// The watcher stops when it is dropped or when the receiving end of its event channel goes away.
+pub struct IfWatcher {
+    snapshot: Arc<Mutex<Snapshot>>,
+    // Dropping this wakes the watcher thread up and tells it to stop. It must be dropped before
+    // the joiner.
+    _stop_tx: Sender<()>,
+    _raii_joiner: RaiiThreadJoiner,
+}
+
+impl IfWatcher {
+    /// Start watching, enumerating the interfaces every `interval` and sending the resulting events
+    /// to `event_tx`.
+    pub fn start(interval: Duration, event_tx: Sender<IfEvent>) -> io::Result<IfWatcher> {
+        IfWatcher::start_with(get_if_addrs::get_if_addrs, interval, event_tx)
+    }
+
+    fn start_with<F>(poll: F,
+                     interval: Duration,
+                     event_tx: Sender<IfEvent>)
+                     -> io::Result<IfWatcher>
+        where F: FnMut() -> io::Result<Vec<Interface>> + Send + 'static
+    {
+        IfWatcher::start_with_sink(poll, interval, move |event| event_tx.send(event).is_ok())
+    }
+
+    // Start watching, handing the events to `sink` until it returns false.
+    fn start_with_sink<F, S>(mut poll: F, interval: Duration, mut sink: S) -> io::Result<IfWatcher>
+        where F: FnMut() -> io::Result<Vec<Interface>> + Send + 'static,
+              S: FnMut(IfEvent) -> bool + Send + 'static
+    {
+        let mut previous = try!(poll());
+        let snapshot = Arc::new(Mutex::new(Snapshot {
+            generation: 0,
+            changed_at: Instant::now(),
+            interfaces: previous.clone(),
+        }));
+        let snapshot_clone = snapshot.clone();
+        let (stop_tx, stop_rx) = mpsc::channel();
+
+        let raii_joiner = thread::named("IfWatcher", move || {
+            while let Err(RecvTimeoutError::Timeout) = stop_rx.recv_timeout(interval) {
+                let current = match poll() {
+                    Ok(current) => current,
+                    Err(e) => {
+                        warn!("Could not enumerate interfaces: {:?}", e);
+                        continue;
+                    }
+                };
+                let events = diff_events(&previous, &current);
+                if events.is_empty() {
+                    continue;
+                }
+                // Update the snapshot first so that it is current by the time the events arrive.
+                {
+                    let mut snapshot = unwrap!(snapshot_clone.lock());
+                    snapshot.generation += 1;
+                    snapshot.changed_at = Instant::now();
+                    snapshot.interfaces = current.clone();
+                }
+                for event in events {
+                    if !sink(event) {
+                        return;
+                    }
+                }
+                previous = current;
+            }
+        });
+
+        Ok(IfWatcher {
+            snapshot: snapshot,
+            _stop_tx: stop_tx,
+            _raii_joiner: raii_joiner,
+        })
+    }
+
+    /// Get the interfaces as of the latest enumeration which changed them.
+    pub fn snapshot(&self) -> Snapshot {
+        unwrap!(self.snapshot.lock()).clone()
+    }
+
+    /// Get the generation of `snapshot()` without copying the interfaces.
+    pub fn snapshot_generation(&self) -> u64 {
+        unwrap!(self.snapshot.lock()).generation
+    }
+}
+
+/// How often the watcher behind `shared_watcher()` enumerates the interfaces.
+pub const SHARED_WATCHER_POLL_MS: u64 = 500;
+
+/// Subscribe to the watcher shared by everyone in this process, starting it if nobody else is
+/// subscribed yet.
+///
+/// Each handle, including each clone of one, receives every event on its own channel. The watcher
+/// stops once the last handle is dropped.
+pub fn shared_watcher() -> io::Result<WatcherHandle> {
+    subscribe(&SHARED_HUB,
+              get_if_addrs::get_if_addrs,
+              Duration::from_millis(SHARED_WATCHER_POLL_MS))
+}
+
+/// A subscription to the watcher returned by `shared_watcher()`.
+pub struct WatcherHandle {
+    hub: Arc<Hub>,
+    event_rx: Receiver<IfEvent>,
+}
+
+impl WatcherHandle {
+    /// The channel the events of this handle arrive on.
+    pub fn events(&self) -> &Receiver<IfEvent> {
+        &self.event_rx
+    }
+
+    /// Get the interfaces as last seen by the shared watcher, as by `IfWatcher::snapshot()`.
+    pub fn snapshot(&self) -> Snapshot {
+        self.hub.watcher.snapshot()
+    }
+
+    /// Get the generation of `snapshot()` without copying the interfaces.
+    pub fn snapshot_generation(&self) -> u64 {
+        self.hub.watcher.snapshot_generation()
+    }
+}
+
+impl Clone for WatcherHandle {
+    fn clone(&self) -> WatcherHandle {
+        Hub::handle(&self.hub)
+    }
+}
+
+lazy_static! {
+    static ref SHARED_HUB: Mutex<Weak<Hub>> = Mutex::new(Weak::new());
+}
+
+// The running shared watcher together with the channels of its handles, which hold the only
+// strong references to it.
+struct Hub {
+    subscribers: Arc<Mutex<Vec<Sender<IfEvent>>>>,
+    watcher: IfWatcher,
+}
+
+impl Hub {
+    fn handle(hub: &Arc<Hub>) -> WatcherHandle {
+        let (event_tx, event_rx) = mpsc::channel();
+        unwrap!(hub.subscribers.lock()).push(event_tx);
+        WatcherHandle {
+            hub: hub.clone(),
+            event_rx: event_rx,
+        }
+    }
+}
+
+fn subscribe<F>(registry: &Mutex<Weak<Hub>>,
+                poll: F,
+                interval: Duration)
+                -> io::Result<WatcherHandle>
+    where F: FnMut() -> io::Result<Vec<Interface>> + Send + 'static
+{
+    let mut registry = unwrap!(registry.lock());
+    if let Some(hub) = registry.upgrade() {
+        return Ok(Hub::handle(&hub));
+    }
+
+    let subscribers = Arc::new(Mutex::new(Vec::<Sender<IfEvent>>::new()));
+    let subscribers_clone = subscribers.clone();
+    let watcher = try!(IfWatcher::start_with_sink(poll, interval, move |event| {
+        // Handles which went away are dropped here. The watcher itself stops when the hub does.
+        unwrap!(subscribers_clone.lock()).retain(|event_tx| event_tx.send(event.clone()).is_ok());
+        true
+    }));
+    let hub = Arc::new(Hub {
+        subscribers: subscribers,
+        watcher: watcher,
+    });
+    *registry = Arc::downgrade(&hub);
+    Ok(Hub::handle(&hub))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::net::{IpAddr, Ipv4Addr};
+    use std::str::FromStr;
+
+    use common::get_if_addrs::{IfFlags, Interface, InterfaceBuilder};
+
+    fn interface(name: &str, last_octet: u8, flags: IfFlags) -> Interface {
+        InterfaceBuilder::new()
+            .name(name)
+            .addr(IpAddr::V4(Ipv4Addr::new(192, 168, 1, last_octet)))
+            .flags(flags)
+            .build()
+    }
+
+    #[test]
+    fn diff_of_two_snapshots() {
+        let up = IfFlags::UP | IfFlags::RUNNING;
+        let old = vec![interface("eth0", 2, up), interface("eth1", 3, up)];
+        let new = vec![interface("eth0", 4, up), interface("eth1", 3, IfFlags::UP)];
+
+        assert_eq!(diff_events(&old, &new),
+                   vec![IfEvent::Removed(old[0].clone()),
+                        IfEvent::FlagsChanged {
+                            name: "eth1".to_string(),
+                            old: up,
+                            new: IfFlags::UP,
+                        },
+                        IfEvent::Added(new[0].clone())]);
+    }
+
+    #[test]
+    fn fingerprint_follows_addresses() {
+        let snapshot = vec![interface("eth0", 2, IfFlags::UP), interface("eth1", 3, IfFlags::UP)];
+        let reordered = vec![snapshot[1].clone(), snapshot[0].clone()];
+        assert_eq!(fingerprint(&snapshot), fingerprint(&snapshot));
+        assert_eq!(fingerprint(&snapshot), fingerprint(&reordered));
+
+        let mut added = snapshot.clone();
+        added.push(interface("eth0", 4, IfFlags::UP));
+        assert!(fingerprint(&snapshot) != fingerprint(&added));
+    }
+
+    #[test]
+    fn token_notices_changes() {
+        let snapshot = vec![interface("eth0", 2, IfFlags::UP)];
+        let mut added = snapshot.clone();
+        added.push(interface("eth0", 4, IfFlags::UP));
+
+        let mut token = NetworkToken::default();
+        assert!(changed_since_with(&mut token, Ok(snapshot.clone())));
+        assert!(!changed_since_with(&mut token, Ok(snapshot.clone())));
+        assert!(changed_since_with(&mut token, Ok(added.clone())));
+        assert!(!changed_since_with(&mut token, Err(io::Error::new(io::ErrorKind::Other, "boom"))));
+        assert!(!changed_since_with(&mut token, Ok(added)));
+        assert!(changed_since_with(&mut token, Ok(snapshot)));
+
+        let mut token = NetworkToken::default();
+        assert!(changed_since(&mut token));
+    }
+
+    // Pretend the address `192.168.1.4` appears on `eth0` at the `appear_at`th enumeration and
+    // disappears again at the `disappear_at`th one.
+    fn flipping_provider(appear_at: usize,
+                         disappear_at: usize)
+                         -> impl FnMut() -> io::Result<Vec<Interface>> + Send + 'static {
+        let mut polls = 0;
+        move || {
+            polls += 1;
+            let mut ifaces = vec![interface("eth0", 2, IfFlags::UP)];
+            if polls >= appear_at && polls < disappear_at {
+                ifaces.push(interface("eth0", 4, IfFlags::UP));
+            }
+            Ok(ifaces)
+        }
+    }
+
+    #[test]
+    fn waits_for_addr_to_appear_and_disappear() {
+        let addr = IpAddr::V4(Ipv4Addr::new(192, 168, 1, 4));
+        let interval = Duration::from_millis(10);
+        let timeout = Duration::from_secs(5);
+
+        assert!(wait_for_addr_with(flipping_provider(3, 1000), addr, true, timeout, interval));
+        assert!(wait_for_addr_with(flipping_provider(1, 4), addr, false, timeout, interval));
+        assert!(wait_for_addr_with(flipping_provider(1, 1000), addr, true, timeout, interval));
+        assert!(!wait_for_addr_with(flipping_provider(1000, 1000),
+                                    addr,
+                                    true,
+                                    Duration::from_millis(100),
+                                    interval));
+    }
+
+    #[test]
+    fn diff_reports_prefix_change() {
+        let with_netmask = |netmask| {
+            InterfaceBuilder::new()
+                .name("eth0")
+                .addr(IpAddr::V4(Ipv4Addr::new(192, 168, 1, 5)))
+                .netmask(IpAddr::V4(netmask))
+                .build()
+        };
+        let old = vec![with_netmask(Ipv4Addr::new(255, 255, 255, 0))];
+        let new = vec![with_netmask(Ipv4Addr::new(255, 255, 255, 128))];
+        assert_eq!(old[0].addr.prefix_len(), Some(24));
+        assert_eq!(new[0].addr.prefix_len(), Some(25));
+
+        assert_eq!(diff_events(&old, &new),
+                   vec![IfEvent::Changed {
+                            old: old[0].clone(),
+                            new: new[0].clone(),
+                        }]);
+    }
+
+    #[test]
+    fn same_network_across_snapshots() {
+        let with_netmask = |name: &str, ip: &str, netmask: &str| {
+            InterfaceBuilder::new()
+                .name(name)
+                .addr(unwrap!(IpAddr::from_str(ip)))
+                .netmask(unwrap!(IpAddr::from_str(netmask)))
+                .build()
+        };
+        let v6_netmask = "ffff:ffff:ffff:ffff::";
+        // As read from `/proc/net/route` and `/proc/net/ipv6_route` at home.
+        let home_gateways = vec![(unwrap!(IpAddr::from_str("192.168.1.1")), "wlan0".to_string()),
+                                 (unwrap!(IpAddr::from_str("fe80::1")), "wlan0".to_string())];
+        let home = vec![with_netmask("lo", "127.0.0.1", "255.0.0.0"),
+                        with_netmask("wlan0", "192.168.1.5", "255.255.255.0"),
+                        with_netmask("wlan0", "2001:db8:1::5", v6_netmask),
+                        with_netmask("wlan0", "2001:db8:1::b0a7", v6_netmask),
+                        with_netmask("wlan0", "fe80::5", v6_netmask),
+                        with_netmask("docker0", "172.17.0.1", "255.255.0.0")];
+        // A temporary address rotated and a container bridge went away.
+        let rotated = vec![home[0].clone(),
+                           home[1].clone(),
+                           home[2].clone(),
+                           with_netmask("wlan0", "2001:db8:1::c0de", v6_netmask),
+                           home[4].clone()];
+        assert!(same_network_via(&home, &rotated, &home_gateways));
+
+        // Moved to the office, with another gateway and other prefixes.
+        let office_gateways = vec![(unwrap!(IpAddr::from_str("10.0.0.1")), "wlan0".to_string())];
+        let office = vec![home[0].clone(),
+                          with_netmask("wlan0", "10.0.3.7", "255.255.0.0"),
+                          home[4].clone()];
+        assert!(!same_network_via(&home, &office, &office_gateways));
+        assert!(!same_network_via(&rotated, &office, &home_gateways));
+
+        // Without default routes everything but link-local addresses counts.
+        assert!(same_network_via(&home[..5], &rotated, &[]));
+        assert!(!same_network_via(&home, &rotated, &[]));
+    }
+
+    #[test]
+    fn new_addresses_between_snapshots() {
+        let up = IfFlags::UP;
+        let before = Snapshot {
+            generation: 3,
+            changed_at: Instant::now(),
+            interfaces: vec![interface("eth0", 2, up), interface("eth1", 3, up)],
+        };
+        let renewed = InterfaceBuilder::new()
+            .name("eth1")
+            .addr(IpAddr::V4(Ipv4Addr::new(192, 168, 1, 3)))
+            .netmask(IpAddr::V4(Ipv4Addr::new(255, 255, 255, 0)))
+            .flags(up)
+            .build();
+        let after = Snapshot {
+            generation: 4,
+            changed_at: before.changed_at + Duration::from_secs(60),
+            interfaces: vec![interface("eth0", 4, up), renewed, interface("wlan0", 2, up)],
+        };
+
+        assert_eq!(new_addresses(&before, &after),
+                   vec![(after.interfaces[0].addr.clone(), after.changed_at),
+                        (after.interfaces[2].addr.clone(), after.changed_at)]);
+        assert!(new_addresses(&after, &after).is_empty());
+    }
+
+    #[test]
+    fn generation_counts_changes_only() {
+        let (event_tx, event_rx) = mpsc::channel();
+        let interval = Duration::from_millis(10);
+        let timeout = Duration::from_secs(5);
+        let watcher = unwrap!(IfWatcher::start_with(flipping_provider(3, 6), interval, event_tx));
+        let original = vec![interface("eth0", 2, IfFlags::UP)];
+        let first = watcher.snapshot();
+        assert_eq!(first.generation, 0);
+        assert_eq!(first.interfaces, original);
+
+        let added = interface("eth0", 4, IfFlags::UP);
+        assert_eq!(unwrap!(event_rx.recv_timeout(timeout)), IfEvent::Added(added.clone()));
+        let second = watcher.snapshot();
+        assert_eq!(second.generation, 1);
+        assert_eq!(second.interfaces, vec![original[0].clone(), added.clone()]);
+        assert!(second.changed_at > first.changed_at);
+
+        assert_eq!(unwrap!(event_rx.recv_timeout(timeout)), IfEvent::Removed(added));
+        // Many more polls, all seeing the same interfaces.
+        ::std::thread::sleep(interval * 10);
+        assert_eq!(watcher.snapshot_generation(), 2);
+        assert_eq!(watcher.snapshot().interfaces, original);
+    }
+
+    #[test]
+    fn handles_share_one_watcher() {
+        let registry = Mutex::new(Weak::new());
+        let interval = Duration::from_millis(10);
+        let timeout = Duration::from_secs(5);
+
+        let first = unwrap!(subscribe(&registry, flipping_provider(3, 1000), interval));
+        let second = unwrap!(subscribe(&registry, flipping_provider(1, 1000), interval));
+        let third = second.clone();
+        assert!(Arc::ptr_eq(&first.hub, &second.hub));
+
+        let added = IfEvent::Added(interface("eth0", 4, IfFlags::UP));
+        for handle in &[first, second, third] {
+            assert_eq!(unwrap!(handle.events().recv_timeout(timeout)), added);
+            assert_eq!(handle.snapshot_generation(), 1);
+        }
+        assert!(unwrap!(registry.lock()).upgrade().is_none());
+    }
+
+    #[test]
+    fn comparison_with_expected_interfaces() {
+        let expected = vec![interface("eth0", 2, IfFlags::UP), interface("eth1", 3, IfFlags::UP)];
+        assert!(compare(&expected, &expected).is_ok());
+
+        match compare(&expected, &expected[..1]) {
+            Err(InterfacesDiff::Differs(events)) => {
+                assert_eq!(events, vec![IfEvent::Removed(expected[1].clone())])
+            }
+            result => panic!("Unexpected {:?}", result),
+        }
+
+        assert!(assert_interfaces_match(&unwrap!(get_if_addrs::get_if_addrs())).is_ok());
+    }
+
+    #[test]
+    fn diff_of_identical_snapshots_is_empty() {
+        let snapshot = vec![interface("eth0", 2, IfFlags::UP)];
+        assert!(diff_events(&snapshot, &snapshot).is_empty());
+    }
+}
@@ -0,0 +1,706 @@
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::{io, mem, ptr, slice};
+use std::ffi::CStr;
+
+use c_linked_list::CLinkedListConst;
+use common::get_if_addrs::{AddrFlags, AddrOrigin, IfAddr, IfFlags, Ifv4Addr, Ifv6Addr,
+                           Interface, InterfaceBuilder, Limit, Routability};
+use common::get_if_addrs::sockaddr::{ipv4_from_in_addr, ipv6_from_in6_addr};
+use libc::{c_char, c_int, c_ulong, c_void, size_t};
+use libc;
+use winapi::{AF_INET, AF_INET6, DWORD, ERROR_SUCCESS, sockaddr_in6};
+use winapi::SOCKADDR as sockaddr;
+use winapi::SOCKADDR_IN as sockaddr_in;
+
+#[repr(C)]
+struct SocketAddress {
+    pub lp_socket_address: *const sockaddr,
+    pub i_socket_address_length: c_int,
+}
+// `IP_ADAPTER_UNICAST_ADDRESS_LH`. Before Vista the structure ended right after `address`, which
+// `length` tells apart.
+#[repr(C)]
+struct IpAdapterUnicastAddress {
+    pub length: c_ulong,
+    pub flags: DWORD,
+    pub next: *const IpAdapterUnicastAddress,
+    pub address: SocketAddress,
+    prefix_origin: c_int,
+    suffix_origin: c_int,
+    dad_state: c_int,
+    valid_lifetime: c_ulong,
+    preferred_lifetime: c_ulong,
+    lease_lifetime: c_ulong,
+    pub on_link_prefix_length: u8,
+}
+// `IP_ADAPTER_ANYCAST_ADDRESS_XP`.
+#[repr(C)]
+struct IpAdapterAnycastAddress {
+    pub length: c_ulong,
+    pub flags: DWORD,
+    pub next: *const IpAdapterAnycastAddress,
+    pub address: SocketAddress,
+}
+// `IP_ADAPTER_ADDRESSES_LH`. Before Vista the structure ended right after `first_prefix`, which
+// `length` tells apart, so nothing past it is read.
+#[repr(C)]
+struct IpAdapterAddresses {
+    pub length: c_ulong,
+    pub if_index: DWORD,
+    pub next: *const IpAdapterAddresses,
+    pub adapter_name: *const c_char,
+    pub first_unicast_address: *const IpAdapterUnicastAddress,
+    pub first_anycast_address: *const IpAdapterAnycastAddress,
+    first_multicast_address: *const c_void,
+    first_dns_server_address: *const c_void,
+    pub dns_suffix: *const u16,
+    pub description: *const u16,
+    friendly_name: *const u16,
+    physical_address: [c_char; 8],
+    physical_address_length: DWORD,
+    flags: DWORD,
+    mtu: DWORD,
+    if_type: DWORD,
+    oper_status: c_int,
+    ipv6_if_index: DWORD,
+    zone_indices: [DWORD; 16],
+    first_prefix: *const c_void,
+    transmit_link_speed: u64,
+    receive_link_speed: u64,
+    first_wins_server_address: *const c_void,
+    first_gateway_address: *const c_void,
+    ipv4_metric: c_ulong,
+    ipv6_metric: c_ulong,
+    luid: u64,
+    dhcpv4_server: SocketAddress,
+    compartment_id: u32,
+    network_guid: [u8; 16],
+    connection_type: c_int,
+    tunnel_type: c_int,
+    dhcpv6_server: SocketAddress,
+    dhcpv6_client_duid: [u8; 130],
+    dhcpv6_client_duid_length: c_ulong,
+    dhcpv6_iaid: c_ulong,
+    first_dns_suffix: *const c_void,
+}
+
+#[link(name="Iphlpapi")]
+extern "system" {
+    /// get adapter's addresses
+    fn GetAdaptersAddresses(family: c_ulong,
+                            flags: c_ulong,
+                            reserved: *const c_void,
+                            addresses: *const IpAdapterAddresses,
+                            size: *mut c_ulong)
+                            -> c_ulong;
+}
+
+const ERROR_BUFFER_OVERFLOW: c_ulong = 111;
+const ERROR_NO_DATA: c_ulong = 232;
+
+// GAA_FLAG_SKIP_MULTICAST | GAA_FLAG_SKIP_DNS_SERVER | GAA_FLAG_INCLUDE_PREFIX |
+// GAA_FLAG_SKIP_FRIENDLY_NAME, but not GAA_FLAG_SKIP_ANYCAST, which would leave
+// `first_anycast_address` null.
+const GAA_FLAGS: c_ulong = 0x3c;
+
+// `IfOperStatusUp`
+const IF_OPER_STATUS_UP: c_int = 1;
+const IF_TYPE_PPP: DWORD = 23;
+const IF_TYPE_SOFTWARE_LOOPBACK: DWORD = 24;
+const IF_TYPE_TUNNEL: DWORD = 131;
+const IP_ADAPTER_NO_MULTICAST: DWORD = 0x10;
+
+// The values of `NL_PREFIX_ORIGIN` and `NL_SUFFIX_ORIGIN` which both share.
+const ORIGIN_MANUAL: c_int = 1;
+const ORIGIN_WELL_KNOWN: c_int = 2;
+const ORIGIN_DHCP: c_int = 3;
+
+// Windows doesn't distinguish between an interface being up and running, so both are derived
+// from the operational status.
+fn convert_flags(adapter: &IpAdapterAddresses) -> IfFlags {
+    let mut flags = IfFlags::empty();
+    if adapter.oper_status == IF_OPER_STATUS_UP {
+        flags.insert(IfFlags::UP | IfFlags::RUNNING);
+    }
+    match adapter.if_type {
+        IF_TYPE_SOFTWARE_LOOPBACK => flags.insert(IfFlags::LOOPBACK),
+        IF_TYPE_PPP | IF_TYPE_TUNNEL => flags.insert(IfFlags::POINT_TO_POINT),
+        _ => (),
+    }
+    if adapter.flags & IP_ADAPTER_NO_MULTICAST == 0 {
+        flags.insert(IfFlags::MULTICAST);
+    }
+    flags
+}
+
+// Reads no further than the `len` bytes Windows says the address takes, leaving out an address
+// shorter than that of its family rather than making up its missing bytes.
+#[allow(unsafe_code)]
+pub fn sockaddr_to_ipaddr(sockaddr: *const sockaddr, len: c_int) -> Option<IpAddr> {
+    if sockaddr.is_null() || len < FAMILY_END {
+        return None;
+    }
+    let family = unsafe { read_bounded::<sockaddr>(sockaddr, len) }.sa_family as u32;
+    if family == AF_INET as u32 && len >= V4_ADDR_END {
+        let sa = unsafe { read_bounded::<sockaddr_in>(sockaddr, len) };
+        Some(IpAddr::V4(ipv4_from_in_addr(sa.sin_addr.S_un)))
+    } else if family == AF_INET6 as u32 && len >= V6_ADDR_END {
+        let sa = unsafe { read_bounded::<sockaddr_in6>(sockaddr, len) };
+        Some(IpAddr::V6(ipv6_from_in6_addr(sa.sin6_addr.s6_addr)))
+    } else {
+        None
+    }
+}
+
+// Where the family field of a `SOCKADDR` ends, i.e. how long one has to be to tell its family.
+const FAMILY_END: c_int = 2;
+// Where the address of a `SOCKADDR_IN` and of a `sockaddr_in6` ends: both start with the family
+// and the port, and a `sockaddr_in6` has its flow info before its address.
+const V4_ADDR_END: c_int = 2 + 2 + 4;
+const V6_ADDR_END: c_int = 2 + 2 + 4 + 16;
+
+// Copy the `T` at `sockaddr`, taking any bytes past the first `len` to be zero.
+#[allow(unsafe_code, trivial_casts)]
+unsafe fn read_bounded<T: Copy>(sockaddr: *const sockaddr, len: c_int) -> T {
+    let len = (len as usize).min(mem::size_of::<T>());
+    let mut value: T = mem::zeroed();
+    ptr::copy_nonoverlapping(sockaddr as *const u8, &mut value as *mut T as *mut u8, len);
+    value
+}
+
+// trivial_numeric_casts lint may become allow by default.
+// Refer: https://github.com/rust-lang/rfcs/issues/1020
+/// Return a vector of IP details for all the valid interfaces on this host
+pub fn get_if_addrs() -> io::Result<Vec<Interface>> {
+    collect(false, Limit::none())
+}
+
+/// Return a vector of IP details for the valid interfaces on this host, stopping at `limit`
+pub fn get_if_addrs_limited(limit: Limit) -> io::Result<Vec<Interface>> {
+    collect(false, limit)
+}
+
+/// Return a vector of only the names and IPs of all the valid interfaces on this host
+pub fn get_if_addrs_minimal() -> io::Result<Vec<Interface>> {
+    collect(true, Limit::none())
+}
+
+/// Hand the IP details of the valid interfaces on this host to `visit` one at a time, stopping as
+/// soon as it returns false
+pub fn for_each_interface(visit: &mut dyn FnMut(Interface) -> bool) -> io::Result<()> {
+    enumerate(&mut get_adapters_addresses, false, visit)
+}
+
+fn collect(minimal: bool, limit: Limit) -> io::Result<Vec<Interface>> {
+    collect_with(&mut get_adapters_addresses, minimal, limit)
+}
+
+// The same as `collect()` with the adapters `get` fills in, as `GetAdaptersAddresses` does.
+fn collect_with(get: &mut dyn FnMut(*mut IpAdapterAddresses, *mut c_ulong) -> c_ulong,
+                minimal: bool,
+                limit: Limit)
+                -> io::Result<Vec<Interface>> {
+    let mut ret = Vec::new();
+    try!(enumerate(get, minimal, &mut |interface| limit.push(&mut ret, interface)));
+    Ok(ret)
+}
+
+// `GetAdaptersAddresses` of both families, given the buffer to fill in and its size, which is
+// set to the size needed if it is too small.
+#[allow(unsafe_code)]
+fn get_adapters_addresses(addresses: *mut IpAdapterAddresses, size: *mut c_ulong) -> c_ulong {
+    unsafe { GetAdaptersAddresses(0, GAA_FLAGS, ptr::null(), addresses, size) }
+}
+
+// The buffer `GetAdaptersAddresses` fills in, freed on drop.
+struct AdaptersBuffer {
+    ptr: *mut IpAdapterAddresses,
+    // How many times the buffer had to be allocated before it was large enough.
+    allocations: usize,
+}
+
+impl AdaptersBuffer {
+    // Ask `get`, which is `get_adapters_addresses()` but in tests, for the size of the buffer
+    // needed first and then for the adapters in a buffer of that size. Adapters can appear in
+    // between, so keep growing the buffer as long as it's too small.
+    #[allow(unsafe_code)]
+    fn fetch(get: &mut dyn FnMut(*mut IpAdapterAddresses, *mut c_ulong) -> c_ulong)
+             -> io::Result<AdaptersBuffer> {
+        let mut buffersize: c_ulong = 0;
+        match get(ptr::null_mut(), &mut buffersize) {
+            ERROR_BUFFER_OVERFLOW => (),
+            ERROR_NO_DATA | ERROR_SUCCESS => {
+                return Ok(AdaptersBuffer {
+                    ptr: ptr::null_mut(),
+                    allocations: 0,
+                })
+            }
+            retcode => return Err(io::Error::from_raw_os_error(retcode as i32)),
+        }
+
+        let mut buffer = AdaptersBuffer {
+            ptr: ptr::null_mut(),
+            allocations: 0,
+        };
+        loop {
+            unsafe {
+                libc::free(buffer.ptr as *mut c_void);
+                buffer.ptr = libc::malloc(buffersize as size_t) as *mut IpAdapterAddresses;
+            }
+            if buffer.ptr.is_null() {
+                panic!("Failed to allocate buffer in get_if_addrs()");
+            }
+            buffer.allocations += 1;
+            match get(buffer.ptr, &mut buffersize) {
+                ERROR_SUCCESS => return Ok(buffer),
+                ERROR_NO_DATA => {
+                    unsafe { libc::free(buffer.ptr as *mut c_void) };
+                    buffer.ptr = ptr::null_mut();
+                    return Ok(buffer);
+                }
+                ERROR_BUFFER_OVERFLOW => continue,
+                retcode => return Err(io::Error::from_raw_os_error(retcode as i32)),
+            }
+        }
+    }
+}
+
+impl Drop for AdaptersBuffer {
+    #[allow(unsafe_code)]
+    fn drop(&mut self) {
+        unsafe { libc::free(self.ptr as *mut c_void) };
+    }
+}
+
+fn enumerate(get: &mut dyn FnMut(*mut IpAdapterAddresses, *mut c_ulong) -> c_ulong,
+             minimal: bool,
+             visit: &mut dyn FnMut(Interface) -> bool)
+             -> io::Result<()> {
+    let buffer = try!(AdaptersBuffer::fetch(get));
+    walk(buffer.ptr, minimal, visit);
+    Ok(())
+}
+
+// Hand `visit` the addresses of the list of adapters `ifaddrs`, none if it is null as it is when
+// the host has no adapters at all. Windows lists the anycast addresses of an adapter apart from
+// its unicast ones, so they are handed over after those, flagged as anycast.
+#[allow(unsafe_code)]
+fn walk(ifaddrs: *const IpAdapterAddresses,
+        minimal: bool,
+        visit: &mut dyn FnMut(Interface) -> bool) {
+    'adapters: for ifaddr in unsafe { CLinkedListConst::from_ptr(ifaddrs, |a| a.next) }.iter() {
+        let name =
+            unsafe { CStr::from_ptr(ifaddr.adapter_name) }.to_string_lossy().into_owned();
+        for addr in unsafe {
+                CLinkedListConst::from_ptr(ifaddr.first_unicast_address, |a| a.next)
+            }
+            .iter() {
+            let ip = match sockaddr_to_ipaddr(addr.address.lp_socket_address,
+                                          addr.address.i_socket_address_length) {
+                Some(ip) => ip,
+                None => continue,
+            };
+            let interface = if minimal {
+                InterfaceBuilder::new().name(&name).addr(ip).build()
+            } else {
+                let prefix_len =
+                    if addr.length as usize >= mem::size_of::<IpAdapterUnicastAddress>() {
+                        Some(addr.on_link_prefix_length)
+                    } else {
+                        None
+                    };
+                let origin = convert_origin(addr.prefix_origin, addr.suffix_origin, &ip);
+                adapter_interface(ifaddr, &name, ip, prefix_len, AddrFlags::empty(), origin)
+            };
+            if !visit(interface) {
+                break 'adapters;
+            }
+        }
+        for addr in unsafe {
+                CLinkedListConst::from_ptr(ifaddr.first_anycast_address, |a| a.next)
+            }
+            .iter() {
+            let ip = match sockaddr_to_ipaddr(addr.address.lp_socket_address,
+                                          addr.address.i_socket_address_length) {
+                Some(ip) => ip,
+                None => continue,
+            };
+            let interface = if minimal {
+                InterfaceBuilder::new().name(&name).addr(ip).build()
+            } else {
+                // Windows reports no prefix for anycast addresses.
+                adapter_interface(ifaddr, &name, ip, None, AddrFlags::ANYCAST, AddrOrigin::Other)
+            };
+            if !visit(interface) {
+                break 'adapters;
+            }
+        }
+    }
+}
+
+// The interface address `ip` of the adapter `ifaddr` named `name`, with the netmask of
+// `prefix_len` or none if that isn't known.
+#[allow(unsafe_code, trivial_numeric_casts)]
+fn adapter_interface(ifaddr: &IpAdapterAddresses,
+                     name: &str,
+                     ip: IpAddr,
+                     prefix_len: Option<u8>,
+                     mut addr_flags: AddrFlags,
+                     origin: AddrOrigin)
+                     -> Interface {
+    let mut flags = convert_flags(ifaddr);
+    if prefix_len.is_none() {
+        addr_flags.insert(AddrFlags::NO_NETMASK);
+    }
+
+    let addr = match ip {
+        IpAddr::V4(ipv4_addr) => {
+            // Point-to-point links and loopback have no broadcast address.
+            let broadcastable = !flags.contains(IfFlags::POINT_TO_POINT) &&
+                                !flags.contains(IfFlags::LOOPBACK);
+            let (netmask, broadcast) = match prefix_len {
+                Some(prefix_len) if broadcastable => {
+                    let netmask = v4_netmask(prefix_len);
+                    let broadcast = u32::from(ipv4_addr) | !u32::from(netmask);
+                    (netmask, Some(Ipv4Addr::from(broadcast)))
+                }
+                Some(prefix_len) => (v4_netmask(prefix_len), None),
+                None => (Ipv4Addr::new(0, 0, 0, 0), None),
+            };
+            if broadcast.is_some() {
+                flags.insert(IfFlags::BROADCAST);
+            }
+            IfAddr::V4(Ifv4Addr {
+                ip: ipv4_addr,
+                netmask: netmask,
+                broadcast: broadcast,
+            })
+        }
+        IpAddr::V6(ipv6_addr) => {
+            IfAddr::V6(Ifv6Addr {
+                ip: ipv6_addr,
+                netmask: prefix_len.map_or(Ipv6Addr::new(0, 0, 0, 0, 0, 0, 0, 0), v6_netmask),
+                broadcast: None,
+            })
+        }
+    };
+    let index = match addr {
+        IfAddr::V4(_) => ifaddr.if_index,
+        IfAddr::V6(_) => ifaddr.ipv6_if_index,
+    };
+    let metric = if has_metrics(ifaddr) {
+        match addr {
+            IfAddr::V4(_) => Some(ifaddr.ipv4_metric as u32),
+            IfAddr::V6(_) => Some(ifaddr.ipv6_metric as u32),
+        }
+    } else {
+        None
+    };
+    Interface {
+        name: name.to_string(),
+        addr: addr,
+        index: index,
+        flags: flags,
+        preferred_lft: None,
+        valid_lft: None,
+        addr_flags: addr_flags,
+        dns_suffix: unsafe { wide_string(ifaddr.dns_suffix) },
+        description: unsafe { wide_string(ifaddr.description) },
+        vlan_id: None,
+        peer: None,
+        origin: origin,
+        metric: metric,
+    }
+}
+
+// Whether `ifaddr` is long enough to hold the metrics, which came in with Vista.
+#[allow(trivial_casts)]
+fn has_metrics(ifaddr: &IpAdapterAddresses) -> bool {
+    let metrics_end = &ifaddr.luid as *const u64 as usize - ifaddr as *const _ as usize;
+    ifaddr.length as usize >= metrics_end
+}
+
+// The origin of the address `ip` from the origins Windows reports of its prefix and of the rest of
+// it, its suffix. Self-assigned link-local addresses come with a well-known prefix, and addresses
+// of stateless autoconfiguration with a prefix from a router advertisement.
+fn convert_origin(prefix_origin: c_int, suffix_origin: c_int, ip: &IpAddr) -> AddrOrigin {
+    if prefix_origin == ORIGIN_DHCP || suffix_origin == ORIGIN_DHCP {
+        AddrOrigin::Dhcp
+    } else if Routability::of(ip) == Routability::LinkLocal {
+        AddrOrigin::LinkLocal
+    } else if prefix_origin == ORIGIN_MANUAL {
+        AddrOrigin::Manual
+    } else if prefix_origin == ORIGIN_WELL_KNOWN {
+        AddrOrigin::WellKnown
+    } else {
+        AddrOrigin::Other
+    }
+}
+
+// The contents of the NUL-terminated UTF-16 string at `ptr`, if it isn't null.
+#[allow(unsafe_code)]
+unsafe fn wide_string(ptr: *const u16) -> Option<String> {
+    if ptr.is_null() {
+        return None;
+    }
+    let mut len = 0;
+    while *ptr.offset(len) != 0 {
+        len += 1;
+    }
+    Some(String::from_utf16_lossy(slice::from_raw_parts(ptr, len as usize)))
+}
+
+// The netmasks with the `prefix_len` leading bits set.
+fn v4_netmask(prefix_len: u8) -> Ipv4Addr {
+    let prefix_len = prefix_len.min(32);
+    Ipv4Addr::from(u32::max_value().checked_shl(32 - u32::from(prefix_len)).unwrap_or(0))
+}
+
+fn v6_netmask(prefix_len: u8) -> Ipv6Addr {
+    let prefix_len = prefix_len.min(128);
+    Ipv6Addr::from(u128::max_value().checked_shl(128 - u32::from(prefix_len)).unwrap_or(0))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // The offset of `field` into `value`.
+    #[allow(trivial_casts)]
+    fn offset<T, F>(value: &T, field: &F) -> usize {
+        field as *const F as usize - value as *const T as usize
+    }
+
+    // The sizes and offsets of the Windows SDK for 64-bit targets, so that a mistake in the layouts
+    // above fails the tests rather than misreading the adapters.
+    #[cfg(target_pointer_width = "64")]
+    #[test]
+    #[allow(unsafe_code)]
+    fn layouts_match_the_windows_sdk() {
+        assert_eq!(mem::size_of::<SocketAddress>(), 16);
+
+        let unicast: IpAdapterUnicastAddress = unsafe { mem::zeroed() };
+        assert_eq!(mem::size_of::<IpAdapterUnicastAddress>(), 64);
+        assert_eq!(offset(&unicast, &unicast.address), 16);
+        assert_eq!(offset(&unicast, &unicast.on_link_prefix_length), 56);
+
+        let adapter: IpAdapterAddresses = unsafe { mem::zeroed() };
+        assert_eq!(mem::size_of::<IpAdapterAddresses>(), 448);
+        assert_eq!(offset(&adapter, &adapter.first_unicast_address), 24);
+        assert_eq!(offset(&adapter, &adapter.dns_suffix), 56);
+        assert_eq!(offset(&adapter, &adapter.friendly_name), 72);
+        assert_eq!(offset(&adapter, &adapter.flags), 92);
+        assert_eq!(offset(&adapter, &adapter.mtu), 96);
+        assert_eq!(offset(&adapter, &adapter.if_type), 100);
+        assert_eq!(offset(&adapter, &adapter.oper_status), 104);
+        assert_eq!(offset(&adapter, &adapter.ipv6_if_index), 108);
+        assert_eq!(offset(&adapter, &adapter.first_prefix), 176);
+        assert_eq!(offset(&adapter, &adapter.luid), 224);
+        assert_eq!(offset(&adapter, &adapter.dhcpv6_client_duid), 296);
+        assert_eq!(offset(&adapter, &adapter.first_dns_suffix), 440);
+    }
+
+    #[test]
+    fn adapters_fetched_with_single_allocation() {
+        let buffer = unwrap!(AdaptersBuffer::fetch(&mut get_adapters_addresses));
+        assert!(!buffer.ptr.is_null());
+        assert_eq!(buffer.allocations, 1);
+    }
+
+    #[test]
+    #[allow(unsafe_code)]
+    fn no_adapters_is_no_interfaces() {
+        // What `GetAdaptersAddresses` returns on a host without any adapters, whether asked for
+        // the size of the buffer needed or for the adapters.
+        let mut sizes_asked = 0;
+        let mut no_data = |addresses: *mut IpAdapterAddresses, _: *mut c_ulong| {
+            assert!(addresses.is_null());
+            sizes_asked += 1;
+            ERROR_NO_DATA
+        };
+        assert_eq!(unwrap!(collect_with(&mut no_data, false, Limit::none())), vec![]);
+        assert_eq!(unwrap!(collect_with(&mut no_data, true, Limit::none())), vec![]);
+        assert_eq!(sizes_asked, 2);
+
+        // Adapters gone in between asking for the size and for the adapters.
+        let mut gone = |addresses: *mut IpAdapterAddresses, size: *mut c_ulong| {
+            if addresses.is_null() {
+                unsafe { *size = mem::size_of::<IpAdapterAddresses>() as c_ulong };
+                ERROR_BUFFER_OVERFLOW
+            } else {
+                ERROR_NO_DATA
+            }
+        };
+        let buffer = unwrap!(AdaptersBuffer::fetch(&mut gone));
+        assert!(buffer.ptr.is_null());
+        assert_eq!(buffer.allocations, 1);
+        assert_eq!(unwrap!(collect_with(&mut gone, false, Limit::none())), vec![]);
+
+        // What `AdaptersBuffer::fetch()` gets then.
+        let mut ifaces = Vec::new();
+        walk(ptr::null(), false, &mut |interface| {
+            ifaces.push(interface);
+            true
+        });
+        assert_eq!(ifaces, vec![]);
+    }
+
+    // A `SOCKADDR_IN` holding `ip`.
+    #[allow(unsafe_code)]
+    fn sockaddr_v4(ip: Ipv4Addr) -> sockaddr_in {
+        let mut sa: sockaddr_in = unsafe { mem::zeroed() };
+        sa.sin_family = AF_INET as u16;
+        sa.sin_addr.S_un = u32::from_ne_bytes(ip.octets());
+        sa
+    }
+
+    #[allow(trivial_casts)]
+    fn socket_address(sa: &sockaddr_in) -> SocketAddress {
+        SocketAddress {
+            lp_socket_address: sa as *const sockaddr_in as *const sockaddr,
+            i_socket_address_length: mem::size_of::<sockaddr_in>() as c_int,
+        }
+    }
+
+    #[allow(unsafe_code)]
+    fn unicast_address(sa: &sockaddr_in,
+                       prefix_len: u8,
+                       next: *const IpAdapterUnicastAddress)
+                       -> IpAdapterUnicastAddress {
+        let mut addr: IpAdapterUnicastAddress = unsafe { mem::zeroed() };
+        addr.length = mem::size_of::<IpAdapterUnicastAddress>() as c_ulong;
+        addr.next = next;
+        addr.address = socket_address(sa);
+        addr.on_link_prefix_length = prefix_len;
+        addr
+    }
+
+    // An adapter named `{adapter}` of type `if_type` with the addresses listed from `unicast` and
+    // `anycast`.
+    #[allow(unsafe_code, trivial_casts)]
+    fn adapter(if_type: DWORD,
+               unicast: *const IpAdapterUnicastAddress,
+               anycast: *const IpAdapterAnycastAddress)
+               -> IpAdapterAddresses {
+        let mut adapter: IpAdapterAddresses = unsafe { mem::zeroed() };
+        adapter.length = mem::size_of::<IpAdapterAddresses>() as c_ulong;
+        adapter.adapter_name = b"{adapter}\0".as_ptr() as *const c_char;
+        adapter.first_unicast_address = unicast;
+        adapter.first_anycast_address = anycast;
+        adapter.if_type = if_type;
+        adapter.oper_status = IF_OPER_STATUS_UP;
+        adapter
+    }
+
+    fn walked(adapter: &IpAdapterAddresses, minimal: bool) -> Vec<Interface> {
+        let mut ifaces = Vec::new();
+        walk(adapter, minimal, &mut |interface| {
+            ifaces.push(interface);
+            true
+        });
+        ifaces
+    }
+
+    #[test]
+    #[allow(trivial_casts)]
+    fn truncated_sockaddrs() {
+        let sa = sockaddr_v4(Ipv4Addr::new(192, 168, 1, 5));
+        let ptr = &sa as *const sockaddr_in as *const sockaddr;
+        assert_eq!(sockaddr_to_ipaddr(ptr, mem::size_of::<sockaddr_in>() as c_int),
+                   Some(IpAddr::V4(Ipv4Addr::new(192, 168, 1, 5))));
+        assert_eq!(sockaddr_to_ipaddr(ptr, 8),
+                   Some(IpAddr::V4(Ipv4Addr::new(192, 168, 1, 5))));
+        assert_eq!(sockaddr_to_ipaddr(ptr, 7), None);
+        assert_eq!(sockaddr_to_ipaddr(ptr, 5), None);
+        assert_eq!(sockaddr_to_ipaddr(ptr, 1), None);
+    }
+
+    #[test]
+    #[allow(unsafe_code)]
+    fn anycast_addresses_are_listed_flagged() {
+        let unicast_ip = sockaddr_v4(Ipv4Addr::new(192, 168, 1, 6));
+        let anycast_ip = sockaddr_v4(Ipv4Addr::new(192, 168, 1, 5));
+        let unicast = unicast_address(&unicast_ip, 24, ptr::null());
+        let mut anycast: IpAdapterAnycastAddress = unsafe { mem::zeroed() };
+        anycast.length = mem::size_of::<IpAdapterAnycastAddress>() as c_ulong;
+        anycast.address = socket_address(&anycast_ip);
+        // `IF_TYPE_ETHERNET_CSMACD`.
+        let adapter = adapter(6, &unicast, &anycast);
+
+        let ifaces = walked(&adapter, false);
+        assert_eq!(ifaces.len(), 2);
+        assert_eq!(ifaces[0].addr.ip(), IpAddr::V4(Ipv4Addr::new(192, 168, 1, 6)));
+        assert_eq!(ifaces[0].addr_flags, AddrFlags::empty());
+        assert_eq!(ifaces[1].addr.ip(), IpAddr::V4(Ipv4Addr::new(192, 168, 1, 5)));
+        assert_eq!(ifaces[1].addr_flags, AddrFlags::ANYCAST | AddrFlags::NO_NETMASK);
+        assert_eq!(ifaces[1].netmask(), None);
+        assert_eq!(ifaces[1].name, ifaces[0].name);
+        // Only the names and IPs are read then.
+        let minimal = walked(&adapter, true);
+        assert_eq!(minimal.len(), 2);
+        assert!(minimal.iter().all(|interface| interface.addr_flags.is_empty()));
+    }
+
+    #[test]
+    fn only_broadcast_links_have_broadcast_addresses() {
+        let ip = sockaddr_v4(Ipv4Addr::new(10, 8, 0, 2));
+        let addr = unicast_address(&ip, 24, ptr::null());
+        let broadcast = |if_type| {
+            let ifaces = walked(&adapter(if_type, &addr, ptr::null()), false);
+            assert_eq!(ifaces[0].addr.prefix_len(), Some(24));
+            let broadcast = match ifaces[0].addr {
+                IfAddr::V4(ref ifv4_addr) => ifv4_addr.broadcast,
+                IfAddr::V6(_) => panic!("Expected an IPv4 address"),
+            };
+            assert_eq!(broadcast.is_some(), ifaces[0].flags.contains(IfFlags::BROADCAST));
+            broadcast
+        };
+        // `IF_TYPE_ETHERNET_CSMACD`.
+        assert_eq!(broadcast(6), Some(Ipv4Addr::new(10, 8, 0, 255)));
+        assert_eq!(broadcast(IF_TYPE_PPP), None);
+        assert_eq!(broadcast(IF_TYPE_TUNNEL), None);
+        assert_eq!(broadcast(IF_TYPE_SOFTWARE_LOOPBACK), None);
+    }
+
+    #[test]
+    fn origins_are_mapped() {
+        use std::str::FromStr;
+
+        let ip = |ip| unwrap!(IpAddr::from_str(ip));
+        assert_eq!(convert_origin(ORIGIN_DHCP, ORIGIN_DHCP, &ip("192.168.1.5")),
+                   AddrOrigin::Dhcp);
+        assert_eq!(convert_origin(ORIGIN_MANUAL, ORIGIN_MANUAL, &ip("192.168.1.5")),
+                   AddrOrigin::Manual);
+        assert_eq!(convert_origin(ORIGIN_WELL_KNOWN, 4, &ip("fe80::5")), AddrOrigin::LinkLocal);
+        assert_eq!(convert_origin(ORIGIN_WELL_KNOWN, ORIGIN_WELL_KNOWN, &ip("::1")),
+                   AddrOrigin::WellKnown);
+        assert_eq!(convert_origin(4, 5, &ip("2001:db8::5")), AddrOrigin::Other);
+
+        let ifaces = unwrap!(get_if_addrs());
+        assert!(ifaces.iter().any(|interface| interface.origin != AddrOrigin::Other));
+        assert!(ifaces.iter()
+            .filter(|interface| interface.is_loopback())
+            .all(|interface| interface.origin == AddrOrigin::WellKnown));
+    }
+
+    #[test]
+    fn metrics_are_mapped() {
+        let ifaces = unwrap!(get_if_addrs());
+        assert!(ifaces.iter().all(|interface| interface.metric.is_some()));
+    }
+
+    #[test]
+    fn dns_suffixes_are_mapped() {
+        let ifaces = unwrap!(get_if_addrs());
+        assert!(ifaces.iter().any(|interface| interface.dns_suffix.is_some()));
+    }
+
+    #[test]
+    fn descriptions_are_mapped() {
+        let ifaces = unwrap!(get_if_addrs());
+        assert!(ifaces.iter().all(|interface| interface.description.is_some()));
+        assert!(ifaces.iter().any(|interface| {
+            interface.description.as_ref().map_or(false, |description| !description.is_empty())
+        }));
+    }
+}
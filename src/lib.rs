@@ -56,12 +56,18 @@ extern crate byteorder;
 extern crate c_linked_list;
 extern crate config_file_handler;
 extern crate crossbeam;
+#[cfg(feature = "async")]
+extern crate futures_core;
 extern crate igd;
+#[macro_use]
+extern crate lazy_static;
 extern crate libc;
 extern crate net2;
 extern crate rand;
 extern crate rustc_serialize;
 extern crate rust_sodium;
+#[cfg(feature = "socket2")]
+extern crate socket2;
 extern crate tmp_mio as mio;
 #[allow(unused_extern_crates)]
 #[macro_use]
@@ -79,7 +85,7 @@ mod common;
 mod service_discovery;
 mod nat;
 
-pub use common::{MSG_DROP_PRIORITY, Priority};
+pub use common::{MSG_DROP_PRIORITY, Priority, get_if_addrs};
 pub use main::{ConnectionInfoResult, CrustError, Event, PeerId, PrivConnectionInfo,
                PubConnectionInfo, Service};
 
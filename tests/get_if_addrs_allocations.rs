@@ -0,0 +1,97 @@
+// Copyright 2016 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement, version 1.0.  This, along with the
+// Licenses can be found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+//! Checks that `for_each_interface_ref()` doesn't allocate. This needs a counting global
+//! allocator, which gets a test binary of its own so it doesn't count for the other tests.
+
+// For explanation of lint checks, run `rustc -W help` or see
+// https://github.com/maidsafe/QA/blob/master/Documentation/Rust%20Lint%20Checks.md
+#![forbid(bad_style, exceeding_bitshifts, mutable_transmutes, no_mangle_const_items,
+          unknown_crate_types, warnings)]
+#![deny(deprecated, drop_with_repr_extern, improper_ctypes, missing_docs,
+        non_shorthand_field_patterns, overflowing_literals, plugin_as_library,
+        private_no_mangle_fns, private_no_mangle_statics, stable_features, unconditional_recursion,
+        unknown_lints, unsafe_code, unused, unused_allocation, unused_attributes,
+        unused_comparisons, unused_features, unused_parens, while_true)]
+#![warn(trivial_casts, trivial_numeric_casts, unused_extern_crates, unused_import_braces,
+        unused_qualifications, unused_results)]
+#![allow(box_pointers, fat_ptr_transmutes, missing_copy_implementations,
+         missing_debug_implementations, variant_size_differences)]
+
+// Names are copied all the same on Windows.
+#![cfg(not(windows))]
+
+extern crate crust;
+#[macro_use]
+extern crate unwrap;
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+use crust::get_if_addrs::{for_each_interface, for_each_interface_ref, get_if_addrs};
+
+// Counts the allocations of each thread, so that the test can tell whether a call allocated
+// whatever the test harness does on its other threads.
+struct CountingAllocator;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = Cell::new(0);
+}
+
+#[allow(unsafe_code)]
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|allocations| allocations.set(allocations.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+fn allocations() -> usize {
+    ALLOCATIONS.with(Cell::get)
+}
+
+#[test]
+fn borrowed_names_are_not_allocated() {
+    let ifaces = unwrap!(get_if_addrs());
+    let first = &ifaces[0].name[..];
+    let expected = ifaces.iter().filter(|interface| interface.name == first).count();
+
+    let mut visited = 0;
+    let mut matching = 0;
+    let before = allocations();
+    unwrap!(for_each_interface_ref(|interface| {
+        visited += 1;
+        if interface.name == first {
+            matching += 1;
+        }
+        true
+    }));
+    assert_eq!(allocations(), before);
+    assert_eq!(visited, ifaces.len());
+    assert_eq!(matching, expected);
+
+    let before = allocations();
+    unwrap!(for_each_interface(|_| true));
+    assert!(allocations() - before >= ifaces.len());
+}